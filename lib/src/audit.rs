@@ -0,0 +1,345 @@
+//! Change history of models annotated with `#[redis(audit_stream = "...")]`.
+//!
+//! Every `save` and `delete` of such models XADDs an [`AuditRecord`] to the audit stream,
+//! in the same transaction as the change itself. The stream is consumed with [`AuditLog`].
+//!
+//! The record key is WATCHed while old values are read, the transaction being retried when the
+//! record changes before it runs. Bulk operations (e.g. `delete_all`) aren't audited.
+use crate::migration::SCHEMA_VERSION;
+use crate::{Namespace, RedisModel, StreamModel};
+use redis::{
+    Cmd, ErrorKind, FromRedisValue, Pipeline, RedisError, RedisResult, RedisWrite, ToRedisArgs,
+    Value,
+};
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Operation recorded in an [`AuditRecord`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditOp {
+    /// Record was saved, created or replaced.
+    Save,
+    /// Record was deleted, with changes holding the deleted values.
+    Delete,
+}
+
+impl AuditOp {
+    fn as_str(&self) -> &'static str {
+        match self {
+            AuditOp::Save => "save",
+            AuditOp::Delete => "delete",
+        }
+    }
+}
+
+/// Change of a single field, `None` for missing values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditChange {
+    /// Stored field name
+    pub field: String,
+    /// Value before the operation
+    pub old: Option<String>,
+    /// Value after the operation
+    pub new: Option<String>,
+}
+
+/// Entry of an audit stream.
+///
+/// Stored as "model", "pk", "op", "timestamp" fields, followed by "old.{field}" and
+/// "new.{field}" fields of every change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditRecord {
+    /// Prefix key of the changed model
+    pub model: String,
+    /// Primary key of the changed record
+    pub pk: String,
+    /// Operation
+    pub op: AuditOp,
+    /// Changed fields
+    pub changes: Vec<AuditChange>,
+    /// Unix time in milliseconds of the operation
+    pub timestamp: u64,
+}
+
+impl AuditRecord {
+    /// Create record of `op` on `pk`, diffing `old` fields against `new` ones.
+    pub(crate) fn diff<M: RedisModel>(
+        pk: &str,
+        op: AuditOp,
+        mut old: BTreeMap<String, String>,
+        mut new: BTreeMap<String, String>,
+    ) -> Self {
+        old.remove(SCHEMA_VERSION);
+        new.remove(SCHEMA_VERSION);
+
+        let mut changes = new
+            .into_iter()
+            .filter_map(|(field, new)| match old.remove(&field) {
+                Some(old) if old == new => None,
+                old => Some(AuditChange {
+                    field,
+                    old,
+                    new: Some(new),
+                }),
+            })
+            .collect::<Vec<_>>();
+
+        changes.extend(old.into_iter().map(|(field, old)| AuditChange {
+            field,
+            old: Some(old),
+            new: None,
+        }));
+        changes.sort_by(|a, b| a.field.cmp(&b.field));
+
+        Self {
+            model: M::_prefix_key().to_owned(),
+            pk: pk.to_owned(),
+            op,
+            changes,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl ToRedisArgs for AuditRecord {
+    fn write_redis_args<W: ?Sized + RedisWrite>(&self, out: &mut W) {
+        for (field, value) in [
+            ("model", self.model.as_str()),
+            ("pk", self.pk.as_str()),
+            ("op", self.op.as_str()),
+        ] {
+            out.write_arg(field.as_bytes());
+            out.write_arg(value.as_bytes());
+        }
+
+        out.write_arg(b"timestamp");
+        out.write_arg_fmt(self.timestamp);
+
+        for change in &self.changes {
+            for (side, value) in [("old", &change.old), ("new", &change.new)] {
+                if let Some(value) = value {
+                    out.write_arg(format!("{side}.{}", change.field).as_bytes());
+                    out.write_arg(value.as_bytes());
+                }
+            }
+        }
+    }
+}
+
+impl FromRedisValue for AuditRecord {
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        let fields = BTreeMap::<String, String>::from_redis_value(v)?;
+        let get = |field: &str| {
+            fields.get(field).cloned().ok_or_else(|| {
+                RedisError::from((
+                    ErrorKind::TypeError,
+                    "invalid audit record",
+                    format!("missing {field:?}"),
+                ))
+            })
+        };
+
+        let op = match get("op")?.as_str() {
+            "save" => AuditOp::Save,
+            "delete" => AuditOp::Delete,
+            op => {
+                return Err(RedisError::from((
+                    ErrorKind::TypeError,
+                    "invalid audit record",
+                    format!("unknown op {op:?}"),
+                )))
+            }
+        };
+
+        let mut changes = BTreeMap::<&str, AuditChange>::new();
+        for (key, value) in &fields {
+            let Some((side, field)) = key.split_once('.') else {
+                continue;
+            };
+
+            let change = changes.entry(field).or_insert_with(|| AuditChange {
+                field: field.to_owned(),
+                old: None,
+                new: None,
+            });
+
+            match side {
+                "old" => change.old = Some(value.clone()),
+                "new" => change.new = Some(value.clone()),
+                _ => continue,
+            }
+        }
+
+        Ok(Self {
+            model: get("model")?,
+            pk: get("pk")?,
+            op,
+            changes: changes.into_values().collect(),
+            timestamp: get("timestamp")?.parse().map_err(|_| {
+                RedisError::from((ErrorKind::TypeError, "invalid audit record timestamp"))
+            })?,
+        })
+    }
+}
+
+/// XADD `record` of record at `key` to audit stream of `M`, `None` if `M` isn't audited.
+pub(crate) fn xadd<M: RedisModel>(
+    ns: &Namespace,
+    key: &str,
+    record: &AuditRecord,
+) -> RedisResult<Option<Cmd>> {
+    let Some(stream) = M::_audit_stream() else {
+        return Ok(None);
+    };
+
+    let stream = ns.model_prefix(stream);
+    ns.ensure_same_slot(&[key, &stream])?;
+
+    let mut cmd = redis::cmd("XADD");
+    cmd.arg(stream).arg("*").arg(record);
+
+    Ok(Some(cmd))
+}
+
+/// Run atomic `pipe` changing record at `key` along the audit XADD built by `audit` from the
+/// record read with `get`, WATCHing `key` in between and retrying when it changed.
+#[cfg(not(feature = "aio"))]
+pub(crate) fn watch<C, T>(
+    con: &mut C,
+    key: &str,
+    get: Cmd,
+    pipe: Pipeline,
+    audit: impl Fn(T) -> RedisResult<Option<Cmd>>,
+) -> RedisResult<()>
+where
+    C: redis::ConnectionLike,
+    T: FromRedisValue,
+{
+    loop {
+        redis::cmd("WATCH").arg(key).query::<()>(con)?;
+
+        let res = get.query(con).and_then(&audit).and_then(|cmd| {
+            let mut pipe = pipe.clone();
+            if let Some(cmd) = cmd {
+                pipe.add_command(cmd).ignore();
+            }
+            pipe.query::<Option<()>>(con)
+        });
+
+        match res {
+            Ok(Some(())) => return Ok(()),
+            // EXEC aborted, the record changed since it was read
+            Ok(None) => continue,
+            Err(err) => {
+                let _: RedisResult<()> = redis::cmd("UNWATCH").query(con);
+                return Err(err);
+            }
+        }
+    }
+}
+
+/// Run atomic `pipe` changing record at `key` along the audit XADD built by `audit` from the
+/// record read with `get`, WATCHing `key` in between and retrying when it changed.
+#[cfg(feature = "aio")]
+pub(crate) async fn watch<C, T>(
+    con: &mut C,
+    key: &str,
+    get: Cmd,
+    pipe: Pipeline,
+    audit: impl Fn(T) -> RedisResult<Option<Cmd>> + Send,
+) -> RedisResult<()>
+where
+    C: redis::aio::ConnectionLike + Send,
+    T: FromRedisValue,
+{
+    loop {
+        redis::cmd("WATCH")
+            .arg(key)
+            .query_async::<_, ()>(con)
+            .await?;
+
+        let res = match get.query_async(con).await.and_then(&audit) {
+            Ok(cmd) => {
+                let mut pipe = pipe.clone();
+                if let Some(cmd) = cmd {
+                    pipe.add_command(cmd).ignore();
+                }
+                pipe.query_async::<_, Option<()>>(con).await
+            }
+            Err(err) => Err(err),
+        };
+
+        match res {
+            Ok(Some(())) => return Ok(()),
+            // EXEC aborted, the record changed since it was read
+            Ok(None) => continue,
+            Err(err) => {
+                let _: RedisResult<()> = redis::cmd("UNWATCH").query_async(con).await;
+                return Err(err);
+            }
+        }
+    }
+}
+
+/// [`StreamModel`] consuming the audit stream of `M`.
+///
+/// # Example
+///
+/// ```ignore
+/// let auditors = AuditLog::<Customer>::new("auditors");
+/// auditors.ensure_group_stream(&mut conn)?;
+///
+/// for message in auditors.read(None, None, &mut conn)? {
+///     let record: &AuditRecord = message.data()?;
+/// }
+/// ```
+pub struct AuditLog<M> {
+    group_name: String,
+    consumer_name: String,
+    model: PhantomData<fn() -> M>,
+}
+
+impl<M> AuditLog<M> {
+    /// Create new AuditLog, with consumer_name being auto generated
+    pub fn new(group_name: impl AsRef<str>) -> Self {
+        Self::new_with_consumer_name(group_name, rusty_ulid::generate_ulid_string())
+    }
+
+    /// Create new AuditLog, with custom consumer_name
+    pub fn new_with_consumer_name(
+        group_name: impl AsRef<str>,
+        consumer_name: impl AsRef<str>,
+    ) -> Self {
+        Self {
+            group_name: group_name.as_ref().to_owned(),
+            consumer_name: consumer_name.as_ref().to_owned(),
+            model: PhantomData,
+        }
+    }
+}
+
+impl<M> Clone for AuditLog<M> {
+    fn clone(&self) -> Self {
+        Self::new_with_consumer_name(&self.group_name, &self.consumer_name)
+    }
+}
+
+impl<M: RedisModel> StreamModel for AuditLog<M> {
+    type Data = AuditRecord;
+
+    fn stream_key() -> &'static str {
+        M::_audit_stream().unwrap_or("audit")
+    }
+
+    fn group_name(&self) -> &str {
+        &self.group_name
+    }
+
+    fn consumer_name(&self) -> &str {
+        &self.consumer_name
+    }
+}
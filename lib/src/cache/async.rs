@@ -0,0 +1,141 @@
+use super::{cmds, transformers, CachedModel, Lookup, RETRY_INTERVAL};
+use crate::{RedisModel, ToPk, WithNamespace};
+use futures::Future;
+use redis::{RedisError, RedisResult};
+use std::time::{Duration, Instant};
+
+impl<M: RedisModel + Clone> CachedModel<M> {
+    /// Get cached record of `pk`, loading and caching it for `ttl` on miss.
+    ///
+    /// Concurrent callers missing the same key wait for the first one to load it, for at
+    /// most the lock timeout before loading it themselves. Misses of `loader` are cached too.
+    pub async fn get_or_load<C, F, Fut, E>(
+        &self,
+        pk: impl ToPk,
+        loader: F,
+        ttl: Duration,
+        conn: &mut C,
+    ) -> Result<Option<M>, E>
+    where
+        C: WithNamespace + Send,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Option<M>, E>>,
+        E: From<RedisError>,
+    {
+        let ns = conn.namespace().clone();
+        let key = M::_fmt_key(&ns, &pk.to_pk());
+
+        if let Some(value) = self.local_get(&key) {
+            return Ok(value);
+        }
+
+        let token = rusty_ulid::generate_ulid_string();
+        let deadline = Instant::now() + self.lock_timeout;
+
+        loop {
+            if let Some(value) = self.lookup(&key, ttl, conn).await? {
+                return Ok(value);
+            }
+
+            let locked: bool = cmds::lock(&ns, &key, &token, self.lock_timeout)
+                .query_async(conn.connection())
+                .await?;
+
+            if locked {
+                // The previous lock holder may have filled it before the lock was acquired
+                let value = match self.lookup(&key, ttl, conn).await {
+                    Ok(Some(value)) => Ok(value),
+                    Ok(None) => self.load(&key, loader, ttl, conn).await,
+                    Err(err) => Err(err.into()),
+                };
+                cmds::unlock(&ns, &key, &token)
+                    .query_async::<_, ()>(conn.connection())
+                    .await?;
+                return value;
+            }
+
+            if Instant::now() >= deadline {
+                break;
+            }
+
+            futures_timer::Delay::new(RETRY_INTERVAL).await;
+        }
+
+        // Lock holder took too long, load without it
+        self.load(&key, loader, ttl, conn).await
+    }
+
+    /// Enable invalidation of the in-process cache, sending invalidation messages of keys
+    /// of `M` to "__redis__:invalidate" of the connection with given `client_id`.
+    ///
+    /// RESP3 push invalidations aren't supported by the redis client, so invalidations are
+    /// redirected to a RESP2 pub/sub connection whose messages are passed to `invalidate`.
+    pub async fn enable_tracking<C>(&self, client_id: i64, conn: &mut C) -> RedisResult<()>
+    where
+        C: WithNamespace + Send,
+    {
+        cmds::tracking::<M>(conn.namespace(), client_id)
+            .query_async(conn.connection())
+            .await
+    }
+
+    /// Cached record at `key`, `None` when it's unknown.
+    async fn lookup<C>(
+        &self,
+        key: &str,
+        ttl: Duration,
+        conn: &mut C,
+    ) -> RedisResult<Option<Option<M>>>
+    where
+        C: WithNamespace + Send,
+    {
+        let ns = conn.namespace().clone();
+        let reply = cmds::lookup(&self.spec, &ns, key)?
+            .query_async(conn.connection())
+            .await?;
+
+        Ok(match transformers::lookup(&self.spec, reply)? {
+            Lookup::Hit(model) => {
+                self.local_put(&ns, key, Some(model.clone()), ttl);
+                Some(Some(model))
+            }
+            Lookup::Miss => {
+                let ttl = self.negative_ttl.unwrap_or_default();
+                self.local_put(&ns, key, None, ttl);
+                Some(None)
+            }
+            Lookup::Unknown => None,
+        })
+    }
+
+    async fn load<C, F, Fut, E>(
+        &self,
+        key: &str,
+        loader: F,
+        ttl: Duration,
+        conn: &mut C,
+    ) -> Result<Option<M>, E>
+    where
+        C: WithNamespace + Send,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Option<M>, E>>,
+        E: From<RedisError>,
+    {
+        let value = loader().await?;
+        let ns = conn.namespace().clone();
+
+        if let Some(pipe) =
+            cmds::fill(&self.spec, &ns, key, value.as_ref(), ttl, self.negative_ttl)?
+        {
+            pipe.query_async::<_, ()>(conn.connection()).await?;
+        }
+
+        let local_ttl = match value {
+            Some(_) => ttl,
+            None => self.negative_ttl.unwrap_or_default(),
+        };
+        self.local_put(&ns, key, value.clone(), local_ttl);
+
+        Ok(value)
+    }
+}
@@ -0,0 +1,326 @@
+#[cfg(feature = "aio")]
+mod r#async;
+#[cfg(not(feature = "aio"))]
+mod sync;
+
+use crate::{Namespace, RedisModel};
+use redis::{Cmd, Msg, Pipeline, RedisResult, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Default time a loader may hold the lock of a key before others load it themselves.
+const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Default time misses are cached for.
+const DEFAULT_NEGATIVE_TTL: Duration = Duration::from_secs(30);
+
+/// Channel invalidation messages of `CLIENT TRACKING ... REDIRECT` are published to.
+const INVALIDATE_CHANNEL: &str = "__redis__:invalidate";
+
+/// Key suffix of markers caching misses
+const MISS: &str = "__miss";
+/// Key suffix of locks held while loading
+const LOCK: &str = "__lock";
+/// Interval between checks whether the lock holder filled the cache
+const RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Model specific parts of a read-through cache.
+pub(crate) struct CacheSpec<M> {
+    /// Command fetching a single model by its redis key.
+    pub fetch: fn(&Namespace, &str) -> RedisResult<Cmd>,
+    /// Parse fetch reply, `None` when no record is stored.
+    pub parse: fn(Value) -> RedisResult<Option<M>>,
+    /// Atomic pipeline storing model at given redis key.
+    pub save: fn(String, &M) -> RedisResult<Pipeline>,
+}
+
+/// Read-through cache of `M` records loaded from another source of truth, e.g. a database.
+///
+/// Created with `HashModel::cache()` or `JsonModel::cache()`, see `get_or_load`.
+///
+/// - Concurrent misses of the same key are loaded once, others wait on a `SET NX` lock.
+/// - Misses are cached as well, for 30 seconds unless configured otherwise.
+/// - With [`CachedModel::local`], records are also kept in a bounded in-process LRU,
+///   invalidated through [`CachedModel::enable_tracking`] and [`CachedModel::invalidate`].
+pub struct CachedModel<M> {
+    spec: CacheSpec<M>,
+    lock_timeout: Duration,
+    negative_ttl: Option<Duration>,
+    local: Option<Mutex<Local<M>>>,
+}
+
+impl<M: RedisModel> CachedModel<M> {
+    pub(crate) fn new(spec: CacheSpec<M>) -> Self {
+        Self {
+            spec,
+            lock_timeout: DEFAULT_LOCK_TIMEOUT,
+            negative_ttl: Some(DEFAULT_NEGATIVE_TTL),
+            local: None,
+        }
+    }
+
+    /// Set how long a loader may hold the lock of a key, defaults to 5 seconds
+    pub fn lock_timeout(mut self, timeout: Duration) -> Self {
+        self.lock_timeout = timeout;
+        self
+    }
+
+    /// Set how long misses are cached, `None` to not cache misses
+    pub fn negative_ttl(mut self, ttl: Option<Duration>) -> Self {
+        self.negative_ttl = ttl.filter(|ttl| !ttl.is_zero());
+        self
+    }
+
+    /// Keep up to `capacity` records in a least recently used in-process cache
+    pub fn local(mut self, capacity: usize) -> Self {
+        self.local = (capacity > 0).then(|| Mutex::new(Local::new(capacity)));
+        self
+    }
+
+    /// Handle a message of "__redis__:invalidate", evicting invalidated keys from the
+    /// in-process cache, or all of them when redis was flushed.
+    pub fn invalidate(&self, msg: &Msg) -> RedisResult<()> {
+        if msg.get_channel_name() != INVALIDATE_CHANNEL {
+            return Ok(());
+        }
+
+        let keys: Option<Vec<String>> = msg.get_payload()?;
+        self.with_local(|local| match keys {
+            Some(keys) => keys.iter().for_each(|key| local.evict(key)),
+            None => local.clear(),
+        });
+
+        Ok(())
+    }
+
+    /// Evict all records from the in-process cache
+    pub fn clear_local(&self) {
+        self.with_local(Local::clear);
+    }
+
+    fn with_local<T>(&self, f: impl FnOnce(&mut Local<M>) -> T) -> Option<T> {
+        let local = self.local.as_ref()?;
+        let mut local = local.lock().unwrap_or_else(|e| e.into_inner());
+        Some(f(&mut local))
+    }
+}
+
+impl<M: RedisModel + Clone> CachedModel<M> {
+    fn local_get(&self, key: &str) -> Option<Option<M>> {
+        self.with_local(|local| local.get(key)).flatten()
+    }
+
+    fn local_put(&self, ns: &Namespace, key: &str, value: Option<M>, ttl: Duration) {
+        let marker = value.is_none().then(|| cmds::side_key(ns, key, MISS));
+        self.with_local(|local| local.put(key, value, ttl, marker));
+    }
+}
+
+/// Outcome of looking up a key in redis.
+enum Lookup<M> {
+    Hit(M),
+    Miss,
+    Unknown,
+}
+
+mod cmds {
+    use super::*;
+
+    /// Key "{key}:{suffix}" next to record `key`, in the same slot.
+    pub fn side_key(ns: &Namespace, key: &str, suffix: &str) -> String {
+        format!("{key}{}{suffix}", ns.get_separator())
+    }
+
+    /// Fetch record of `key` along whether its miss is cached.
+    pub fn lookup<M>(spec: &CacheSpec<M>, ns: &Namespace, key: &str) -> RedisResult<Pipeline> {
+        let mut pipe = redis::pipe();
+        pipe.add_command((spec.fetch)(ns, key)?)
+            .exists(side_key(ns, key, MISS));
+
+        Ok(pipe)
+    }
+
+    /// Acquire lock of `key` held by `token`, replying whether it was acquired.
+    pub fn lock(ns: &Namespace, key: &str, token: &str, timeout: Duration) -> Cmd {
+        let mut cmd = redis::cmd("SET");
+        cmd.arg(side_key(ns, key, LOCK))
+            .arg(token)
+            .arg("NX")
+            .arg("PX")
+            .arg(timeout.as_millis().max(1) as u64);
+
+        cmd
+    }
+
+    /// Release lock of `key` if still held by `token`.
+    pub fn unlock(ns: &Namespace, key: &str, token: &str) -> Cmd {
+        let mut cmd = redis::cmd("EVAL");
+        cmd.arg("if redis.call('GET', KEYS[1]) == ARGV[1] then return redis.call('DEL', KEYS[1]) end return 0")
+            .arg(1)
+            .arg(side_key(ns, key, LOCK))
+            .arg(token);
+
+        cmd
+    }
+
+    /// Store loaded record at `key` expiring after `ttl`, or cache its miss.
+    pub fn fill<M>(
+        spec: &CacheSpec<M>,
+        ns: &Namespace,
+        key: &str,
+        value: Option<&M>,
+        ttl: Duration,
+        negative_ttl: Option<Duration>,
+    ) -> RedisResult<Option<Pipeline>> {
+        let pipe = match (value, negative_ttl) {
+            (Some(value), _) => {
+                let mut pipe = (spec.save)(key.to_owned(), value)?;
+                pipe.cmd("PEXPIRE")
+                    .arg(key)
+                    .arg(ttl.as_millis().max(1) as u64)
+                    .ignore();
+                pipe
+            }
+            (None, Some(negative_ttl)) => {
+                let mut pipe = redis::pipe();
+                pipe.cmd("SET")
+                    .arg(side_key(ns, key, MISS))
+                    .arg(1)
+                    .arg("PX")
+                    .arg(negative_ttl.as_millis() as u64)
+                    .ignore();
+                pipe
+            }
+            (None, None) => return Ok(None),
+        };
+
+        Ok(Some(pipe))
+    }
+
+    /// Enable invalidation messages of keys under prefix of `M`, redirected to `client_id`.
+    pub fn tracking<M: RedisModel>(ns: &Namespace, client_id: i64) -> Cmd {
+        let mut cmd = redis::cmd("CLIENT");
+        cmd.arg("TRACKING")
+            .arg("ON")
+            .arg("REDIRECT")
+            .arg(client_id)
+            .arg("BCAST")
+            .arg("PREFIX")
+            .arg(ns.key(M::_prefix_key(), ""));
+
+        cmd
+    }
+}
+
+mod transformers {
+    use super::*;
+    use redis::FromRedisValue;
+
+    pub fn lookup<M>(spec: &CacheSpec<M>, reply: Value) -> RedisResult<Lookup<M>> {
+        let (record, missed): (Value, bool) = FromRedisValue::from_redis_value(&reply)?;
+
+        Ok(match (spec.parse)(record)? {
+            Some(model) => Lookup::Hit(model),
+            None if missed => Lookup::Miss,
+            None => Lookup::Unknown,
+        })
+    }
+}
+
+/// Bounded least recently used in-process cache, keyed by redis key.
+struct Local<M> {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<String, LocalEntry<M>>,
+    order: BTreeMap<u64, String>,
+    /// Miss marker keys of cached misses, mapped to their record keys
+    markers: HashMap<String, String>,
+}
+
+struct LocalEntry<M> {
+    value: Option<M>,
+    expires_at: Instant,
+    tick: u64,
+    marker: Option<String>,
+}
+
+impl<M: Clone> Local<M> {
+    fn get(&mut self, key: &str) -> Option<Option<M>> {
+        let entry = self.entries.get_mut(key)?;
+
+        if entry.expires_at <= Instant::now() {
+            self.evict(key);
+            return None;
+        }
+
+        self.tick += 1;
+        self.order.remove(&entry.tick);
+        self.order.insert(self.tick, key.to_owned());
+        entry.tick = self.tick;
+
+        Some(entry.value.clone())
+    }
+
+    /// Cache `value` of `key`, with `marker` being the miss marker key of cached misses.
+    fn put(&mut self, key: &str, value: Option<M>, ttl: Duration, marker: Option<String>) {
+        self.evict(key);
+
+        self.tick += 1;
+        self.order.insert(self.tick, key.to_owned());
+        if let Some(marker) = &marker {
+            self.markers.insert(marker.clone(), key.to_owned());
+        }
+        self.entries.insert(
+            key.to_owned(),
+            LocalEntry {
+                value,
+                expires_at: Instant::now() + ttl,
+                tick: self.tick,
+                marker,
+            },
+        );
+
+        while self.entries.len() > self.capacity {
+            let Some((_, key)) = self.order.pop_first() else {
+                break;
+            };
+            self.evict(&key);
+        }
+    }
+}
+
+impl<M> Local<M> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            tick: 0,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            markers: HashMap::new(),
+        }
+    }
+
+    /// Evict record of `key`, which is either a record key or a miss marker key.
+    fn evict(&mut self, key: &str) {
+        let key = self
+            .markers
+            .get(key)
+            .cloned()
+            .unwrap_or_else(|| key.to_owned());
+        let Some(entry) = self.entries.remove(&key) else {
+            return;
+        };
+
+        self.order.remove(&entry.tick);
+        if let Some(marker) = entry.marker {
+            self.markers.remove(&marker);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+        self.markers.clear();
+    }
+}
@@ -0,0 +1,93 @@
+use redis::{ErrorKind, RedisError, RedisResult};
+
+/// Number of hash slots in a redis cluster.
+const SLOT_COUNT: u16 = 16384;
+
+/// Part of a model key wrapped in `{...}`, so that related keys land on the same cluster slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashTag {
+    /// Keys are of format "{prefix}:{pk}", slot is computed from the whole key.
+    None,
+    /// Keys are of format "{prefix}:{{tag}}:{pk}", all records share a single slot.
+    Static(&'static str),
+    /// Keys are of format "{prefix}:{{pk}}", keys sharing a pk share a slot.
+    PrimaryKey,
+}
+
+impl HashTag {
+    /// Get pk segment of a model key.
+    pub fn tag_pk(&self, separator: &str, pk: &str) -> String {
+        match self {
+            HashTag::None => pk.to_owned(),
+            HashTag::Static(tag) => format!("{{{tag}}}{separator}{pk}"),
+            HashTag::PrimaryKey => format!("{{{pk}}}"),
+        }
+    }
+
+    /// Get pk from pk segment of a model key.
+    pub fn untag_pk<'k>(&self, separator: &str, segment: &'k str) -> Option<&'k str> {
+        match self {
+            HashTag::None => Some(segment),
+            HashTag::Static(tag) => segment
+                .strip_prefix('{')
+                .and_then(|s| s.strip_prefix(tag))
+                .and_then(|s| s.strip_prefix('}'))
+                .and_then(|s| s.strip_prefix(separator)),
+            HashTag::PrimaryKey => segment.strip_prefix('{').and_then(|s| s.strip_suffix('}')),
+        }
+    }
+}
+
+/// Get cluster slot of `key`, taking its hash tag into account.
+///
+/// # Example
+///
+/// ```
+/// use redis_om::key_slot;
+///
+/// assert_eq!(key_slot("foo"), 12182);
+/// assert_eq!(key_slot("{user1}:following"), key_slot("{user1}:followers"));
+/// ```
+pub fn key_slot(key: impl AsRef<[u8]>) -> u16 {
+    crc16(hash_tag(key.as_ref())) % SLOT_COUNT
+}
+
+/// Ensure all `keys` share a single cluster slot.
+pub fn ensure_same_slot<K: AsRef<[u8]>>(keys: &[K]) -> RedisResult<()> {
+    let mut slots = keys.iter().map(key_slot);
+    let first = slots.next();
+
+    match slots.find(|slot| Some(*slot) != first) {
+        Some(slot) => Err(RedisError::from((
+            ErrorKind::CrossSlot,
+            "keys of multi-key operation don't hash to the same slot",
+            format!("got slots {} and {}", first.unwrap_or_default(), slot),
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Get part of `key` used to compute its slot, the first non empty `{...}` or the whole key.
+fn hash_tag(key: &[u8]) -> &[u8] {
+    key.iter()
+        .position(|b| *b == b'{')
+        .and_then(|open| {
+            let rest = &key[open + 1..];
+            let close = rest.iter().position(|b| *b == b'}')?;
+            (close > 0).then(|| &rest[..close])
+        })
+        .unwrap_or(key)
+}
+
+/// CRC16 (XMODEM) as used by redis cluster.
+fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0, |crc, byte| {
+        (0..8).fold(crc ^ (u16::from(*byte) << 8), |crc, _| {
+            if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            }
+        })
+    })
+}
@@ -0,0 +1,160 @@
+use super::{ConsumerStats, StreamConsumer, CLAIM_START, PENDING_START};
+use crate::{StreamModel, TypedMessage, WithNamespace};
+use futures_timer::Delay;
+use redis::RedisResult;
+use std::fmt::Display;
+use std::future::Future;
+use std::time::Instant;
+
+impl<S: StreamModel + Sync> StreamConsumer<S>
+where
+    S::Data: Send,
+{
+    /// Consume messages with async `handler` until the consumer is cancelled, acknowledging
+    /// messages it handles successfully.
+    ///
+    /// Returns once cancelled, after finishing messages at hand, or on redis errors.
+    pub async fn run<C, F, Fut, E>(
+        &self,
+        mut handler: F,
+        conn: &mut C,
+    ) -> RedisResult<ConsumerStats>
+    where
+        C: WithNamespace + Send,
+        F: FnMut(TypedMessage<S::Data>) -> Fut + Send,
+        Fut: Future<Output = Result<(), E>> + Send,
+        E: Display,
+    {
+        let group = self.manager.group_name();
+        let consumer = self.manager.consumer_name();
+        let mut stats = ConsumerStats::default();
+        let mut claim_id = CLAIM_START;
+        let mut next_claim = Instant::now();
+
+        if self.recover {
+            self.recover(&mut handler, &mut stats, conn).await?;
+        }
+
+        while !self.token.is_cancelled() {
+            let messages = if Instant::now() >= next_claim {
+                let (next_id, messages) = S::autoclaim(
+                    group,
+                    consumer,
+                    self.min_idle_millis(),
+                    claim_id,
+                    Some(self.read_count),
+                    conn,
+                )
+                .await?;
+
+                // Keep claiming until the whole pending list was scanned
+                if next_id == CLAIM_START {
+                    next_claim = Instant::now() + self.claim_interval;
+                    if let Some(min_idle) = self.cleanup_idle {
+                        S::cleanup_consumers(group, min_idle, conn).await?;
+                    }
+                }
+                claim_id = next_id;
+                stats.claimed += messages.len();
+                messages
+            } else {
+                let block = Some(self.block_millis());
+                self.manager
+                    .read(Some(self.read_count), block, conn)
+                    .await?
+            };
+
+            for message in messages {
+                self.dispatch(message, &mut handler, &mut stats, conn)
+                    .await?;
+            }
+        }
+
+        Ok(stats)
+    }
+
+    /// Dispatch messages pending for this consumer, in batches following the last dispatched
+    async fn recover<C, F, Fut, E>(
+        &self,
+        handler: &mut F,
+        stats: &mut ConsumerStats,
+        conn: &mut C,
+    ) -> RedisResult<()>
+    where
+        C: WithNamespace + Send,
+        F: FnMut(TypedMessage<S::Data>) -> Fut + Send,
+        Fut: Future<Output = Result<(), E>> + Send,
+        E: Display,
+    {
+        let mut start_id = PENDING_START;
+
+        while !self.token.is_cancelled() {
+            let messages = self
+                .manager
+                .read_pending(start_id, Some(self.read_count), conn)
+                .await?;
+            let Some(last) = messages.last() else {
+                break;
+            };
+
+            start_id = last.id;
+            stats.recovered += messages.len();
+            for message in messages {
+                self.dispatch(message, handler, stats, conn).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn dispatch<C, F, Fut, E>(
+        &self,
+        message: TypedMessage<S::Data>,
+        handler: &mut F,
+        stats: &mut ConsumerStats,
+        conn: &mut C,
+    ) -> RedisResult<()>
+    where
+        C: WithNamespace + Send,
+        F: FnMut(TypedMessage<S::Data>) -> Fut + Send,
+        Fut: Future<Output = Result<(), E>> + Send,
+        E: Display,
+    {
+        let mut error = String::new();
+        for attempt in 0..=self.retry.max_retries {
+            if attempt > 0 {
+                if self.token.is_cancelled() {
+                    break;
+                }
+                Delay::new(self.retry.delay(attempt)).await;
+            }
+
+            match handler(message.duplicate()).await {
+                Ok(()) => {
+                    message.ack::<S, _>(conn).await?;
+                    stats.handled += 1;
+                    return Ok(());
+                }
+                Err(err) => error = err.to_string(),
+            }
+        }
+
+        if let Some(max_deliveries) = self.retry.max_deliveries {
+            let group = self.manager.group_name();
+            let deliveries = match message.deliveries {
+                Some(deliveries) => deliveries,
+                None => S::delivery_count(group, message.id, conn)
+                    .await?
+                    .unwrap_or(1),
+            };
+            if deliveries >= max_deliveries {
+                S::dead_letter(&message, error, deliveries, conn).await?;
+                stats.dead_lettered += 1;
+                return Ok(());
+            }
+        }
+
+        stats.failed += 1;
+        Ok(())
+    }
+}
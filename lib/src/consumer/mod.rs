@@ -0,0 +1,241 @@
+#[cfg(feature = "aio")]
+mod r#async;
+#[cfg(not(feature = "aio"))]
+mod sync;
+
+use crate::{StreamEntryId, StreamModel};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Default number of messages read or claimed per round trip.
+const DEFAULT_READ_COUNT: usize = 10;
+/// Default time XREADGROUP blocks waiting for new messages.
+const DEFAULT_BLOCK: Duration = Duration::from_secs(5);
+/// Default number of times a failing message is retried before leaving it pending.
+const DEFAULT_MAX_RETRIES: usize = 2;
+/// Default delay before the first retry of a failing message.
+const DEFAULT_RETRY_DELAY: Duration = Duration::from_millis(100);
+/// Default maximum delay between retries of a failing message.
+const DEFAULT_MAX_RETRY_DELAY: Duration = Duration::from_secs(10);
+/// Default interval between reclaims of idle pending messages.
+const DEFAULT_CLAIM_INTERVAL: Duration = Duration::from_secs(30);
+/// Default time a pending message must be idle before it is reclaimed.
+const DEFAULT_MIN_IDLE: Duration = Duration::from_secs(60);
+
+/// XAUTOCLAIM cursor of the start of the pending entries list.
+const CLAIM_START: StreamEntryId = StreamEntryId::MIN;
+/// XREADGROUP id of the start of the pending entries list of a consumer.
+const PENDING_START: StreamEntryId = StreamEntryId::MIN;
+
+/// Token stopping a [`StreamConsumer`], shared between clones.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Create new token
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stop consumers using this token, once done with messages at hand
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether the token was cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// How a [`StreamConsumer`] retries failing messages.
+///
+/// Each delivery of a message is retried up to `max_retries` times, with delays doubling
+/// from the initial delay. Messages still failing are left pending to be reclaimed later,
+/// or, once delivered `max_deliveries` times (as counted by XPENDING), moved to the
+/// dead-letter stream "{stream_key}:dlq" and acknowledged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    max_retries: usize,
+    initial_delay: Duration,
+    max_delay: Duration,
+    max_deliveries: Option<usize>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: DEFAULT_MAX_RETRIES,
+            initial_delay: DEFAULT_RETRY_DELAY,
+            max_delay: DEFAULT_MAX_RETRY_DELAY,
+            max_deliveries: None,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Create policy retrying twice, 100 milliseconds then 200 milliseconds after failing,
+    /// and never dead-lettering messages
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set number of retries of each delivery
+    pub fn max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Set delay before the first retry, doubling for each retry up to `max_delay`
+    pub fn backoff(mut self, initial_delay: Duration, max_delay: Duration) -> Self {
+        self.initial_delay = initial_delay;
+        self.max_delay = max_delay.max(initial_delay);
+        self
+    }
+
+    /// Move messages still failing on their `max_deliveries`th delivery to the dead-letter stream
+    pub fn dead_letter_after(mut self, max_deliveries: usize) -> Self {
+        self.max_deliveries = Some(max_deliveries.max(1));
+        self
+    }
+
+    /// Delay before `retry`, starting at 1
+    pub fn delay(&self, retry: usize) -> Duration {
+        let exponent = retry.saturating_sub(1).min(u32::BITS as usize - 1) as u32;
+        self.initial_delay
+            .saturating_mul(1 << exponent)
+            .min(self.max_delay)
+    }
+}
+
+/// Counts of messages processed by [`StreamConsumer::run`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConsumerStats {
+    /// Messages handled and acknowledged
+    pub handled: usize,
+    /// Messages still failing after all retries, left pending to be reclaimed
+    pub failed: usize,
+    /// Messages moved to the dead-letter stream
+    pub dead_lettered: usize,
+    /// Idle pending messages claimed from this or other consumers
+    pub claimed: usize,
+    /// Pending messages of this consumer delivered again on start, see
+    /// [`StreamConsumer::recover_pending`]
+    pub recovered: usize,
+}
+
+/// Long-running consumer of a stream through manager `S`, e.g. a derived `*Manager`.
+///
+/// `run` blocks on XREADGROUP and passes each message to a handler, acknowledging it when
+/// handled. Failing messages are retried according to its [`RetryPolicy`], and pending
+/// messages idle for too long (e.g. of crashed consumers) are periodically reclaimed with
+/// XAUTOCLAIM. Once done reclaiming, idle consumers of managers created with `new` can be
+/// deleted as well, see [`StreamConsumer::cleanup_consumers`].
+///
+/// # Example
+///
+/// ```ignore
+/// let consumer = StreamConsumer::new(RoomServiceEventManager::new("Staff"));
+/// let token = consumer.token();
+///
+/// // e.g. on SIGTERM
+/// token.cancel();
+///
+/// consumer.run(|message| handle(message.data()?), &mut conn)?;
+/// ```
+pub struct StreamConsumer<S> {
+    manager: S,
+    token: CancellationToken,
+    read_count: usize,
+    block: Duration,
+    retry: RetryPolicy,
+    claim_interval: Duration,
+    min_idle: Duration,
+    cleanup_idle: Option<Duration>,
+    recover: bool,
+}
+
+impl<S: StreamModel> StreamConsumer<S> {
+    /// Create new consumer reading as group and consumer of `manager`
+    pub fn new(manager: S) -> Self {
+        Self {
+            manager,
+            token: CancellationToken::new(),
+            read_count: DEFAULT_READ_COUNT,
+            block: DEFAULT_BLOCK,
+            retry: RetryPolicy::default(),
+            claim_interval: DEFAULT_CLAIM_INTERVAL,
+            min_idle: DEFAULT_MIN_IDLE,
+            cleanup_idle: None,
+            recover: false,
+        }
+    }
+
+    /// Set number of messages read or claimed per round trip, defaults to 10
+    pub fn read_count(mut self, count: usize) -> Self {
+        self.read_count = count.max(1);
+        self
+    }
+
+    /// Set how long reads block waiting for new messages, defaults to 5 seconds.
+    ///
+    /// This bounds how long a cancelled consumer takes to stop.
+    pub fn block(mut self, block: Duration) -> Self {
+        self.block = block;
+        self
+    }
+
+    /// Set how failing messages are retried, see [`RetryPolicy::new`] for defaults
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// Reclaim messages pending for at least `min_idle` every `interval`, defaults to messages
+    /// idle for 60 seconds every 30 seconds
+    pub fn reclaim(mut self, interval: Duration, min_idle: Duration) -> Self {
+        self.claim_interval = interval;
+        self.min_idle = min_idle;
+        self
+    }
+
+    /// Delete consumers with generated names idle for at least `min_idle` and without pending
+    /// messages after each reclaim, e.g. of crashed consumers whose messages were reclaimed
+    pub fn cleanup_consumers(mut self, min_idle: Duration) -> Self {
+        self.cleanup_idle = Some(min_idle);
+        self
+    }
+
+    /// Handle messages left pending for this consumer before reading new ones, e.g. after a
+    /// crash. Only meaningful for managers with a stable consumer name, i.e. created with
+    /// `new_with_consumer_name`
+    pub fn recover_pending(mut self) -> Self {
+        self.recover = true;
+        self
+    }
+
+    /// Stop consumer with given `token` rather than its own
+    pub fn with_token(mut self, token: CancellationToken) -> Self {
+        self.token = token;
+        self
+    }
+
+    /// Token stopping this consumer
+    pub fn token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    /// Manager messages are read with
+    pub fn manager(&self) -> &S {
+        &self.manager
+    }
+
+    fn block_millis(&self) -> usize {
+        self.block.as_millis().max(1) as usize
+    }
+
+    fn min_idle_millis(&self) -> usize {
+        self.min_idle.as_millis() as usize
+    }
+}
@@ -0,0 +1,141 @@
+use super::{ConsumerStats, StreamConsumer, CLAIM_START, PENDING_START};
+use crate::{StreamModel, TypedMessage, WithNamespace};
+use redis::RedisResult;
+use std::fmt::Display;
+use std::time::Instant;
+
+impl<S: StreamModel> StreamConsumer<S> {
+    /// Consume messages with `handler` until the consumer is cancelled, acknowledging
+    /// messages it handles successfully.
+    ///
+    /// Returns once cancelled, after finishing messages at hand, or on redis errors.
+    pub fn run<C, F, E>(&self, mut handler: F, conn: &mut C) -> RedisResult<ConsumerStats>
+    where
+        C: WithNamespace,
+        F: FnMut(&TypedMessage<S::Data>) -> Result<(), E>,
+        E: Display,
+    {
+        let group = self.manager.group_name();
+        let consumer = self.manager.consumer_name();
+        let mut stats = ConsumerStats::default();
+        let mut claim_id = CLAIM_START;
+        let mut next_claim = Instant::now();
+
+        if self.recover {
+            self.recover(&mut handler, &mut stats, conn)?;
+        }
+
+        while !self.token.is_cancelled() {
+            let messages = if Instant::now() >= next_claim {
+                let (next_id, messages) = S::autoclaim(
+                    group,
+                    consumer,
+                    self.min_idle_millis(),
+                    claim_id,
+                    Some(self.read_count),
+                    conn,
+                )?;
+
+                // Keep claiming until the whole pending list was scanned
+                if next_id == CLAIM_START {
+                    next_claim = Instant::now() + self.claim_interval;
+                    if let Some(min_idle) = self.cleanup_idle {
+                        S::cleanup_consumers(group, min_idle, conn)?;
+                    }
+                }
+                claim_id = next_id;
+                stats.claimed += messages.len();
+                messages
+            } else {
+                let block = Some(self.block_millis());
+                self.manager.read(Some(self.read_count), block, conn)?
+            };
+
+            for message in &messages {
+                self.dispatch(message, &mut handler, &mut stats, conn)?;
+            }
+        }
+
+        Ok(stats)
+    }
+
+    /// Dispatch messages pending for this consumer, in batches following the last dispatched
+    fn recover<C, F, E>(
+        &self,
+        handler: &mut F,
+        stats: &mut ConsumerStats,
+        conn: &mut C,
+    ) -> RedisResult<()>
+    where
+        C: WithNamespace,
+        F: FnMut(&TypedMessage<S::Data>) -> Result<(), E>,
+        E: Display,
+    {
+        let mut start_id = PENDING_START;
+
+        while !self.token.is_cancelled() {
+            let messages = self
+                .manager
+                .read_pending(start_id, Some(self.read_count), conn)?;
+            let Some(last) = messages.last() else {
+                break;
+            };
+
+            start_id = last.id;
+            stats.recovered += messages.len();
+            for message in &messages {
+                self.dispatch(message, handler, stats, conn)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn dispatch<C, F, E>(
+        &self,
+        message: &TypedMessage<S::Data>,
+        handler: &mut F,
+        stats: &mut ConsumerStats,
+        conn: &mut C,
+    ) -> RedisResult<()>
+    where
+        C: WithNamespace,
+        F: FnMut(&TypedMessage<S::Data>) -> Result<(), E>,
+        E: Display,
+    {
+        let mut error = String::new();
+        for attempt in 0..=self.retry.max_retries {
+            if attempt > 0 {
+                if self.token.is_cancelled() {
+                    break;
+                }
+                std::thread::sleep(self.retry.delay(attempt));
+            }
+
+            match handler(message) {
+                Ok(()) => {
+                    message.ack::<S, _>(conn)?;
+                    stats.handled += 1;
+                    return Ok(());
+                }
+                Err(err) => error = err.to_string(),
+            }
+        }
+
+        if let Some(max_deliveries) = self.retry.max_deliveries {
+            let group = self.manager.group_name();
+            let deliveries = match message.deliveries {
+                Some(deliveries) => deliveries,
+                None => S::delivery_count(group, message.id, conn)?.unwrap_or(1),
+            };
+            if deliveries >= max_deliveries {
+                S::dead_letter(message, error, deliveries, conn)?;
+                stats.dead_lettered += 1;
+                return Ok(());
+            }
+        }
+
+        stats.failed += 1;
+        Ok(())
+    }
+}
@@ -1,7 +1,18 @@
-use super::cmds;
-use crate::{RedisModel, RedisSearchModel};
-use redis::aio::ConnectionLike;
+use super::{
+    audit, cache_spec, cmds, parse_from_find_resp, parse_from_get_resp, scan_spec, upgrade,
+    upgrade_cmd, KEY_TYPE,
+};
+use crate::audit::AuditOp;
+use crate::migration::{HashRecord, MigrationProgress, Migrations};
+use crate::primary_key::ensure_pk;
+use crate::redis_model::parse_pttl;
+use crate::redissearch_model;
+use crate::scan::{self, DEFAULT_BATCH_SIZE};
+use crate::soft_delete;
+use crate::{CachedModel, Namespace, RedisModel, RedisSearchModel, ToPk, WithNamespace};
+use futures::stream::BoxStream;
 use redis::{FromRedisValue, RedisResult, ToRedisArgs};
+use std::time::{Duration, SystemTime};
 
 /// Hash Object Model
 #[async_trait::async_trait]
@@ -12,52 +23,336 @@ pub trait HashModel: RedisModel + RedisSearchModel + ToRedisArgs + FromRedisValu
         <Self as RedisModel>::_prefix_key()
     }
 
+    /// Get per-field time to live applied on every save, set with `#[redis(ttl = "...")]`
+    fn _field_ttls() -> &'static [(&'static str, Duration)] {
+        &[]
+    }
+
     /// Redis search schema
     fn redissearch_schema() -> &'static str {
         <Self as RedisSearchModel>::_REDIS_SEARCH_SCHEMA
     }
 
-    /// Save Self into redis database
+    /// Redis search schema with its `PREFIX` scoped to given namespace
+    fn redissearch_schema_in(ns: &Namespace) -> String {
+        Self::_redis_search_schema(&ns.model_prefix(Self::_prefix_key()))
+    }
+
+    /// Save Self into redis database, replacing any previously stored fields.
+    /// Note: replacing the record clears its expiry.
     async fn save<C>(&mut self, conn: &mut C) -> RedisResult<()>
     where
-        C: ConnectionLike + Send,
+        C: WithNamespace + Send,
+    {
+        ensure_pk(self, conn).await?;
+        let key = self._get_redis_key(conn.namespace());
+        let pipe = cmds::save(key.clone(), self)?;
+
+        if Self::_audit_stream().is_none() {
+            return pipe.query_async(conn.connection()).await;
+        }
+
+        let (ns, pk) = (conn.namespace().clone(), self._get_pk());
+        let get = cmds::get::<Self>(&ns, &pk)?;
+        let audit = audit(&ns, &pk, AuditOp::Save, Some(self), false);
+        audit::watch(conn.connection(), &key, get, pipe, audit).await
+    }
+
+    /// Save Self into redis database, keeping stored fields missing from Self
+    async fn save_merge<C>(&mut self, conn: &mut C) -> RedisResult<()>
+    where
+        C: WithNamespace + Send,
     {
-        self._ensure_pk();
-        let key = self._get_redis_key();
-        let cmd = cmds::save(key, self)?;
-        cmd.query_async(conn).await
+        ensure_pk(self, conn).await?;
+        let key = self._get_redis_key(conn.namespace());
+        let pipe = cmds::merge(key.clone(), self)?;
+
+        if Self::_audit_stream().is_none() {
+            return pipe.query_async(conn.connection()).await;
+        }
+
+        let (ns, pk) = (conn.namespace().clone(), self._get_pk());
+        let get = cmds::get::<Self>(&ns, &pk)?;
+        let audit = audit(&ns, &pk, AuditOp::Save, Some(self), true);
+        audit::watch(conn.connection(), &key, get, pipe, audit).await
+    }
+
+    /// Read-through cache of Self in front of another source of truth, see [`CachedModel`]
+    fn cache() -> CachedModel<Self> {
+        CachedModel::new(cache_spec::<Self>())
+    }
+
+    /// Stream all records of current type, fetching them in pipelined batches
+    fn all<'a, C>(conn: &'a mut C) -> BoxStream<'a, RedisResult<Self>>
+    where
+        C: WithNamespace + Send,
+        Self: Send + 'a,
+    {
+        Self::scan(DEFAULT_BATCH_SIZE, conn)
+    }
+
+    /// Stream all records of current type, fetching `batch_size` records per round trip
+    fn scan<'a, C>(batch_size: usize, conn: &'a mut C) -> BoxStream<'a, RedisResult<Self>>
+    where
+        C: WithNamespace + Send,
+        Self: Send + 'a,
+    {
+        scan::scan(scan_spec::<Self>(), batch_size, conn)
     }
 
     /// Get a list of all primary keys for current type
-    async fn all_pks<C: ConnectionLike + Send>(
+    async fn all_pks<C: WithNamespace + Send>(
         conn: &mut C,
     ) -> RedisResult<redis::AsyncIter<'_, String>> {
-        cmds::all_pks::<Self>()?.iter_async(conn).await
+        cmds::all_pks::<Self>(conn.namespace())?
+            .iter_async(conn.connection())
+            .await
     }
 
     /// Get a list of all primary keys for current type
     async fn get<C, S>(pk: S, conn: &mut C) -> RedisResult<Self>
     where
-        S: AsRef<str> + Send,
-        C: ConnectionLike + Send,
+        S: ToPk + Send,
+        C: WithNamespace + Send,
+    {
+        let key = Self::_fmt_key(conn.namespace(), &pk.to_pk());
+        let mut resp = cmds::get::<Self>(conn.namespace(), &key)?
+            .query_async(conn.connection())
+            .await?;
+
+        if let Some(upgrade) = upgrade::<Self>(&resp)? {
+            cmds::upgrade(&key, &upgrade)
+                .query_async::<_, ()>(conn.connection())
+                .await?;
+            resp = upgrade.to_value();
+        }
+
+        parse_from_get_resp(resp)
+    }
+
+    /// Find by given pk, returning `None` if no record exists
+    async fn find_by_pk<S, C>(pk: S, conn: &mut C) -> RedisResult<Option<Self>>
+    where
+        S: ToPk + Send,
+        C: WithNamespace + Send,
+    {
+        let key = Self::_fmt_key(conn.namespace(), &pk.to_pk());
+        let mut resp = cmds::get::<Self>(conn.namespace(), &key)?
+            .query_async(conn.connection())
+            .await?;
+
+        if let Some(upgrade) = upgrade::<Self>(&resp)? {
+            cmds::upgrade(&key, &upgrade)
+                .query_async::<_, ()>(conn.connection())
+                .await?;
+            resp = upgrade.to_value();
+        }
+
+        parse_from_find_resp(resp)
+    }
+
+    /// Check whether a record exists for given pk, excluding soft deleted records
+    async fn exists<S, C>(pk: S, conn: &mut C) -> RedisResult<bool>
+    where
+        S: ToPk + Send,
+        C: WithNamespace + Send,
     {
-        cmds::get::<Self>(pk)?.query_async(conn).await
+        if Self::_soft_delete().is_some() {
+            return Ok(Self::find_by_pk(pk, conn).await?.is_some());
+        }
+
+        cmds::exists::<Self>(conn.namespace(), pk)?
+            .query_async(conn.connection())
+            .await
     }
 
-    /// Delete by given pk
+    /// Delete by given pk, soft deleting it for `#[redis(soft_delete)]` models
     async fn delete<S, C>(pk: S, conn: &mut C) -> RedisResult<()>
     where
-        S: AsRef<str> + Send,
-        C: ConnectionLike + Send,
+        S: ToPk + Send,
+        C: WithNamespace + Send,
     {
-        cmds::delete::<Self>(pk)?.query_async(conn).await
+        let pk = pk.to_pk();
+        let mut pipe = redis::pipe();
+        pipe.atomic()
+            .add_command(cmds::delete::<Self>(conn.namespace(), &pk)?)
+            .ignore();
+
+        if Self::_audit_stream().is_none() {
+            return pipe.query_async(conn.connection()).await;
+        }
+
+        let ns = conn.namespace().clone();
+        let (key, get) = (Self::_fmt_key(&ns, &pk), cmds::get::<Self>(&ns, &pk)?);
+        let audit = audit::<Self>(&ns, &pk, AuditOp::Delete, None, false);
+        audit::watch(conn.connection(), &key, get, pipe, audit).await
+    }
+
+    /// Restore soft deleted record of given pk, returning whether one was found
+    async fn restore<S, C>(pk: S, conn: &mut C) -> RedisResult<bool>
+    where
+        S: ToPk + Send,
+        C: WithNamespace + Send,
+    {
+        cmds::restore::<Self>(conn.namespace(), pk)?
+            .query_async(conn.connection())
+            .await
+    }
+
+    /// Permanently delete records soft deleted more than `older_than` ago,
+    /// returning the number of deleted records
+    async fn purge_deleted<C>(older_than: Duration, conn: &mut C) -> RedisResult<usize>
+    where
+        C: WithNamespace + Send,
+    {
+        let storage = &soft_delete::HASH;
+        scan::purge_deleted::<Self, _>(KEY_TYPE, storage, older_than, DEFAULT_BATCH_SIZE, conn)
+            .await
+    }
+
+    /// Migrations upgrading records of older schema versions, set with
+    /// `#[redis(migrations = "path::to::fn")]`
+    fn migrations() -> Migrations<HashRecord> {
+        Migrations::new()
+    }
+
+    /// Upgrade stored records of older schema versions in batches, starting from SCAN `cursor`
+    /// (`0`, or [`MigrationProgress::cursor`] to resume), reporting progress after each batch
+    async fn migrate_all<C, P>(
+        cursor: u64,
+        mut on_progress: P,
+        conn: &mut C,
+    ) -> RedisResult<MigrationProgress>
+    where
+        C: WithNamespace + Send,
+        P: FnMut(&MigrationProgress) + Send,
+    {
+        let spec = scan_spec::<Self>();
+        scan::migrate_all(
+            spec,
+            upgrade_cmd::<Self>,
+            cursor,
+            DEFAULT_BATCH_SIZE,
+            &mut on_progress,
+            conn,
+        )
+        .await
+    }
+
+    /// Count all records of current type by scanning their keys, excluding soft deleted records,
+    /// approximately when records are added or deleted meanwhile. See [`Self::count_indexed`]
+    /// for an exact count from a search index.
+    async fn count<C>(conn: &mut C) -> RedisResult<usize>
+    where
+        C: WithNamespace + Send,
+    {
+        let storage = Self::_soft_delete().map(|_| &soft_delete::HASH);
+        scan::count::<Self, _>(KEY_TYPE, storage, DEFAULT_BATCH_SIZE, conn).await
+    }
+
+    /// Count records indexed by search `index`, e.g. created from [`Self::redissearch_schema_in`],
+    /// with FT.SEARCH instead of scanning. Unlike [`Self::count`] it's exact, and excludes soft
+    /// deleted records through the schema FILTER.
+    async fn count_indexed<C>(index: impl AsRef<str> + Send, conn: &mut C) -> RedisResult<usize>
+    where
+        C: WithNamespace + Send,
+    {
+        redissearch_model::cmds::count(index.as_ref())
+            .query_async(conn.connection())
+            .await
+            .and_then(redissearch_model::transformers::count)
+    }
+
+    /// Permanently delete all records of current type in batches, soft deleted records included,
+    /// returning the number of deleted records
+    async fn delete_all<C>(conn: &mut C) -> RedisResult<usize>
+    where
+        C: WithNamespace + Send,
+    {
+        Self::delete_all_with(DEFAULT_BATCH_SIZE, false, conn).await
+    }
+
+    /// Permanently delete all records of current type, soft deleted records included, unlinking
+    /// `batch_size` records per round trip.
+    /// With `dry_run`, nothing is deleted and the number of records to be deleted is returned.
+    async fn delete_all_with<C>(
+        batch_size: usize,
+        dry_run: bool,
+        conn: &mut C,
+    ) -> RedisResult<usize>
+    where
+        C: WithNamespace + Send,
+    {
+        scan::unlink_all::<Self, _>(KEY_TYPE, batch_size, dry_run, conn).await
     }
 
     /// Expire Self at given duration
     async fn expire<C>(&self, secs: usize, conn: &mut C) -> RedisResult<()>
     where
-        C: ConnectionLike + Send,
+        C: WithNamespace + Send,
     {
-        self._expire_cmd(secs)?.query_async(conn).await
+        self._expire_cmd(conn.namespace(), secs)?
+            .query_async(conn.connection())
+            .await
+    }
+
+    /// Expire Self at given point in time
+    async fn expire_at<C>(&self, at: SystemTime, conn: &mut C) -> RedisResult<()>
+    where
+        C: WithNamespace + Send,
+    {
+        self._expire_at_cmd(conn.namespace(), at)?
+            .query_async(conn.connection())
+            .await
+    }
+
+    /// Remove expiry of Self, returning whether it had one
+    async fn persist<C>(&self, conn: &mut C) -> RedisResult<bool>
+    where
+        C: WithNamespace + Send,
+    {
+        self._persist_cmd(conn.namespace())?
+            .query_async(conn.connection())
+            .await
+    }
+
+    /// Get remaining time to live of Self, `None` if it has no expiry or doesn't exist
+    async fn ttl<C>(&self, conn: &mut C) -> RedisResult<Option<Duration>>
+    where
+        C: WithNamespace + Send,
+    {
+        let millis = self
+            ._pttl_cmd(conn.namespace())?
+            .query_async(conn.connection())
+            .await?;
+
+        Ok(parse_pttl(millis))
+    }
+
+    /// Expire `field` of Self after given duration, requires redis 7.4
+    #[cfg(feature = "hash-field-ttl")]
+    async fn expire_field<C>(&self, field: &str, ttl: Duration, conn: &mut C) -> RedisResult<()>
+    where
+        C: WithNamespace + Send,
+    {
+        let key = self._get_redis_key(conn.namespace());
+        cmds::expire_field(&key, field, ttl.as_millis() as u64)
+            .query_async::<_, Vec<i64>>(conn.connection())
+            .await
+            .map(|_| ())
+    }
+
+    /// Get remaining time to live of `field` of Self, requires redis 7.4
+    #[cfg(feature = "hash-field-ttl")]
+    async fn field_ttl<C>(&self, field: &str, conn: &mut C) -> RedisResult<Option<Duration>>
+    where
+        C: WithNamespace + Send,
+    {
+        let key = self._get_redis_key(conn.namespace());
+        let millis: Vec<i64> = cmds::field_pttl(&key, field)
+            .query_async(conn.connection())
+            .await?;
+
+        Ok(millis.first().copied().and_then(parse_pttl))
     }
 }
@@ -8,55 +8,338 @@ pub use r#async::HashModel;
 #[cfg(not(feature = "aio"))]
 pub use sync::HashModel;
 
+use crate::audit::{self, AuditOp, AuditRecord};
+use crate::cache::CacheSpec;
+use crate::migration::{self, HashRecord, SCHEMA_VERSION};
+use crate::scan::ScanSpec;
+use crate::soft_delete::{self, DELETED_AT};
+use crate::{Namespace, RedisModel};
+use redis::{Cmd, FromRedisValue, RedisResult, Value};
+use std::collections::BTreeMap;
+use tap::Pipe;
+
+/// Redis TYPE of keys holding hashes.
+const KEY_TYPE: &str = "hash";
+
 mod cmds {
+    use super::{HashModel, Upgrade};
+    use crate::migration::SCHEMA_VERSION;
     use crate::redis_model::RedisModel;
-    use redis::{Cmd, RedisResult, ToRedisArgs};
+    use crate::soft_delete;
+    use crate::{Namespace, ToPk};
+    use redis::{Cmd, Pipeline, RedisResult};
 
-    /// Save data into redis database
-    pub fn save<D: ToRedisArgs>(key: String, data: &D) -> RedisResult<Cmd> {
-        let mut cmd = redis::cmd("HSET");
-        cmd.arg(key).arg(data);
+    /// Replace data in redis database, atomically removing fields missing from `data`
+    pub fn save<M: HashModel>(key: String, data: &M) -> RedisResult<Pipeline> {
+        let mut pipe = redis::pipe();
+        pipe.atomic()
+            .del(&key)
+            .ignore()
+            .cmd("HSET")
+            .arg(&key)
+            .arg(data)
+            .arg(schema_version::<M>())
+            .ignore();
 
-        Ok(cmd)
+        expire::<M>(&mut pipe, &key);
+
+        Ok(pipe)
+    }
+
+    /// Save data into redis database, keeping fields missing from `data`
+    pub fn merge<M: HashModel>(key: String, data: &M) -> RedisResult<Pipeline> {
+        let mut pipe = redis::pipe();
+        pipe.atomic()
+            .cmd("HSET")
+            .arg(&key)
+            .arg(data)
+            .arg(schema_version::<M>())
+            .ignore();
+
+        expire::<M>(&mut pipe, &key);
+
+        Ok(pipe)
+    }
+
+    /// Schema version field of `M`, if versioned
+    fn schema_version<M: RedisModel>() -> Option<(&'static str, u32)> {
+        M::_schema_version().map(|version| (SCHEMA_VERSION, version))
+    }
+
+    /// Persist upgrade of record at `key`, unless it changed since read with `stored` version.
+    /// Fields are set in chunks, staying below Lua's unpack limit. Replies whether the record
+    /// was upgraded.
+    pub fn upgrade(key: &str, upgrade: &Upgrade) -> Cmd {
+        let mut cmd = redis::cmd("EVAL");
+        cmd.arg(
+            "local key = KEYS[1] \
+            if redis.call('EXISTS', key) == 0 then return 0 end \
+            if (redis.call('HGET', key, ARGV[1]) or '') ~= ARGV[2] then return 0 end \
+            local removed = tonumber(ARGV[3]) \
+            for i = 4, 3 + removed do redis.call('HDEL', key, ARGV[i]) end \
+            for i = 4 + removed, #ARGV, 1000 do \
+                redis.call('HSET', key, unpack(ARGV, i, math.min(i + 999, #ARGV))) \
+            end \
+            return 1",
+        )
+        .arg(1)
+        .arg(key)
+        .arg(SCHEMA_VERSION)
+        .arg(upgrade.stored.as_deref().unwrap_or_default())
+        .arg(upgrade.removed.len())
+        .arg(&upgrade.removed)
+        .arg(&upgrade.record);
+
+        cmd
+    }
+
+    /// Apply time to live policies of `M` to `key`
+    fn expire<M: HashModel>(pipe: &mut Pipeline, key: &str) {
+        if let Some(ttl) = M::_ttl() {
+            pipe.cmd("PEXPIRE")
+                .arg(key)
+                .arg(ttl.as_millis() as u64)
+                .ignore();
+        }
+
+        for (field, ttl) in M::_field_ttls() {
+            pipe.add_command(expire_field(key, field, ttl.as_millis() as u64))
+                .ignore();
+        }
+    }
+
+    /// Expire `field` of `key` after given milliseconds
+    pub fn expire_field(key: &str, field: &str, millis: u64) -> Cmd {
+        let mut cmd = redis::cmd("HPEXPIRE");
+        cmd.arg(key).arg(millis).arg("FIELDS").arg(1).arg(field);
+
+        cmd
+    }
+
+    /// Get remaining time to live of `field` of `key` in milliseconds
+    #[cfg(feature = "hash-field-ttl")]
+    pub fn field_pttl(key: &str, field: &str) -> Cmd {
+        let mut cmd = redis::cmd("HPTTL");
+        cmd.arg(key).arg("FIELDS").arg(1).arg(field);
+
+        cmd
     }
 
     /// Get a list of all primary keys for current type
-    pub fn get<M: RedisModel>(pk: impl AsRef<str>) -> RedisResult<Cmd> {
-        let pk = pk.as_ref();
+    pub fn get<M: RedisModel>(ns: &Namespace, pk: impl ToPk) -> RedisResult<Cmd> {
+        let pk = pk.to_pk();
 
         let mut cmd = redis::cmd("HGETALL");
 
-        if M::_is_pk_fmt(pk) {
-            cmd.arg(pk);
-        } else {
-            cmd.arg(M::_fmt_pk(pk));
-        }
+        cmd.arg(M::_fmt_key(ns, &pk));
 
         Ok(cmd)
     }
 
     /// Get a list of all primary keys for current type
-    pub fn all_pks<M: RedisModel>() -> RedisResult<Cmd> {
-        let pattern = format!("{}:*", M::_prefix_key());
-
+    pub fn all_pks<M: RedisModel>(ns: &Namespace) -> RedisResult<Cmd> {
         let mut cmd = redis::cmd("SCAN");
-        cmd.cursor_arg(0).arg("MATCH").arg(pattern);
+        cmd.cursor_arg(0)
+            .arg("MATCH")
+            .arg(M::_scan_pattern(ns))
+            .arg("TYPE")
+            .arg(super::KEY_TYPE);
 
         Ok(cmd)
     }
 
-    /// Delete by given pk
-    pub fn delete<M: RedisModel>(pk: impl AsRef<str>) -> RedisResult<Cmd> {
-        let pk = pk.as_ref();
+    /// Check whether a record exists for given pk
+    pub fn exists<M: RedisModel>(ns: &Namespace, pk: impl ToPk) -> RedisResult<Cmd> {
+        let pk = pk.to_pk();
 
-        let mut cmd = redis::cmd("DEL");
+        let mut cmd = redis::cmd("EXISTS");
+
+        cmd.arg(M::_fmt_key(ns, &pk));
+
+        Ok(cmd)
+    }
 
-        if M::_is_pk_fmt(pk) {
-            cmd.arg(pk);
-        } else {
-            cmd.arg(M::_fmt_pk(pk));
+    /// Delete by given pk, soft deleting records of soft delete models
+    pub fn delete<M: RedisModel>(ns: &Namespace, pk: impl ToPk) -> RedisResult<Cmd> {
+        if M::_soft_delete().is_some() {
+            return soft_delete::delete::<M>(&soft_delete::HASH, ns, pk);
         }
 
+        let pk = pk.to_pk();
+
+        let mut cmd = redis::cmd("DEL");
+
+        cmd.arg(M::_fmt_key(ns, &pk));
+
         Ok(cmd)
     }
+
+    /// Restore soft deleted record of given pk
+    pub fn restore<M: RedisModel>(ns: &Namespace, pk: impl ToPk) -> RedisResult<Cmd> {
+        soft_delete::restore::<M>(&soft_delete::HASH, ns, pk)
+    }
+}
+
+/// HGETALL replies with an empty bulk for missing keys, treat that as no record.
+/// Soft deleted records are treated as no record as well.
+fn parse_from_find_resp<M>(resp: Value) -> RedisResult<Option<M>>
+where
+    M: RedisModel + FromRedisValue,
+{
+    match resp {
+        Value::Nil => Ok(None),
+        Value::Bulk(ref bulk) if bulk.is_empty() => Ok(None),
+        ref resp if is_soft_deleted::<M>(resp) => Ok(None),
+        resp => M::from_redis_value(&resp).map(Some),
+    }
+}
+
+/// Parse HGETALL reply, failing for soft deleted records.
+fn parse_from_get_resp<M>(resp: Value) -> RedisResult<M>
+where
+    M: RedisModel + FromRedisValue,
+{
+    if is_soft_deleted::<M>(&resp) {
+        return Err(soft_delete::deleted_error());
+    }
+
+    M::from_redis_value(&resp)
+}
+
+/// Check whether HGETALL reply holds a soft deleted record of a soft delete model.
+fn is_soft_deleted<M: RedisModel>(resp: &Value) -> bool {
+    let Value::Bulk(bulk) = resp else {
+        return false;
+    };
+
+    M::_soft_delete().is_some()
+        && bulk
+            .chunks(2)
+            .any(|pair| matches!(&pair[0], Value::Data(field) if field == DELETED_AT.as_bytes()))
+}
+
+/// Audit XADD of `op` on record `pk`, diffing its HGETALL reply `old` against `new` data.
+/// With `merge`, stored fields missing from `new` are kept. Deletes of no record aren't audited.
+fn audit<'a, M: HashModel + 'a>(
+    ns: &'a Namespace,
+    pk: &'a str,
+    op: AuditOp,
+    new: Option<&M>,
+    merge: bool,
+) -> impl Fn(Value) -> RedisResult<Option<Cmd>> + Send + 'a {
+    let new = new
+        .map(|data| fields(data.to_redis_args()))
+        .unwrap_or_default();
+
+    move |old| {
+        if op == AuditOp::Delete && parse_from_find_resp::<M>(old.clone())?.is_none() {
+            return Ok(None);
+        }
+
+        let mut old = fields(Vec::<Vec<u8>>::from_redis_value(&old)?);
+        if merge {
+            old.retain(|field, _| new.contains_key(field));
+        }
+
+        let record = AuditRecord::diff::<M>(pk, op, old, new.clone());
+        audit::xadd::<M>(ns, &M::_fmt_key(ns, pk), &record)
+    }
+}
+
+/// Collect flat field-value pairs of a hash.
+fn fields(args: Vec<Vec<u8>>) -> BTreeMap<String, String> {
+    args.chunks(2)
+        .filter_map(|pair| match pair {
+            [field, value] => Some((
+                String::from_utf8_lossy(field).into_owned(),
+                String::from_utf8_lossy(value).into_owned(),
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Record of an older schema version upgraded in memory.
+struct Upgrade {
+    /// Stored schema version, `None` for records saved before versioning
+    stored: Option<String>,
+    /// Fields removed by migrations
+    removed: Vec<String>,
+    /// Upgraded fields, including the new schema version
+    record: HashRecord,
+}
+
+impl Upgrade {
+    fn to_value(&self) -> Value {
+        self.record
+            .iter()
+            .flat_map(|(field, value)| [field, value])
+            .map(|s| Value::Data(s.clone().into_bytes()))
+            .collect::<Vec<_>>()
+            .pipe(Value::Bulk)
+    }
+}
+
+/// Upgrade HGETALL reply of an older schema version of `M` in memory, `None` if up to date.
+fn upgrade<M: HashModel>(resp: &Value) -> RedisResult<Option<Upgrade>> {
+    let Some(version) = M::_schema_version() else {
+        return Ok(None);
+    };
+
+    let mut record = fields(Vec::<Vec<u8>>::from_redis_value(resp)?);
+    if record.is_empty() {
+        return Ok(None);
+    }
+
+    let stored = record.remove(SCHEMA_VERSION);
+    let from = migration::parse_version(stored.as_deref())?;
+    if from >= version {
+        return Ok(None);
+    }
+
+    let old_fields = record.keys().cloned().collect::<Vec<_>>();
+    M::migrations().upgrade(&mut record, from, version)?;
+
+    let removed = old_fields
+        .into_iter()
+        .filter(|field| !record.contains_key(field))
+        .collect();
+    record.insert(SCHEMA_VERSION.to_owned(), version.to_string());
+
+    Ok(Some(Upgrade {
+        stored,
+        removed,
+        record,
+    }))
+}
+
+/// Upgrade HGETALL reply of an older schema version of `M` in memory only.
+fn upgraded<M: HashModel>(resp: Value) -> RedisResult<Value> {
+    Ok(match upgrade::<M>(&resp)? {
+        Some(upgrade) => upgrade.to_value(),
+        None => resp,
+    })
+}
+
+/// Command persisting upgrade of HGETALL reply of record at `key`, `None` if up to date.
+fn upgrade_cmd<M: HashModel>(key: &str, resp: Value) -> RedisResult<Option<Cmd>> {
+    Ok(upgrade::<M>(&resp)?.map(|upgrade| cmds::upgrade(key, &upgrade)))
+}
+
+/// Read-through cache of hashes of a given model.
+fn cache_spec<M: HashModel>() -> CacheSpec<M> {
+    CacheSpec {
+        fetch: |ns, key| cmds::get::<M>(ns, key),
+        parse: |resp| parse_from_find_resp(upgraded::<M>(resp)?),
+        save: |key, data| cmds::save(key, data),
+    }
+}
+
+/// Batched scan over all hashes of a given model.
+fn scan_spec<M: HashModel>() -> ScanSpec<M> {
+    ScanSpec {
+        key_type: KEY_TYPE,
+        fetch: |ns, key| cmds::get::<M>(ns, key),
+        parse: |resp| parse_from_find_resp(upgraded::<M>(resp)?),
+    }
 }
@@ -1,7 +1,17 @@
-use super::cmds;
-use crate::{RedisModel, RedisSearchModel};
-use redis::ConnectionLike;
+use super::{
+    audit, cache_spec, cmds, parse_from_find_resp, parse_from_get_resp, scan_spec, upgrade,
+    upgrade_cmd, KEY_TYPE,
+};
+use crate::audit::AuditOp;
+use crate::migration::{HashRecord, MigrationProgress, Migrations};
+use crate::primary_key::ensure_pk;
+use crate::redis_model::parse_pttl;
+use crate::redissearch_model;
+use crate::scan::{self, DEFAULT_BATCH_SIZE};
+use crate::soft_delete;
+use crate::{CachedModel, Namespace, RedisModel, RedisSearchModel, ToPk, WithNamespace};
 use redis::{FromRedisValue, RedisResult, ToRedisArgs};
+use std::time::{Duration, SystemTime};
 
 /// Hash Object Model
 pub trait HashModel: RedisModel + RedisSearchModel + ToRedisArgs + FromRedisValue {
@@ -11,35 +21,261 @@ pub trait HashModel: RedisModel + RedisSearchModel + ToRedisArgs + FromRedisValu
         <Self as RedisModel>::_prefix_key()
     }
 
+    /// Get per-field time to live applied on every save, set with `#[redis(ttl = "...")]`
+    fn _field_ttls() -> &'static [(&'static str, Duration)] {
+        &[]
+    }
+
     /// Redis search schema
     fn redissearch_schema() -> &'static str {
         <Self as RedisSearchModel>::_REDIS_SEARCH_SCHEMA
     }
 
-    /// Save Self into redis database
-    fn save<C: ConnectionLike>(&mut self, conn: &mut C) -> RedisResult<()> {
-        self._ensure_pk();
-        let key = self._get_redis_key();
-        cmds::save(key, self)?.query(conn)
+    /// Redis search schema with its `PREFIX` scoped to given namespace
+    fn redissearch_schema_in(ns: &Namespace) -> String {
+        Self::_redis_search_schema(&ns.model_prefix(Self::_prefix_key()))
+    }
+
+    /// Save Self into redis database, replacing any previously stored fields.
+    /// Note: replacing the record clears its expiry.
+    fn save<C: WithNamespace>(&mut self, conn: &mut C) -> RedisResult<()> {
+        ensure_pk(self, conn)?;
+        let key = self._get_redis_key(conn.namespace());
+        let pipe = cmds::save(key.clone(), self)?;
+
+        if Self::_audit_stream().is_none() {
+            return pipe.query(conn.connection());
+        }
+
+        let (ns, pk) = (conn.namespace().clone(), self._get_pk());
+        let get = cmds::get::<Self>(&ns, &pk)?;
+        let audit = audit(&ns, &pk, AuditOp::Save, Some(self), false);
+        audit::watch(conn.connection(), &key, get, pipe, audit)
+    }
+
+    /// Save Self into redis database, keeping stored fields missing from Self
+    fn save_merge<C: WithNamespace>(&mut self, conn: &mut C) -> RedisResult<()> {
+        ensure_pk(self, conn)?;
+        let key = self._get_redis_key(conn.namespace());
+        let pipe = cmds::merge(key.clone(), self)?;
+
+        if Self::_audit_stream().is_none() {
+            return pipe.query(conn.connection());
+        }
+
+        let (ns, pk) = (conn.namespace().clone(), self._get_pk());
+        let get = cmds::get::<Self>(&ns, &pk)?;
+        let audit = audit(&ns, &pk, AuditOp::Save, Some(self), true);
+        audit::watch(conn.connection(), &key, get, pipe, audit)
+    }
+
+    /// Read-through cache of Self in front of another source of truth, see [`CachedModel`]
+    fn cache() -> CachedModel<Self> {
+        CachedModel::new(cache_spec::<Self>())
+    }
+
+    /// Iterate all records of current type, fetching them in pipelined batches
+    fn all<'a, C>(conn: &'a mut C) -> Box<dyn Iterator<Item = RedisResult<Self>> + 'a>
+    where
+        C: WithNamespace,
+        Self: 'a,
+    {
+        Self::scan(DEFAULT_BATCH_SIZE, conn)
+    }
+
+    /// Iterate all records of current type, fetching `batch_size` records per round trip
+    fn scan<'a, C>(
+        batch_size: usize,
+        conn: &'a mut C,
+    ) -> Box<dyn Iterator<Item = RedisResult<Self>> + 'a>
+    where
+        C: WithNamespace,
+        Self: 'a,
+    {
+        scan::scan(scan_spec::<Self>(), batch_size, conn)
     }
 
     /// Get a list of all primary keys for current type
-    fn all_pks<C: ConnectionLike>(conn: &mut C) -> RedisResult<redis::Iter<'_, String>> {
-        cmds::all_pks::<Self>()?.iter(conn)
+    fn all_pks<C: WithNamespace>(conn: &mut C) -> RedisResult<redis::Iter<'_, String>> {
+        cmds::all_pks::<Self>(conn.namespace())?.iter(conn.connection())
     }
 
     /// Get a list of all primary keys for current type
-    fn get<C: ConnectionLike>(pk: impl AsRef<str>, conn: &mut C) -> RedisResult<Self> {
-        cmds::get::<Self>(pk)?.query(conn)
+    fn get<C: WithNamespace>(pk: impl ToPk, conn: &mut C) -> RedisResult<Self> {
+        let key = Self::_fmt_key(conn.namespace(), &pk.to_pk());
+        let mut resp = cmds::get::<Self>(conn.namespace(), &key)?.query(conn.connection())?;
+
+        if let Some(upgrade) = upgrade::<Self>(&resp)? {
+            cmds::upgrade(&key, &upgrade).query::<()>(conn.connection())?;
+            resp = upgrade.to_value();
+        }
+
+        parse_from_get_resp(resp)
+    }
+
+    /// Find by given pk, returning `None` if no record exists
+    fn find_by_pk<C: WithNamespace>(pk: impl ToPk, conn: &mut C) -> RedisResult<Option<Self>> {
+        let key = Self::_fmt_key(conn.namespace(), &pk.to_pk());
+        let mut resp = cmds::get::<Self>(conn.namespace(), &key)?.query(conn.connection())?;
+
+        if let Some(upgrade) = upgrade::<Self>(&resp)? {
+            cmds::upgrade(&key, &upgrade).query::<()>(conn.connection())?;
+            resp = upgrade.to_value();
+        }
+
+        parse_from_find_resp(resp)
     }
 
-    /// Delete by given pk
-    fn delete<C: ConnectionLike>(pk: impl AsRef<str>, conn: &mut C) -> RedisResult<()> {
-        cmds::delete::<Self>(pk)?.query(conn)
+    /// Check whether a record exists for given pk, excluding soft deleted records
+    fn exists<C: WithNamespace>(pk: impl ToPk, conn: &mut C) -> RedisResult<bool> {
+        if Self::_soft_delete().is_some() {
+            return Ok(Self::find_by_pk(pk, conn)?.is_some());
+        }
+
+        cmds::exists::<Self>(conn.namespace(), pk)?.query(conn.connection())
+    }
+
+    /// Delete by given pk, soft deleting it for `#[redis(soft_delete)]` models
+    fn delete<C: WithNamespace>(pk: impl ToPk, conn: &mut C) -> RedisResult<()> {
+        let pk = pk.to_pk();
+        let mut pipe = redis::pipe();
+        pipe.atomic()
+            .add_command(cmds::delete::<Self>(conn.namespace(), &pk)?)
+            .ignore();
+
+        if Self::_audit_stream().is_none() {
+            return pipe.query(conn.connection());
+        }
+
+        let ns = conn.namespace().clone();
+        let (key, get) = (Self::_fmt_key(&ns, &pk), cmds::get::<Self>(&ns, &pk)?);
+        let audit = audit::<Self>(&ns, &pk, AuditOp::Delete, None, false);
+        audit::watch(conn.connection(), &key, get, pipe, audit)
+    }
+
+    /// Restore soft deleted record of given pk, returning whether one was found
+    fn restore<C: WithNamespace>(pk: impl ToPk, conn: &mut C) -> RedisResult<bool> {
+        cmds::restore::<Self>(conn.namespace(), pk)?.query(conn.connection())
+    }
+
+    /// Permanently delete records soft deleted more than `older_than` ago,
+    /// returning the number of deleted records
+    fn purge_deleted<C: WithNamespace>(older_than: Duration, conn: &mut C) -> RedisResult<usize> {
+        let storage = &soft_delete::HASH;
+        scan::purge_deleted::<Self, _>(KEY_TYPE, storage, older_than, DEFAULT_BATCH_SIZE, conn)
+    }
+
+    /// Migrations upgrading records of older schema versions, set with
+    /// `#[redis(migrations = "path::to::fn")]`
+    fn migrations() -> Migrations<HashRecord> {
+        Migrations::new()
+    }
+
+    /// Upgrade stored records of older schema versions in batches, starting from SCAN `cursor`
+    /// (`0`, or [`MigrationProgress::cursor`] to resume), reporting progress after each batch
+    fn migrate_all<C, P>(
+        cursor: u64,
+        mut on_progress: P,
+        conn: &mut C,
+    ) -> RedisResult<MigrationProgress>
+    where
+        C: WithNamespace,
+        P: FnMut(&MigrationProgress),
+    {
+        let spec = scan_spec::<Self>();
+        scan::migrate_all(
+            spec,
+            upgrade_cmd::<Self>,
+            cursor,
+            DEFAULT_BATCH_SIZE,
+            &mut on_progress,
+            conn,
+        )
+    }
+
+    /// Count all records of current type by scanning their keys, excluding soft deleted records,
+    /// approximately when records are added or deleted meanwhile. See [`Self::count_indexed`]
+    /// for an exact count from a search index.
+    fn count<C: WithNamespace>(conn: &mut C) -> RedisResult<usize> {
+        let storage = Self::_soft_delete().map(|_| &soft_delete::HASH);
+        scan::count::<Self, _>(KEY_TYPE, storage, DEFAULT_BATCH_SIZE, conn)
+    }
+
+    /// Count records indexed by search `index`, e.g. created from [`Self::redissearch_schema_in`],
+    /// with FT.SEARCH instead of scanning. Unlike [`Self::count`] it's exact, and excludes soft
+    /// deleted records through the schema FILTER.
+    fn count_indexed<C: WithNamespace>(index: impl AsRef<str>, conn: &mut C) -> RedisResult<usize> {
+        redissearch_model::cmds::count(index.as_ref())
+            .query(conn.connection())
+            .and_then(redissearch_model::transformers::count)
+    }
+
+    /// Permanently delete all records of current type in batches, soft deleted records included,
+    /// returning the number of deleted records
+    fn delete_all<C: WithNamespace>(conn: &mut C) -> RedisResult<usize> {
+        Self::delete_all_with(DEFAULT_BATCH_SIZE, false, conn)
+    }
+
+    /// Permanently delete all records of current type, soft deleted records included, unlinking
+    /// `batch_size` records per round trip.
+    /// With `dry_run`, nothing is deleted and the number of records to be deleted is returned.
+    fn delete_all_with<C: WithNamespace>(
+        batch_size: usize,
+        dry_run: bool,
+        conn: &mut C,
+    ) -> RedisResult<usize> {
+        scan::unlink_all::<Self, _>(KEY_TYPE, batch_size, dry_run, conn)
     }
 
     /// Expire Self at given duration
-    fn expire<C: ConnectionLike>(&self, secs: usize, conn: &mut C) -> RedisResult<()> {
-        self._expire_cmd(secs)?.query(conn)
+    fn expire<C: WithNamespace>(&self, secs: usize, conn: &mut C) -> RedisResult<()> {
+        self._expire_cmd(conn.namespace(), secs)?
+            .query(conn.connection())
+    }
+
+    /// Expire Self at given point in time
+    fn expire_at<C: WithNamespace>(&self, at: SystemTime, conn: &mut C) -> RedisResult<()> {
+        self._expire_at_cmd(conn.namespace(), at)?
+            .query(conn.connection())
+    }
+
+    /// Remove expiry of Self, returning whether it had one
+    fn persist<C: WithNamespace>(&self, conn: &mut C) -> RedisResult<bool> {
+        self._persist_cmd(conn.namespace())?
+            .query(conn.connection())
+    }
+
+    /// Get remaining time to live of Self, `None` if it has no expiry or doesn't exist
+    fn ttl<C: WithNamespace>(&self, conn: &mut C) -> RedisResult<Option<Duration>> {
+        let millis = self._pttl_cmd(conn.namespace())?.query(conn.connection())?;
+
+        Ok(parse_pttl(millis))
+    }
+
+    /// Expire `field` of Self after given duration, requires redis 7.4
+    #[cfg(feature = "hash-field-ttl")]
+    fn expire_field<C: WithNamespace>(
+        &self,
+        field: &str,
+        ttl: Duration,
+        conn: &mut C,
+    ) -> RedisResult<()> {
+        let key = self._get_redis_key(conn.namespace());
+        cmds::expire_field(&key, field, ttl.as_millis() as u64)
+            .query::<Vec<i64>>(conn.connection())
+            .map(|_| ())
+    }
+
+    /// Get remaining time to live of `field` of Self, requires redis 7.4
+    #[cfg(feature = "hash-field-ttl")]
+    fn field_ttl<C: WithNamespace>(
+        &self,
+        field: &str,
+        conn: &mut C,
+    ) -> RedisResult<Option<Duration>> {
+        let key = self._get_redis_key(conn.namespace());
+        let millis: Vec<i64> = cmds::field_pttl(&key, field).query(conn.connection())?;
+
+        Ok(millis.first().copied().and_then(parse_pttl))
     }
 }
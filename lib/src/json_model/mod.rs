@@ -8,32 +8,85 @@ pub use r#async::JsonModel;
 #[cfg(not(feature = "aio"))]
 pub use sync::JsonModel;
 
-use redis::{ErrorKind, RedisError, RedisResult};
+use crate::audit::{self, AuditOp, AuditRecord};
+use crate::cache::CacheSpec;
+use crate::migration::{self, SCHEMA_VERSION};
+use crate::scan::ScanSpec;
+use crate::soft_delete::{self, DELETED_AT};
+use crate::{Namespace, RedisModel};
+use redis::{Cmd, ErrorKind, FromRedisValue, RedisError, RedisResult};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Redis TYPE of keys holding json documents.
+const KEY_TYPE: &str = "ReJSON-RL";
 
 mod cmds {
+    use super::Upgrade;
+    use crate::migration::SCHEMA_VERSION;
     use crate::redis_model::RedisModel;
-    use redis::{Cmd, RedisResult};
+    use crate::soft_delete;
+    use crate::{Namespace, ToPk};
+    use redis::{Cmd, Pipeline, RedisResult};
 
-    /// Save data into redis database
-    pub fn save<D: serde::Serialize>(key: String, data: &D) -> RedisResult<Cmd> {
-        let mut cmd = redis::cmd("JSON.SET");
+    /// Save data into redis database, applying time to live of `M` in the same transaction
+    pub fn save<M>(key: String, data: &M) -> RedisResult<Pipeline>
+    where
+        M: RedisModel + serde::Serialize,
+    {
+        let mut document = serde_json::to_value(data)?;
+        if let (Some(version), Some(fields)) = (M::_schema_version(), document.as_object_mut()) {
+            fields.insert(SCHEMA_VERSION.to_owned(), version.into());
+        }
 
-        cmd.arg(key).arg("$").arg(serde_json::to_string(data)?);
+        let mut pipe = redis::pipe();
+        pipe.atomic()
+            .cmd("JSON.SET")
+            .arg(&key)
+            .arg("$")
+            .arg(document.to_string())
+            .ignore();
 
-        Ok(cmd)
+        if let Some(ttl) = M::_ttl() {
+            pipe.cmd("PEXPIRE")
+                .arg(key)
+                .arg(ttl.as_millis() as u64)
+                .ignore();
+        }
+
+        Ok(pipe)
+    }
+
+    /// Persist upgrade of document at `key`, unless it changed since read with `stored` version.
+    /// Replies whether the document was upgraded.
+    pub fn upgrade(key: &str, upgrade: &Upgrade) -> Cmd {
+        let mut cmd = redis::cmd("EVAL");
+        cmd.arg(
+            "local key = KEYS[1] \
+            if redis.call('EXISTS', key) == 0 then return 0 end \
+            local raw = redis.call('JSON.GET', key, '$.' .. ARGV[1]) \
+            local stored = raw and cjson.decode(raw)[1] \
+            if (stored and tostring(stored) or '') ~= ARGV[2] then return 0 end \
+            redis.call('JSON.SET', key, '$', ARGV[3]) \
+            return 1",
+        )
+        .arg(1)
+        .arg(key)
+        .arg(SCHEMA_VERSION)
+        .arg(upgrade.stored.as_deref().unwrap_or_default())
+        .arg(upgrade.document.to_string());
+
+        cmd
     }
 
     /// Get a list of all primary keys for current type
-    pub fn get<M: RedisModel>(pk: impl AsRef<str>) -> RedisResult<Cmd> {
-        let pk = pk.as_ref();
+    pub fn get<M: RedisModel>(ns: &Namespace, pk: impl ToPk) -> RedisResult<Cmd> {
+        let pk = pk.to_pk();
 
         let mut cmd = redis::cmd("JSON.GET");
 
-        if M::_is_pk_fmt(pk) {
-            cmd.arg(pk);
-        } else {
-            cmd.arg(M::_fmt_pk(pk));
-        }
+        cmd.arg(M::_fmt_key(ns, &pk));
 
         cmd.arg("$");
 
@@ -41,35 +94,81 @@ mod cmds {
     }
 
     /// Get a list of all primary keys for current type
-    pub fn all_pks<M: RedisModel>() -> RedisResult<Cmd> {
-        let pattern = format!("{}:*", M::_prefix_key());
-
+    pub fn all_pks<M: RedisModel>(ns: &Namespace) -> RedisResult<Cmd> {
         let mut cmd = redis::cmd("SCAN");
-        cmd.cursor_arg(0).arg("MATCH").arg(pattern);
+        cmd.cursor_arg(0)
+            .arg("MATCH")
+            .arg(M::_scan_pattern(ns))
+            .arg("TYPE")
+            .arg(super::KEY_TYPE);
 
         Ok(cmd)
     }
 
-    /// Delete by given pk
-    pub fn delete<M: RedisModel>(pk: impl AsRef<str>) -> RedisResult<Cmd> {
-        let pk = pk.as_ref();
+    /// Check whether a record exists for given pk
+    pub fn exists<M: RedisModel>(ns: &Namespace, pk: impl ToPk) -> RedisResult<Cmd> {
+        let pk = pk.to_pk();
 
-        let mut cmd = redis::cmd("JSON.DEL");
+        let mut cmd = redis::cmd("EXISTS");
+
+        cmd.arg(M::_fmt_key(ns, &pk));
+
+        Ok(cmd)
+    }
 
-        if M::_is_pk_fmt(pk) {
-            cmd.arg(pk);
-        } else {
-            cmd.arg(M::_fmt_pk(pk));
+    /// Delete by given pk, soft deleting records of soft delete models
+    pub fn delete<M: RedisModel>(ns: &Namespace, pk: impl ToPk) -> RedisResult<Cmd> {
+        if M::_soft_delete().is_some() {
+            return soft_delete::delete::<M>(&soft_delete::JSON, ns, pk);
         }
 
+        let pk = pk.to_pk();
+
+        let mut cmd = redis::cmd("JSON.DEL");
+
+        cmd.arg(M::_fmt_key(ns, &pk));
+
         cmd.arg("$");
 
         Ok(cmd)
     }
+
+    /// Restore soft deleted record of given pk
+    pub fn restore<M: RedisModel>(ns: &Namespace, pk: impl ToPk) -> RedisResult<Cmd> {
+        soft_delete::restore::<M>(&soft_delete::JSON, ns, pk)
+    }
 }
 
-fn parse_from_get_resp<D: for<'de> serde::Deserialize<'de>>(resp: String) -> RedisResult<D> {
-    let value = serde_json::from_str::<'_, serde_json::Value>(&resp)
+fn parse_from_get_resp<M: RedisModel + DeserializeOwned>(resp: String) -> RedisResult<M> {
+    let value = parse_document(&resp)?;
+
+    if is_soft_deleted::<M>(&value) {
+        return Err(soft_delete::deleted_error());
+    }
+
+    serde_json::from_value(value).map_err(|e| e.into())
+}
+
+/// JSON.GET replies with nil for missing keys, treat that as no record.
+/// Soft deleted records are treated as no record as well.
+fn parse_from_find_resp<M: RedisModel + DeserializeOwned>(
+    resp: Option<String>,
+) -> RedisResult<Option<M>> {
+    let Some(resp) = resp else {
+        return Ok(None);
+    };
+
+    match parse_document(&resp)? {
+        value if is_soft_deleted::<M>(&value) => Ok(None),
+        value => serde_json::from_value(value)
+            .map(Some)
+            .map_err(|e| e.into()),
+    }
+}
+
+/// Get first document of JSON.GET reply.
+fn parse_document(resp: &str) -> RedisResult<serde_json::Value> {
+    serde_json::from_str::<'_, serde_json::Value>(resp)
         .map_err(RedisError::from)?
         .as_array()
         .and_then(|f| f.first())
@@ -80,7 +179,135 @@ fn parse_from_get_resp<D: for<'de> serde::Deserialize<'de>>(resp: String) -> Red
                 "expect an array with at least one item",
                 format!("got {:#?}", resp),
             ))
-        })?;
+        })
+}
 
-    serde_json::from_value(value).map_err(|e| e.into())
+/// Check whether document holds a soft deleted record of a soft delete model.
+fn is_soft_deleted<M: RedisModel>(value: &serde_json::Value) -> bool {
+    M::_soft_delete().is_some() && value.get(DELETED_AT).is_some()
+}
+
+/// Audit XADD of `op` on record `pk`, diffing top-level fields of its JSON.GET reply `old`
+/// against `new` data. Deletes of no record aren't audited.
+fn audit<'a, M: RedisModel + Serialize + DeserializeOwned + 'a>(
+    ns: &'a Namespace,
+    pk: &'a str,
+    op: AuditOp,
+    new: Option<&M>,
+) -> RedisResult<impl Fn(Option<String>) -> RedisResult<Option<Cmd>> + Send + 'a> {
+    let new = match new {
+        Some(data) => fields(serde_json::to_value(data)?),
+        None => BTreeMap::new(),
+    };
+
+    Ok(move |old: Option<String>| {
+        if op == AuditOp::Delete && parse_from_find_resp::<M>(old.clone())?.is_none() {
+            return Ok(None);
+        }
+
+        let old = match old {
+            Some(resp) => fields(parse_document(&resp)?),
+            None => BTreeMap::new(),
+        };
+
+        let record = AuditRecord::diff::<M>(pk, op, old, new.clone());
+        audit::xadd::<M>(ns, &M::_fmt_key(ns, pk), &record)
+    })
+}
+
+/// Collect top-level fields of a document, with values encoded as json.
+fn fields(value: serde_json::Value) -> BTreeMap<String, String> {
+    match value {
+        serde_json::Value::Object(map) => map
+            .into_iter()
+            .map(|(field, value)| (field, value.to_string()))
+            .collect(),
+        _ => BTreeMap::new(),
+    }
+}
+
+/// Document of an older schema version upgraded in memory.
+struct Upgrade {
+    /// Stored schema version, `None` for documents saved before versioning
+    stored: Option<String>,
+    /// Upgraded document, including the new schema version
+    document: serde_json::Value,
+}
+
+impl Upgrade {
+    /// Get upgraded document as JSON.GET reply
+    fn to_resp(&self) -> String {
+        serde_json::Value::Array(vec![self.document.clone()]).to_string()
+    }
+}
+
+/// Upgrade JSON.GET reply of an older schema version of `M` in memory, `None` if up to date.
+fn upgrade<M: JsonModel>(resp: &str) -> RedisResult<Option<Upgrade>> {
+    let Some(version) = M::_schema_version() else {
+        return Ok(None);
+    };
+
+    let mut document = parse_document(resp)?;
+    let Some(fields) = document.as_object_mut() else {
+        return Ok(None);
+    };
+
+    let stored = fields.remove(SCHEMA_VERSION).map(|stored| match stored {
+        serde_json::Value::String(stored) => stored,
+        stored => stored.to_string(),
+    });
+    let from = migration::parse_version(stored.as_deref())?;
+    if from >= version {
+        return Ok(None);
+    }
+
+    M::migrations().upgrade(&mut document, from, version)?;
+    if let Some(fields) = document.as_object_mut() {
+        fields.insert(SCHEMA_VERSION.to_owned(), version.into());
+    }
+
+    Ok(Some(Upgrade { stored, document }))
+}
+
+/// Upgrade JSON.GET reply of an older schema version of `M` in memory only.
+fn upgraded<M: JsonModel>(resp: Option<String>) -> RedisResult<Option<String>> {
+    let Some(resp) = resp else {
+        return Ok(None);
+    };
+
+    Ok(Some(match upgrade::<M>(&resp)? {
+        Some(upgrade) => upgrade.to_resp(),
+        None => resp,
+    }))
+}
+
+/// Command persisting upgrade of JSON.GET reply of document at `key`, `None` if up to date.
+fn upgrade_cmd<M: JsonModel>(key: &str, resp: redis::Value) -> RedisResult<Option<Cmd>> {
+    let Some(resp) = Option::<String>::from_redis_value(&resp)? else {
+        return Ok(None);
+    };
+
+    Ok(upgrade::<M>(&resp)?.map(|upgrade| cmds::upgrade(key, &upgrade)))
+}
+
+/// Read-through cache of json documents of a given model.
+fn cache_spec<M: JsonModel>() -> CacheSpec<M> {
+    CacheSpec {
+        fetch: |ns, key| cmds::get::<M>(ns, key),
+        parse: |resp| {
+            parse_from_find_resp(upgraded::<M>(FromRedisValue::from_redis_value(&resp)?)?)
+        },
+        save: |key, data| cmds::save(key, data),
+    }
+}
+
+/// Batched scan over all json documents of a given model.
+fn scan_spec<M: JsonModel>() -> ScanSpec<M> {
+    ScanSpec {
+        key_type: KEY_TYPE,
+        fetch: |ns, key| cmds::get::<M>(ns, key),
+        parse: |resp| {
+            parse_from_find_resp(upgraded::<M>(FromRedisValue::from_redis_value(&resp)?)?)
+        },
+    }
 }
@@ -1,7 +1,18 @@
-use super::{cmds, parse_from_get_resp};
-use crate::{RedisModel, RedisSearchModel};
-use redis::{ConnectionLike, Iter, RedisResult};
+use super::{
+    audit, cache_spec, cmds, parse_from_find_resp, parse_from_get_resp, scan_spec, upgrade,
+    upgrade_cmd, KEY_TYPE,
+};
+use crate::audit::AuditOp;
+use crate::migration::{JsonRecord, MigrationProgress, Migrations};
+use crate::primary_key::ensure_pk;
+use crate::redis_model::parse_pttl;
+use crate::redissearch_model;
+use crate::scan::{self, DEFAULT_BATCH_SIZE};
+use crate::soft_delete;
+use crate::{CachedModel, Namespace, RedisModel, RedisSearchModel, ToPk, WithNamespace};
+use redis::{Iter, RedisResult};
 use serde::{de::DeserializeOwned, Serialize};
+use std::time::{Duration, SystemTime};
 
 /// Hash Object Model
 pub trait JsonModel: RedisModel + RedisSearchModel + Serialize + DeserializeOwned {
@@ -10,6 +21,11 @@ pub trait JsonModel: RedisModel + RedisSearchModel + Serialize + DeserializeOwne
         <Self as RedisSearchModel>::_REDIS_SEARCH_SCHEMA
     }
 
+    /// Redis search schema with its `PREFIX` scoped to given namespace
+    fn redissearch_schema_in(ns: &Namespace) -> String {
+        Self::_redis_search_schema(&ns.model_prefix(Self::_prefix_key()))
+    }
+
     /// Get Redis key to be used in storing HashModel object.
     /// This should by default that HashModel name in lowercase.
     fn redis_prefix() -> &'static str {
@@ -19,55 +35,231 @@ pub trait JsonModel: RedisModel + RedisSearchModel + Serialize + DeserializeOwne
     /// Save Self into redis database
     fn save<C>(&mut self, conn: &mut C) -> RedisResult<()>
     where
-        C: ConnectionLike + Send,
+        C: WithNamespace + Send,
     {
-        self._ensure_pk();
-        let cmd = cmds::save(self._get_redis_key(), self)?;
+        ensure_pk(self, conn)?;
+        let key = self._get_redis_key(conn.namespace());
+        let pipe = cmds::save(key.clone(), self)?;
+
+        if Self::_audit_stream().is_none() {
+            return pipe.query(conn.connection());
+        }
+
+        let (ns, pk) = (conn.namespace().clone(), self._get_pk());
+        let get = cmds::get::<Self>(&ns, &pk)?;
+        let audit = audit(&ns, &pk, AuditOp::Save, Some(self))?;
+        audit::watch(conn.connection(), &key, get, pipe, audit)
+    }
+
+    /// Read-through cache of Self in front of another source of truth, see [`CachedModel`]
+    fn cache() -> CachedModel<Self> {
+        CachedModel::new(cache_spec::<Self>())
+    }
 
-        cmd.query(conn)
+    /// Iterate all records of current type, fetching them in pipelined batches
+    fn all<'a, C>(conn: &'a mut C) -> Box<dyn Iterator<Item = RedisResult<Self>> + 'a>
+    where
+        C: WithNamespace,
+        Self: 'a,
+    {
+        Self::scan(DEFAULT_BATCH_SIZE, conn)
+    }
+
+    /// Iterate all records of current type, fetching `batch_size` records per round trip
+    fn scan<'a, C>(
+        batch_size: usize,
+        conn: &'a mut C,
+    ) -> Box<dyn Iterator<Item = RedisResult<Self>> + 'a>
+    where
+        C: WithNamespace,
+        Self: 'a,
+    {
+        scan::scan(scan_spec::<Self>(), batch_size, conn)
     }
 
     /// Get a list of all primary keys for current type
     fn all_pks<C>(conn: &mut C) -> RedisResult<Iter<'_, String>>
     where
-        C: ConnectionLike + Send,
+        C: WithNamespace + Send,
     {
-        let cmd = cmds::all_pks::<Self>()?;
+        let cmd = cmds::all_pks::<Self>(conn.namespace())?;
 
-        cmd.iter(conn)
+        cmd.iter(conn.connection())
     }
 
     /// Get a list of all primary keys for current type
     fn get<S, C>(pk: S, conn: &mut C) -> RedisResult<Self>
     where
-        S: AsRef<str> + Send,
-        C: ConnectionLike + Send,
+        S: ToPk + Send,
+        C: WithNamespace + Send,
     {
-        let pk = pk.as_ref();
-        let cmd = cmds::get::<Self>(pk)?;
-        let resp = cmd.query(conn)?;
+        let key = Self::_fmt_key(conn.namespace(), &pk.to_pk());
+        let cmd = cmds::get::<Self>(conn.namespace(), &key)?;
+        let mut resp: String = cmd.query(conn.connection())?;
+
+        if let Some(upgrade) = upgrade::<Self>(&resp)? {
+            cmds::upgrade(&key, &upgrade).query::<()>(conn.connection())?;
+            resp = upgrade.to_resp();
+        }
 
         parse_from_get_resp(resp)
     }
 
-    /// Delete by given pk
+    /// Find by given pk, returning `None` if no record exists
+    fn find_by_pk<S, C>(pk: S, conn: &mut C) -> RedisResult<Option<Self>>
+    where
+        S: ToPk + Send,
+        C: WithNamespace + Send,
+    {
+        let key = Self::_fmt_key(conn.namespace(), &pk.to_pk());
+        let cmd = cmds::get::<Self>(conn.namespace(), &key)?;
+        let mut resp: Option<String> = cmd.query(conn.connection())?;
+
+        if let Some(upgrade) = resp.as_deref().map(upgrade::<Self>).transpose()?.flatten() {
+            cmds::upgrade(&key, &upgrade).query::<()>(conn.connection())?;
+            resp = Some(upgrade.to_resp());
+        }
+
+        parse_from_find_resp(resp)
+    }
+
+    /// Check whether a record exists for given pk, excluding soft deleted records
+    fn exists<S, C>(pk: S, conn: &mut C) -> RedisResult<bool>
+    where
+        S: ToPk + Send,
+        C: WithNamespace + Send,
+    {
+        if Self::_soft_delete().is_some() {
+            return Ok(Self::find_by_pk(pk, conn)?.is_some());
+        }
+
+        let cmd = cmds::exists::<Self>(conn.namespace(), pk)?;
+
+        cmd.query(conn.connection())
+    }
+
+    /// Delete by given pk, soft deleting it for `#[redis(soft_delete)]` models
     fn delete<S, C>(pk: S, conn: &mut C) -> RedisResult<()>
     where
-        S: AsRef<str> + Send,
-        C: ConnectionLike + Send,
+        S: ToPk + Send,
+        C: WithNamespace + Send,
     {
-        let cmd = cmds::delete::<Self>(pk)?;
+        let pk = pk.to_pk();
+        let mut pipe = redis::pipe();
+        pipe.atomic()
+            .add_command(cmds::delete::<Self>(conn.namespace(), &pk)?)
+            .ignore();
 
-        cmd.query(conn)
+        if Self::_audit_stream().is_none() {
+            return pipe.query(conn.connection());
+        }
+
+        let ns = conn.namespace().clone();
+        let (key, get) = (Self::_fmt_key(&ns, &pk), cmds::get::<Self>(&ns, &pk)?);
+        let audit = audit::<Self>(&ns, &pk, AuditOp::Delete, None)?;
+        audit::watch(conn.connection(), &key, get, pipe, audit)
+    }
+
+    /// Restore soft deleted record of given pk, returning whether one was found
+    fn restore<C: WithNamespace>(pk: impl ToPk, conn: &mut C) -> RedisResult<bool> {
+        cmds::restore::<Self>(conn.namespace(), pk)?.query(conn.connection())
+    }
+
+    /// Permanently delete records soft deleted more than `older_than` ago,
+    /// returning the number of deleted records
+    fn purge_deleted<C: WithNamespace>(older_than: Duration, conn: &mut C) -> RedisResult<usize> {
+        let storage = &soft_delete::JSON;
+        scan::purge_deleted::<Self, _>(KEY_TYPE, storage, older_than, DEFAULT_BATCH_SIZE, conn)
+    }
+
+    /// Migrations upgrading documents of older schema versions, set with
+    /// `#[redis(migrations = "path::to::fn")]`
+    fn migrations() -> Migrations<JsonRecord> {
+        Migrations::new()
+    }
+
+    /// Upgrade stored documents of older schema versions in batches, starting from SCAN `cursor`
+    /// (`0`, or [`MigrationProgress::cursor`] to resume), reporting progress after each batch
+    fn migrate_all<C, P>(
+        cursor: u64,
+        mut on_progress: P,
+        conn: &mut C,
+    ) -> RedisResult<MigrationProgress>
+    where
+        C: WithNamespace,
+        P: FnMut(&MigrationProgress),
+    {
+        let spec = scan_spec::<Self>();
+        scan::migrate_all(
+            spec,
+            upgrade_cmd::<Self>,
+            cursor,
+            DEFAULT_BATCH_SIZE,
+            &mut on_progress,
+            conn,
+        )
+    }
+
+    /// Count all records of current type by scanning their keys, excluding soft deleted records,
+    /// approximately when records are added or deleted meanwhile. See [`Self::count_indexed`]
+    /// for an exact count from a search index.
+    fn count<C: WithNamespace>(conn: &mut C) -> RedisResult<usize> {
+        let storage = Self::_soft_delete().map(|_| &soft_delete::JSON);
+        scan::count::<Self, _>(KEY_TYPE, storage, DEFAULT_BATCH_SIZE, conn)
+    }
+
+    /// Count records indexed by search `index`, e.g. created from [`Self::redissearch_schema_in`],
+    /// with FT.SEARCH instead of scanning. Unlike [`Self::count`] it's exact, and excludes soft
+    /// deleted records through the schema FILTER.
+    fn count_indexed<C: WithNamespace>(index: impl AsRef<str>, conn: &mut C) -> RedisResult<usize> {
+        redissearch_model::cmds::count(index.as_ref())
+            .query(conn.connection())
+            .and_then(redissearch_model::transformers::count)
+    }
+
+    /// Permanently delete all records of current type in batches, soft deleted records included,
+    /// returning the number of deleted records
+    fn delete_all<C: WithNamespace>(conn: &mut C) -> RedisResult<usize> {
+        Self::delete_all_with(DEFAULT_BATCH_SIZE, false, conn)
+    }
+
+    /// Permanently delete all records of current type, soft deleted records included, unlinking
+    /// `batch_size` records per round trip.
+    /// With `dry_run`, nothing is deleted and the number of records to be deleted is returned.
+    fn delete_all_with<C: WithNamespace>(
+        batch_size: usize,
+        dry_run: bool,
+        conn: &mut C,
+    ) -> RedisResult<usize> {
+        scan::unlink_all::<Self, _>(KEY_TYPE, batch_size, dry_run, conn)
     }
 
     /// Expire Self at given duration
     fn expire<C>(&self, secs: usize, conn: &mut C) -> RedisResult<()>
     where
-        C: ConnectionLike + Send,
+        C: WithNamespace + Send,
     {
-        let cmd = self._expire_cmd(secs)?;
+        let cmd = self._expire_cmd(conn.namespace(), secs)?;
+
+        cmd.query(conn.connection())
+    }
+
+    /// Expire Self at given point in time
+    fn expire_at<C: WithNamespace>(&self, at: SystemTime, conn: &mut C) -> RedisResult<()> {
+        self._expire_at_cmd(conn.namespace(), at)?
+            .query(conn.connection())
+    }
+
+    /// Remove expiry of Self, returning whether it had one
+    fn persist<C: WithNamespace>(&self, conn: &mut C) -> RedisResult<bool> {
+        self._persist_cmd(conn.namespace())?
+            .query(conn.connection())
+    }
+
+    /// Get remaining time to live of Self, `None` if it has no expiry or doesn't exist
+    fn ttl<C: WithNamespace>(&self, conn: &mut C) -> RedisResult<Option<Duration>> {
+        let millis = self._pttl_cmd(conn.namespace())?.query(conn.connection())?;
 
-        cmd.query(conn)
+        Ok(parse_pttl(millis))
     }
 }
@@ -1,11 +1,24 @@
 #![deny(missing_docs, unstable_features)]
 #![doc = include_str!("../README.md")]
+mod audit;
+mod cache;
+mod cluster;
+mod consumer;
 mod hash_model;
 #[cfg(feature = "json")]
 mod json_model;
+#[doc(hidden)]
+pub mod map_field;
+mod migration;
+mod multi_stream;
+mod namespace;
+mod primary_key;
 mod redis_model;
 mod redissearch_model;
+mod scan;
+mod soft_delete;
 mod stream_model;
+pub mod with;
 
 pub use redis;
 pub use redis::{Client, FromRedisValue, RedisError, RedisResult, ToRedisArgs};
@@ -13,6 +26,7 @@ pub use redis::{Client, FromRedisValue, RedisError, RedisResult, ToRedisArgs};
 pub use redis_om_macros::HashModel;
 #[cfg(feature = "json")]
 pub use redis_om_macros::JsonModel;
+pub use redis_om_macros::MultiStreamModel;
 pub use redis_om_macros::RedisModel;
 pub use redis_om_macros::StreamModel;
 
@@ -62,12 +76,30 @@ pub use redis_om_macros::StreamModel;
 /// - Fields with types that do not implement `ToRedisArgs` and `FromRedisValue` are not supported
 pub use redis_om_macros::RedisTransportValue;
 
+pub use audit::{AuditChange, AuditLog, AuditOp, AuditRecord};
+pub use cache::CachedModel;
+pub use cluster::{key_slot, HashTag};
+pub use consumer::{CancellationToken, ConsumerStats, RetryPolicy, StreamConsumer};
 pub use hash_model::HashModel;
 #[cfg(feature = "json")]
 pub use json_model::*;
+#[cfg(feature = "json")]
+pub use migration::JsonRecord;
+pub use migration::{HashRecord, Migration, MigrationProgress, Migrations, SCHEMA_VERSION};
+pub use multi_stream::{MultiStreamManager, MultiStreamModel};
+pub use namespace::{Namespace, Namespaced, WithNamespace};
+#[doc(hidden)]
+pub use primary_key::{ensure_pk_parts, join_pk_parts, split_pk_parts};
+pub use primary_key::{PkStrategy, PrimaryKey, ToPk};
 pub use redis_model::RedisModel;
 pub use redissearch_model::RedisSearchModel;
-pub use stream_model::StreamModel;
+pub use soft_delete::{SoftDelete, DELETED_AT};
+pub use stream_model::{
+    ClaimOptions, ConsumerInfo, GroupInfo, PendingEntry, PendingQuery, PendingSummary, StreamInfo,
+};
+pub use stream_model::{
+    DeadLetter, PublishOptions, StartId, StreamEntryId, StreamModel, StreamRange, TypedMessage,
+};
 
 #[cfg(feature = "aio")]
 pub use async_trait::async_trait;
@@ -0,0 +1,89 @@
+//! Encoding of map fields as "{field}.{key}" hash entries, used by derived models.
+use redis::{RedisWrite, ToRedisArgs, Value};
+use std::collections::BTreeMap;
+use tap::Pipe;
+
+/// Escape `.` and `\` in map `key`, so it can be embedded in "{field}.{key}".
+pub fn escape_key(key: &[u8]) -> Vec<u8> {
+    key.iter()
+        .fold(Vec::with_capacity(key.len()), |mut out, b| {
+            if matches!(b, b'.' | b'\\') {
+                out.push(b'\\');
+            }
+            out.push(*b);
+            out
+        })
+}
+
+/// Write map entries as "{field}.{key}" fields, or "{field}.{key}.{idx}" for multi-arg values.
+pub fn write_map<'a, K, V, W>(
+    field: &str,
+    map: impl IntoIterator<Item = (&'a K, &'a V)>,
+    out: &mut W,
+) where
+    K: ToRedisArgs + 'a,
+    V: ToRedisArgs + 'a,
+    W: ?Sized + RedisWrite,
+{
+    for (key, value) in map {
+        let key = key.to_redis_args().concat();
+        let mut prefix = format!("{field}.").into_bytes();
+        prefix.extend(escape_key(&key));
+
+        match value.to_redis_args() {
+            args if args.len() == 1 => {
+                out.write_arg(&prefix);
+                out.write_arg(&args[0]);
+            }
+            args => {
+                for (idx, arg) in args.iter().enumerate() {
+                    out.write_arg(&[&prefix[..], format!(".{idx}").as_bytes()].concat());
+                    out.write_arg(arg);
+                }
+            }
+        }
+    }
+}
+
+/// Collect `(suffix, value)` entries of a map field, with suffix being "{key}" or
+/// "{key}.{idx}", into a bulk of key-value pairs.
+pub fn entries_to_value(entries: Vec<(String, Value)>) -> Value {
+    let mut map = Vec::<(String, Value)>::new();
+    let mut lists = BTreeMap::<String, BTreeMap<usize, Value>>::new();
+
+    for (suffix, value) in entries {
+        let (key, idx) = split_key(&suffix);
+        match idx.and_then(|idx| idx.parse().ok()) {
+            Some(idx) => {
+                lists.entry(key).or_default().insert(idx, value);
+            }
+            None => map.push((key, value)),
+        }
+    }
+
+    let lists = lists
+        .into_iter()
+        .map(|(key, list)| (key, Value::Bulk(list.into_values().collect())));
+
+    map.into_iter()
+        .chain(lists)
+        .flat_map(|(key, value)| [Value::Data(key.into_bytes()), value])
+        .collect::<Vec<_>>()
+        .pipe(Value::Bulk)
+}
+
+/// Split "{key}.{idx}" at the first unescaped `.`, unescaping key.
+fn split_key(suffix: &str) -> (String, Option<&str>) {
+    let mut key = String::with_capacity(suffix.len());
+    let mut chars = suffix.char_indices();
+
+    while let Some((pos, c)) = chars.next() {
+        match c {
+            '\\' => key.extend(chars.next().map(|(_, c)| c)),
+            '.' => return (key, Some(&suffix[pos + 1..])),
+            c => key.push(c),
+        }
+    }
+
+    (key, None)
+}
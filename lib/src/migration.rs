@@ -0,0 +1,111 @@
+//! Schema versions of stored records, set with `#[redis(schema_version = N)]`.
+//!
+//! Records are saved with their schema version. Records of older versions are upgraded by
+//! [`Migrations`] registered with `#[redis(migrations = "path::to::fn")]`: lazily on `get`
+//! and `find_by_pk`, and in bulk by `migrate_all`.
+use redis::{ErrorKind, RedisError, RedisResult};
+use std::collections::BTreeMap;
+
+/// Field holding schema version of a record.
+pub const SCHEMA_VERSION: &str = "__schema_version";
+
+/// Stored fields of a hash record, migrated by `HashModel` migrations.
+pub type HashRecord = BTreeMap<String, String>;
+
+/// Stored document of a json record, migrated by `JsonModel` migrations.
+#[cfg(feature = "json")]
+pub type JsonRecord = serde_json::Value;
+
+/// Upgrade of stored records from one schema version to the next.
+///
+/// Implemented for `(from_version, fn)` pairs, e.g.
+/// `(1, |record: &mut HashRecord| { ... })`.
+pub trait Migration<R>: Send + Sync {
+    /// Schema version records are upgraded from, to `version() + 1`
+    fn version(&self) -> u32;
+
+    /// Upgrade `record` in place
+    fn up(&self, record: &mut R) -> Result<(), String>;
+}
+
+impl<R, F> Migration<R> for (u32, F)
+where
+    F: Fn(&mut R) -> Result<(), String> + Send + Sync,
+{
+    fn version(&self) -> u32 {
+        self.0
+    }
+
+    fn up(&self, record: &mut R) -> Result<(), String> {
+        (self.1)(record)
+    }
+}
+
+/// Registry of migrations of a model, keyed by the version they upgrade from.
+pub struct Migrations<R> {
+    migrations: BTreeMap<u32, Box<dyn Migration<R>>>,
+}
+
+impl<R> Default for Migrations<R> {
+    fn default() -> Self {
+        Self {
+            migrations: BTreeMap::new(),
+        }
+    }
+}
+
+impl<R> Migrations<R> {
+    /// Create empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `migration`, replacing any registered from the same version
+    pub fn register(mut self, migration: impl Migration<R> + 'static) -> Self {
+        self.migrations
+            .insert(migration.version(), Box::new(migration));
+        self
+    }
+
+    /// Upgrade `record` from schema version `from` to `to`, failing if a step is missing.
+    pub fn upgrade(&self, record: &mut R, from: u32, to: u32) -> RedisResult<()> {
+        for version in from..to {
+            let migration = self.migrations.get(&version).ok_or_else(|| {
+                migration_error(format!("no migration from schema version {version}"))
+            })?;
+
+            migration.up(record).map_err(|err| {
+                migration_error(format!("migration from schema version {version}: {err}"))
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Progress of `migrate_all`, reported after each SCAN batch.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MigrationProgress {
+    /// Number of records scanned
+    pub scanned: usize,
+    /// Number of records upgraded
+    pub migrated: usize,
+    /// Keys of records failing to upgrade, along the error
+    pub failed: Vec<(String, String)>,
+    /// SCAN cursor to resume migrating from, `0` once done
+    pub cursor: u64,
+}
+
+/// Parse stored schema version, records saved before versioning are of version 1.
+pub(crate) fn parse_version(stored: Option<&str>) -> RedisResult<u32> {
+    match stored {
+        None => Ok(1),
+        Some(version) => version
+            .parse()
+            .map_err(|_| migration_error(format!("invalid schema version {version:?}"))),
+    }
+}
+
+fn migration_error(detail: String) -> RedisError {
+    RedisError::from((ErrorKind::TypeError, "schema migration failed", detail))
+}
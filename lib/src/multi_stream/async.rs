@@ -0,0 +1,71 @@
+use super::{cmds, transformers, MultiStreamManager, MultiStreamModel};
+use crate::stream_model::{self, reply::StreamReadReply};
+use crate::{StartId, TypedMessage, WithNamespace};
+use redis::RedisResult;
+
+impl<M: MultiStreamModel> MultiStreamManager<M> {
+    /// Ensure group exists for every stream of `M`, creating missing streams.
+    /// Errors if it fails to ensure a stream
+    pub async fn ensure_group_streams<C: WithNamespace + Send>(
+        &self,
+        conn: &mut C,
+    ) -> RedisResult<()> {
+        self.ensure_group_streams_from(StartId::Latest, None, conn)
+            .await
+    }
+
+    /// Ensure group exists for every stream of `M` like [`Self::ensure_group_streams`], new
+    /// groups delivering messages added after `start`, with `entries_read` messages read.
+    pub async fn ensure_group_streams_from<C: WithNamespace + Send>(
+        &self,
+        start: StartId,
+        entries_read: Option<u64>,
+        conn: &mut C,
+    ) -> RedisResult<()> {
+        for key in cmds::keys::<M>(conn.namespace()) {
+            let res =
+                stream_model::cmds::ensure_group(&key, &self.group_name, &start, entries_read)
+                    .query_async(conn.connection())
+                    .await;
+            stream_model::transformers::ensure_group_stream_success(res)?;
+        }
+        Ok(())
+    }
+
+    /// Read new messages of every stream of `M` with group name and consumer name.
+    ///
+    /// `read_count` applies to each stream.
+    pub async fn read<C: WithNamespace + Send>(
+        &self,
+        read_count: Option<usize>,
+        block_interval: Option<usize>,
+        conn: &mut C,
+    ) -> RedisResult<Vec<TypedMessage<M>>> {
+        let ns = conn.namespace();
+        let keys = cmds::keys::<M>(ns);
+        let group = self.group_name.as_str();
+        cmds::read(
+            ns,
+            &keys,
+            group,
+            &self.consumer_name,
+            read_count,
+            block_interval,
+        )?
+        .query_async::<_, StreamReadReply>(conn.connection())
+        .await
+        .map(|reply| transformers::read_reply_to_messages(&keys, group, reply))?
+    }
+
+    /// Acknowledge `message` for its group, in the stream it was read from
+    pub async fn ack<C: WithNamespace + Send>(
+        &self,
+        message: &TypedMessage<M>,
+        conn: &mut C,
+    ) -> RedisResult<()> {
+        let key = transformers::message_stream(message)?;
+        cmds::ack(key, &message.group, message.id)
+            .query_async(conn.connection())
+            .await
+    }
+}
@@ -0,0 +1,177 @@
+#[cfg(feature = "aio")]
+mod r#async;
+#[cfg(not(feature = "aio"))]
+mod sync;
+
+use redis::{RedisResult, Value};
+use std::marker::PhantomData;
+
+/// Enum of data of several streams read together by a [`MultiStreamManager`].
+///
+/// Derived with `#[derive(MultiStreamModel)]` on an enum holding the data of a stream model
+/// in each variant, e.g. `Service(RoomServiceEvent)`. The stream model defaults to the
+/// derived manager of the data, e.g. `RoomServiceEventManager`, and is set with
+/// `#[redis(manager = "...")]`. The derive also generates `{Enum}Manager`.
+pub trait MultiStreamModel: Sized {
+    /// Redis stream keys, i.e. `StreamModel::stream_key` of each variant
+    fn stream_keys() -> Vec<&'static str>;
+
+    /// Decoder of messages of the stream at `index` of [`Self::stream_keys`]
+    fn decoder(index: usize) -> Option<fn(&Value) -> RedisResult<Self>>;
+}
+
+/// Manager reading the streams of `M` as the same group and consumer, in a single XREADGROUP.
+///
+/// Keys of the streams must hash to the same slot in cluster mode, e.g. share a hash tag.
+///
+/// # Example
+///
+/// ```ignore
+/// #[derive(MultiStreamModel)]
+/// enum RoomEvent {
+///     Service(RoomServiceEvent),
+///     Chat(ChatEvent),
+/// }
+///
+/// let manager = RoomEventManager::new("Staff");
+/// manager.ensure_group_streams(&mut conn)?;
+///
+/// for message in manager.read(None, None, &mut conn)? {
+///     match message.data()? {
+///         RoomEvent::Service(event) => {}
+///         RoomEvent::Chat(event) => {}
+///     }
+///     manager.ack(&message, &mut conn)?;
+/// }
+/// ```
+pub struct MultiStreamManager<M> {
+    group_name: String,
+    consumer_name: String,
+    model: PhantomData<fn() -> M>,
+}
+
+impl<M> MultiStreamManager<M> {
+    /// Create new MultiStreamManager, with consumer_name being auto generated
+    pub fn new(group_name: impl AsRef<str>) -> Self {
+        Self::new_with_consumer_name(group_name, rusty_ulid::generate_ulid_string())
+    }
+
+    /// Create new MultiStreamManager, with custom consumer_name
+    pub fn new_with_consumer_name(
+        group_name: impl AsRef<str>,
+        consumer_name: impl AsRef<str>,
+    ) -> Self {
+        Self {
+            group_name: group_name.as_ref().to_owned(),
+            consumer_name: consumer_name.as_ref().to_owned(),
+            model: PhantomData,
+        }
+    }
+
+    /// Group Name
+    pub fn group_name(&self) -> &str {
+        &self.group_name
+    }
+
+    /// Consumer Name
+    pub fn consumer_name(&self) -> &str {
+        &self.consumer_name
+    }
+}
+
+impl<M> Clone for MultiStreamManager<M> {
+    fn clone(&self) -> Self {
+        Self::new_with_consumer_name(&self.group_name, &self.consumer_name)
+    }
+}
+
+mod cmds {
+    use super::MultiStreamModel;
+    use crate::{Namespace, StreamEntryId};
+    use redis::{streams::StreamReadOptions, Cmd, RedisResult};
+
+    /// Get keys "{namespace}:{stream_key}" of streams of `M`
+    pub fn keys<M: MultiStreamModel>(ns: &Namespace) -> Vec<String> {
+        M::stream_keys()
+            .into_iter()
+            .map(|stream_key| ns.model_prefix(stream_key))
+            .collect()
+    }
+
+    pub fn read(
+        ns: &Namespace,
+        keys: &[String],
+        group: &str,
+        consumer: &str,
+        read_count: Option<usize>,
+        block_interval: Option<usize>,
+    ) -> RedisResult<Cmd> {
+        ns.ensure_same_slot(keys)?;
+
+        let mut cmd = redis::cmd("XREADGROUP");
+        let mut opts = StreamReadOptions::default().group(group, consumer);
+        if let Some(read_count) = read_count {
+            opts = opts.count(read_count)
+        }
+        if let Some(block_interval) = block_interval {
+            opts = opts.block(block_interval)
+        }
+
+        cmd.arg(opts)
+            .arg("STREAMS")
+            .arg(keys)
+            .arg(vec![">"; keys.len()]);
+
+        Ok(cmd)
+    }
+
+    pub fn ack(key: &str, group: &str, id: StreamEntryId) -> Cmd {
+        let mut cmd = redis::cmd("XACK");
+        cmd.arg(key).arg(group).arg(id);
+        cmd
+    }
+}
+
+mod transformers {
+    use super::MultiStreamModel;
+    use crate::stream_model::reply::StreamReadReply;
+    use crate::TypedMessage;
+    use redis::{ErrorKind, RedisError, RedisResult};
+
+    pub fn read_reply_to_messages<M: MultiStreamModel>(
+        keys: &[String],
+        group: &str,
+        reply: StreamReadReply,
+    ) -> RedisResult<Vec<TypedMessage<M>>> {
+        let mut messages = Vec::new();
+        for stream in reply.keys {
+            let decode = keys
+                .iter()
+                .position(|key| key == &stream.key)
+                .and_then(M::decoder)
+                .ok_or_else(|| {
+                    RedisError::from((
+                        ErrorKind::TypeError,
+                        "unexpected stream in XREADGROUP reply",
+                        stream.key.clone(),
+                    ))
+                })?;
+
+            for item in stream.ids {
+                let key = Some(stream.key.clone());
+                messages.push(TypedMessage::with_decoder(item, group.into(), key, decode));
+            }
+        }
+
+        Ok(messages)
+    }
+
+    pub fn message_stream<M>(message: &TypedMessage<M>) -> RedisResult<&str> {
+        message.stream().ok_or_else(|| {
+            RedisError::from((
+                ErrorKind::ClientError,
+                "message wasn't read by a MultiStreamManager",
+            ))
+        })
+    }
+}
@@ -0,0 +1,238 @@
+use crate::cluster::ensure_same_slot;
+#[cfg(feature = "aio")]
+use redis::aio::ConnectionLike;
+#[cfg(not(feature = "aio"))]
+use redis::ConnectionLike;
+use redis::RedisResult;
+
+/// Namespace used with no explicit one given, keys are of format "{prefix}:{pk}".
+static DEFAULT_NAMESPACE: Namespace = Namespace::new();
+
+/// Key namespace applied to model keys, scan patterns, stream keys and search schemas.
+///
+/// Keys are built as "{app}{sep}{tenant}{sep}{model_prefix}{sep}{pk}", skipping unset segments.
+///
+/// # Example
+///
+/// ```
+/// use redis_om::Namespace;
+///
+/// let ns = Namespace::new().app("shop").tenant("acme");
+///
+/// assert_eq!(ns.key("Customer", "01H"), "shop:acme:Customer:01H");
+/// assert_eq!(ns.pattern("Customer"), "shop:acme:Customer:*");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Namespace {
+    app: Option<String>,
+    tenant: Option<String>,
+    separator: Option<String>,
+    cluster: bool,
+}
+
+impl Namespace {
+    /// Create a new empty namespace, producing "{prefix}:{pk}" keys.
+    pub const fn new() -> Self {
+        Self {
+            app: None,
+            tenant: None,
+            separator: None,
+            cluster: false,
+        }
+    }
+
+    /// Set global application prefix.
+    pub fn app(mut self, app: impl Into<String>) -> Self {
+        self.app = Some(app.into());
+        self
+    }
+
+    /// Set tenant segment.
+    pub fn tenant(mut self, tenant: impl Into<String>) -> Self {
+        self.tenant = Some(tenant.into());
+        self
+    }
+
+    /// Set separator between key segments, defaults to ":".
+    pub fn separator(mut self, separator: impl Into<String>) -> Self {
+        self.separator = Some(separator.into());
+        self
+    }
+
+    /// Enable redis cluster mode, validating that keys of multi-key operations share a slot
+    /// and splitting batched operations by slot.
+    ///
+    /// Note: SCAN based operations only cover keys of the node the connection is routed to.
+    pub fn cluster(mut self, cluster: bool) -> Self {
+        self.cluster = cluster;
+        self
+    }
+
+    /// Whether redis cluster mode is enabled.
+    pub fn is_cluster(&self) -> bool {
+        self.cluster
+    }
+
+    /// Ensure `keys` of a multi-key operation share a slot, a no-op outside of cluster mode.
+    pub fn ensure_same_slot<K: AsRef<[u8]>>(&self, keys: &[K]) -> RedisResult<()> {
+        if self.cluster {
+            ensure_same_slot(keys)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Get separator between key segments.
+    pub fn get_separator(&self) -> &str {
+        self.separator.as_deref().unwrap_or(":")
+    }
+
+    /// Get "{app}{sep}{tenant}{sep}{model_prefix}".
+    pub fn model_prefix(&self, model_prefix: &str) -> String {
+        let segments = [self.app.as_deref(), self.tenant.as_deref()];
+
+        segments
+            .into_iter()
+            .flatten()
+            .chain([model_prefix])
+            .collect::<Vec<_>>()
+            .join(self.get_separator())
+    }
+
+    /// Get "{app}{sep}{tenant}{sep}{model_prefix}{sep}{pk}".
+    pub fn key(&self, model_prefix: &str, pk: &str) -> String {
+        format!(
+            "{}{}{pk}",
+            self.model_prefix(model_prefix),
+            self.get_separator()
+        )
+    }
+
+    /// Get SCAN pattern matching all keys under `model_prefix`, with glob characters escaped.
+    pub fn pattern(&self, model_prefix: &str) -> String {
+        let prefix = escape_pattern(&self.model_prefix(model_prefix));
+        let separator = escape_pattern(self.get_separator());
+
+        format!("{prefix}{separator}*")
+    }
+
+    /// Get pk from key of format "{app}{sep}{tenant}{sep}{model_prefix}{sep}{pk}".
+    pub fn strip_key<'k>(&self, model_prefix: &str, key: &'k str) -> Option<&'k str> {
+        key.strip_prefix(&self.model_prefix(model_prefix))
+            .and_then(|pk| pk.strip_prefix(self.get_separator()))
+    }
+
+    /// Wrap `conn` so that models use this namespace with it.
+    pub fn wrap<C>(self, conn: C) -> Namespaced<C> {
+        Namespaced::new(self, conn)
+    }
+}
+
+fn escape_pattern(value: &str) -> String {
+    value
+        .chars()
+        .flat_map(|c| {
+            let escape = matches!(c, '*' | '?' | '[' | ']' | '\\').then_some('\\');
+            escape.into_iter().chain([c])
+        })
+        .collect()
+}
+
+/// Redis connection bound to a [`Namespace`].
+///
+/// # Example
+///
+/// ```ignore
+/// let client = redis_om::Client::open("redis://127.0.0.1/")?;
+/// let mut conn = Namespace::new().tenant("acme").wrap(client.get_connection()?);
+///
+/// // Saved under "acme:Customer:{pk}"
+/// customer.save(&mut conn)?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct Namespaced<C> {
+    namespace: Namespace,
+    conn: C,
+}
+
+impl<C> Namespaced<C> {
+    /// Create a new connection bound to `namespace`.
+    pub fn new(namespace: Namespace, conn: C) -> Self {
+        Self { namespace, conn }
+    }
+
+    /// Get underlying connection back.
+    pub fn into_inner(self) -> C {
+        self.conn
+    }
+}
+
+/// Connection that models are read from and written to, with the namespace to build keys with.
+///
+/// Implemented for every redis connection (using the default namespace) and [`Namespaced`].
+pub trait WithNamespace {
+    /// Underlying redis connection.
+    #[cfg(feature = "aio")]
+    type Connection: ConnectionLike + Send;
+    /// Underlying redis connection.
+    #[cfg(not(feature = "aio"))]
+    type Connection: ConnectionLike;
+
+    /// Namespace applied to model keys.
+    fn namespace(&self) -> &Namespace;
+
+    /// Get underlying redis connection.
+    fn connection(&mut self) -> &mut Self::Connection;
+}
+
+#[cfg(feature = "aio")]
+impl<C: ConnectionLike + Send> WithNamespace for C {
+    type Connection = C;
+
+    fn namespace(&self) -> &Namespace {
+        &DEFAULT_NAMESPACE
+    }
+
+    fn connection(&mut self) -> &mut Self::Connection {
+        self
+    }
+}
+
+#[cfg(feature = "aio")]
+impl<C: ConnectionLike + Send> WithNamespace for Namespaced<C> {
+    type Connection = C;
+
+    fn namespace(&self) -> &Namespace {
+        &self.namespace
+    }
+
+    fn connection(&mut self) -> &mut Self::Connection {
+        &mut self.conn
+    }
+}
+
+#[cfg(not(feature = "aio"))]
+impl<C: ConnectionLike> WithNamespace for C {
+    type Connection = C;
+
+    fn namespace(&self) -> &Namespace {
+        &DEFAULT_NAMESPACE
+    }
+
+    fn connection(&mut self) -> &mut Self::Connection {
+        self
+    }
+}
+
+#[cfg(not(feature = "aio"))]
+impl<C: ConnectionLike> WithNamespace for Namespaced<C> {
+    type Connection = C;
+
+    fn namespace(&self) -> &Namespace {
+        &self.namespace
+    }
+
+    fn connection(&mut self) -> &mut Self::Connection {
+        &mut self.conn
+    }
+}
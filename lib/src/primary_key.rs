@@ -0,0 +1,214 @@
+use crate::{RedisModel, WithNamespace};
+use redis::{ErrorKind, RedisError, RedisResult};
+use std::borrow::Cow;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Separator between parts of composite primary keys, e.g. "{tenant}:{user}".
+const PART_SEPARATOR: char = ':';
+
+/// Types usable as model primary keys, e.g. `String`, `u64`, `Uuid` or newtypes of them.
+///
+/// Implemented for every type that can be formatted, parsed and has a default value
+/// marking the key as unset (e.g. `""`, `0` or the nil uuid).
+pub trait PrimaryKey: Sized {
+    /// Format Self as key segment
+    fn to_pk_string(&self) -> String;
+
+    /// Parse Self from key segment
+    fn from_pk_str(pk: &str) -> RedisResult<Self>;
+
+    /// Check whether Self is unset and should be generated on save
+    fn is_unset(&self) -> bool;
+}
+
+impl<T> PrimaryKey for T
+where
+    T: Display + FromStr + Default + PartialEq,
+    T::Err: Display,
+{
+    fn to_pk_string(&self) -> String {
+        self.to_string()
+    }
+
+    fn from_pk_str(pk: &str) -> RedisResult<Self> {
+        pk.parse().map_err(|err: T::Err| {
+            RedisError::from((
+                ErrorKind::TypeError,
+                "invalid primary key",
+                format!("{pk:?}: {err}"),
+            ))
+        })
+    }
+
+    fn is_unset(&self) -> bool {
+        *self == T::default()
+    }
+}
+
+/// Primary key arguments of lookups, a single key or a tuple of composite key parts.
+///
+/// # Example
+///
+/// ```
+/// use redis_om::ToPk;
+///
+/// assert_eq!("jane".to_pk(), "jane");
+/// assert_eq!(42u64.to_pk(), "42");
+/// assert_eq!(("acme", 7).to_pk(), "acme:7");
+/// ```
+pub trait ToPk {
+    /// Format Self as primary key segment
+    fn to_pk(&self) -> String;
+}
+
+impl<T: ToPk + ?Sized> ToPk for &T {
+    fn to_pk(&self) -> String {
+        (**self).to_pk()
+    }
+}
+
+impl ToPk for str {
+    fn to_pk(&self) -> String {
+        self.to_owned()
+    }
+}
+
+impl ToPk for String {
+    fn to_pk(&self) -> String {
+        self.clone()
+    }
+}
+
+impl ToPk for Cow<'_, str> {
+    fn to_pk(&self) -> String {
+        self.to_string()
+    }
+}
+
+macro_rules! to_pk_display {
+    ($($ty:ty),*) => {
+        $(impl ToPk for $ty {
+            fn to_pk(&self) -> String {
+                self.to_string()
+            }
+        })*
+    };
+}
+
+to_pk_display!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+#[cfg(feature = "uuid")]
+to_pk_display!(uuid::Uuid);
+
+macro_rules! to_pk_tuple {
+    ($($part:ident),*) => {
+        impl<$($part: ToPk),*> ToPk for ($($part,)*) {
+            #[allow(non_snake_case)]
+            fn to_pk(&self) -> String {
+                let ($($part,)*) = self;
+                join_pk_parts(&[$($part.to_pk()),*])
+            }
+        }
+    };
+}
+
+to_pk_tuple!(A, B);
+to_pk_tuple!(A, B, C);
+to_pk_tuple!(A, B, C, D);
+
+/// Join parts of a composite primary key.
+pub fn join_pk_parts(parts: &[String]) -> String {
+    parts.join(&PART_SEPARATOR.to_string())
+}
+
+/// Ensure no part of a composite primary key contains the separator, which would make the
+/// joined key ambiguous.
+pub fn ensure_pk_parts(parts: &[String]) -> RedisResult<()> {
+    match parts.iter().find(|part| part.contains(PART_SEPARATOR)) {
+        Some(part) => Err(RedisError::from((
+            ErrorKind::ClientError,
+            "invalid composite primary key",
+            format!("{part:?}: parts can't contain {PART_SEPARATOR:?}"),
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Split composite primary key into exactly `N` parts.
+pub fn split_pk_parts<const N: usize>(pk: &str) -> RedisResult<[&str; N]> {
+    let mut split = pk.split(PART_SEPARATOR);
+    let parts: [&str; N] = std::array::from_fn(|_| split.next().unwrap_or_default());
+
+    if parts.iter().any(|part| part.is_empty()) || split.next().is_some() {
+        return Err(RedisError::from((
+            ErrorKind::TypeError,
+            "invalid composite primary key",
+            format!("{pk:?}: expected {N} parts separated by {PART_SEPARATOR:?}"),
+        )));
+    }
+
+    Ok(parts)
+}
+
+/// How unset primary keys are generated on save, set with `#[redis(pk_strategy = "...")]`.
+#[derive(Debug, Clone, Copy)]
+pub enum PkStrategy {
+    /// Generate a ULID string (default of `String` primary keys).
+    Ulid,
+    /// Generate a random UUID.
+    #[cfg(feature = "uuid")]
+    UuidV4,
+    /// Generate a time ordered UUID.
+    #[cfg(feature = "uuid")]
+    UuidV7,
+    /// INCR a "{prefix}:__seq" key, producing 1, 2, 3, ...
+    Counter,
+    /// Call given function, generated from `pk_strategy = "path::to::fn"`.
+    Custom(fn() -> String),
+}
+
+impl PkStrategy {
+    /// Generate a new primary key, `None` for [`PkStrategy::Counter`] which requires redis.
+    pub fn generate(&self) -> Option<String> {
+        match self {
+            PkStrategy::Ulid => Some(rusty_ulid::generate_ulid_string()),
+            #[cfg(feature = "uuid")]
+            PkStrategy::UuidV4 => Some(uuid::Uuid::new_v4().to_string()),
+            #[cfg(feature = "uuid")]
+            PkStrategy::UuidV7 => Some(uuid::Uuid::now_v7().to_string()),
+            PkStrategy::Counter => None,
+            PkStrategy::Custom(generate) => Some(generate()),
+        }
+    }
+}
+
+/// Ensure `model` has a primary key, querying the sequence of counter strategy.
+#[cfg(not(feature = "aio"))]
+pub(crate) fn ensure_pk<M, C>(model: &mut M, conn: &mut C) -> RedisResult<()>
+where
+    M: RedisModel,
+    C: WithNamespace,
+{
+    if let Some(cmd) = model._ensure_pk(conn.namespace())? {
+        let seq: u64 = cmd.query(conn.connection())?;
+        model._set_pk(&seq.to_string())?;
+    }
+
+    Ok(())
+}
+
+/// Ensure `model` has a primary key, querying the sequence of counter strategy.
+#[cfg(feature = "aio")]
+pub(crate) async fn ensure_pk<M, C>(model: &mut M, conn: &mut C) -> RedisResult<()>
+where
+    M: RedisModel,
+    C: WithNamespace + Send,
+{
+    if let Some(cmd) = model._ensure_pk(conn.namespace())? {
+        let seq: u64 = cmd.query_async(conn.connection()).await?;
+        model._set_pk(&seq.to_string())?;
+    }
+
+    Ok(())
+}
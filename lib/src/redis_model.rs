@@ -1,4 +1,6 @@
-use redis::RedisResult;
+use crate::{HashTag, Namespace, PkStrategy, SoftDelete};
+use redis::{ErrorKind, RedisError, RedisResult};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 /// Shared Redis Object Model
 pub trait RedisModel {
@@ -6,41 +8,167 @@ pub trait RedisModel {
     /// This should by default that HashModel name in lowercase.
     fn _prefix_key() -> &'static str;
 
-    /// Get primary key
-    fn _get_pk(&self) -> &str;
+    /// Get hash tag wrapped in `{...}` within keys of Self
+    fn _hash_tag() -> HashTag {
+        HashTag::None
+    }
+
+    /// Get time to live applied on every save, set with `#[redis(ttl = "...")]`
+    fn _ttl() -> Option<Duration> {
+        None
+    }
+
+    /// Get how records are soft deleted, set with `#[redis(soft_delete)]`
+    fn _soft_delete() -> Option<SoftDelete> {
+        None
+    }
+
+    /// Get stream key audited changes are added to, set with `#[redis(audit_stream = "...")]`
+    fn _audit_stream() -> Option<&'static str> {
+        None
+    }
+
+    /// Get schema version records are saved with, set with `#[redis(schema_version = N)]`
+    fn _schema_version() -> Option<u32> {
+        None
+    }
+
+    /// Get how unset primary keys are generated
+    fn _pk_strategy() -> PkStrategy {
+        PkStrategy::Ulid
+    }
 
     /// Get primary key
-    fn _set_pk(&mut self, pk: String);
+    fn _get_pk(&self) -> String;
+
+    /// Check whether primary key is set
+    fn _has_pk(&self) -> bool;
+
+    /// Set primary key from its key segment
+    fn _set_pk(&mut self, pk: &str) -> RedisResult<()>;
+
+    /// Generate primary key if unset, returning the command to query for counter strategy
+    fn _ensure_pk(&mut self, ns: &Namespace) -> RedisResult<Option<redis::Cmd>> {
+        if self._has_pk() {
+            return Ok(None);
+        }
+
+        match Self::_pk_strategy().generate() {
+            Some(pk) => self._set_pk(&pk).map(|_| None),
+            None => {
+                let mut cmd = redis::cmd("INCR");
+                cmd.arg(Self::_seq_key(ns));
+                Ok(Some(cmd))
+            }
+        }
+    }
+
+    /// Get key "{namespace}:{self::redis_key}:__seq" of counter strategy
+    fn _seq_key(ns: &Namespace) -> String {
+        ns.key(Self::_prefix_key(), "__seq")
+    }
+
+    /// Get key "{namespace}:{self::redis_key}:{pk}"
+    fn _fmt_pk(ns: &Namespace, pk: &str) -> String {
+        let pk = Self::_hash_tag().tag_pk(ns.get_separator(), pk);
+        ns.key(Self::_prefix_key(), &pk)
+    }
+
+    /// Check if str is  of format "{namespace}:{self::redis_key}:{pk}"
+    fn _is_pk_fmt(ns: &Namespace, pk: &str) -> bool {
+        Self::_untag_key(ns, pk).is_some()
+    }
 
-    /// Ensure primary key
-    fn _ensure_pk(&mut self) {
-        if self._get_pk() == "" {
-            self._set_pk(rusty_ulid::generate_ulid_string())
+    /// Get key of `pk`, which may already be of format "{namespace}:{self::redis_key}:{pk}"
+    fn _fmt_key(ns: &Namespace, pk: &str) -> String {
+        if Self::_is_pk_fmt(ns, pk) {
+            pk.to_owned()
+        } else {
+            Self::_fmt_pk(ns, pk)
         }
     }
 
-    /// Get key "{self::redis_key}:{pk}"
-    fn _fmt_pk(pk: &str) -> String {
-        format!("{}:{}", Self::_prefix_key(), pk)
+    /// Get pk from key of format "{namespace}:{self::redis_key}:{pk}"
+    fn _strip_pk<'k>(ns: &Namespace, key: &'k str) -> &'k str {
+        Self::_untag_key(ns, key).unwrap_or(key)
     }
 
-    /// Check if str is  of format "{self::redis_key}:{pk}"
-    fn _is_pk_fmt(pk: &str) -> bool {
-        pk.starts_with(Self::_prefix_key())
+    /// Get pk from key of format "{namespace}:{self::redis_key}:{pk}", `None` if not of that format
+    fn _untag_key<'k>(ns: &Namespace, key: &'k str) -> Option<&'k str> {
+        let segment = ns.strip_key(Self::_prefix_key(), key)?;
+        Self::_hash_tag().untag_pk(ns.get_separator(), segment)
     }
 
-    /// Get key "{self::redis_key}:{self._get_primary_key()}"
-    fn _get_redis_key(&self) -> String {
-        Self::_fmt_pk(self._get_pk())
+    /// Get key "{namespace}:{self::redis_key}__deleted:{pk}" of soft deleted records, outside
+    /// of [`Self::_scan_pattern`]
+    fn _fmt_deleted_key(ns: &Namespace, pk: &str) -> String {
+        let pk = Self::_hash_tag().tag_pk(ns.get_separator(), pk);
+        ns.key(&Self::_deleted_prefix_key(), &pk)
+    }
+
+    /// Get prefix "{self::redis_key}__deleted" of soft deleted records
+    fn _deleted_prefix_key() -> String {
+        format!("{}__deleted", Self::_prefix_key())
+    }
+
+    /// Get SCAN pattern "{namespace}:{self::redis_key}:*" matching all keys of Self
+    fn _scan_pattern(ns: &Namespace) -> String {
+        ns.pattern(Self::_prefix_key())
+    }
+
+    /// Get SCAN pattern "{namespace}:{self::redis_key}__deleted:*" matching soft deleted keys
+    fn _deleted_scan_pattern(ns: &Namespace) -> String {
+        ns.pattern(&Self::_deleted_prefix_key())
+    }
+
+    /// Get key "{namespace}:{self::redis_key}:{self._get_primary_key()}"
+    fn _get_redis_key(&self, ns: &Namespace) -> String {
+        Self::_fmt_pk(ns, &self._get_pk())
     }
 
     /// Expire Self at given duration
-    fn _expire_cmd(&self, secs: usize) -> RedisResult<redis::Cmd> {
-        let key = self._get_redis_key();
+    fn _expire_cmd(&self, ns: &Namespace, secs: usize) -> RedisResult<redis::Cmd> {
+        let key = self._get_redis_key(ns);
         let mut cmd = redis::cmd("EXPIRE");
 
         cmd.arg(key).arg(secs);
 
         Ok(cmd)
     }
+
+    /// Expire Self at given point in time
+    fn _expire_at_cmd(&self, ns: &Namespace, at: SystemTime) -> RedisResult<redis::Cmd> {
+        let millis = at.duration_since(UNIX_EPOCH).map_err(|_| {
+            RedisError::from((ErrorKind::ClientError, "expiry time is before unix epoch"))
+        })?;
+        let mut cmd = redis::cmd("PEXPIREAT");
+
+        cmd.arg(self._get_redis_key(ns))
+            .arg(millis.as_millis() as u64);
+
+        Ok(cmd)
+    }
+
+    /// Remove expiry of Self
+    fn _persist_cmd(&self, ns: &Namespace) -> RedisResult<redis::Cmd> {
+        let mut cmd = redis::cmd("PERSIST");
+
+        cmd.arg(self._get_redis_key(ns));
+
+        Ok(cmd)
+    }
+
+    /// Get remaining time to live of Self in milliseconds
+    fn _pttl_cmd(&self, ns: &Namespace) -> RedisResult<redis::Cmd> {
+        let mut cmd = redis::cmd("PTTL");
+
+        cmd.arg(self._get_redis_key(ns));
+
+        Ok(cmd)
+    }
+}
+
+/// Parse PTTL reply, negative for missing keys or keys without expiry.
+pub(crate) fn parse_pttl(millis: i64) -> Option<Duration> {
+    u64::try_from(millis).ok().map(Duration::from_millis)
 }
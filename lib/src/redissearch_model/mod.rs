@@ -2,4 +2,33 @@
 pub trait RedisSearchModel {
     /// full redis search schema
     const _REDIS_SEARCH_SCHEMA: &'static str;
+
+    /// full redis search schema indexing keys under `prefix`
+    fn _redis_search_schema(prefix: &str) -> String;
+}
+
+pub(crate) mod cmds {
+    use redis::Cmd;
+
+    /// Count documents of search `index`, without returning any
+    pub fn count(index: &str) -> Cmd {
+        let mut cmd = redis::cmd("FT.SEARCH");
+        cmd.arg(index).arg("*").arg("LIMIT").arg(0).arg(0);
+        cmd
+    }
+}
+
+pub(crate) mod transformers {
+    use redis::{ErrorKind, FromRedisValue, RedisError, RedisResult, Value};
+
+    /// Total of FT.SEARCH reply, preceding the returned documents
+    pub fn count(reply: Value) -> RedisResult<usize> {
+        match Vec::<Value>::from_redis_value(&reply)?.first() {
+            Some(total) => usize::from_redis_value(total),
+            None => Err(RedisError::from((
+                ErrorKind::TypeError,
+                "FT.SEARCH reply is missing its total",
+            ))),
+        }
+    }
 }
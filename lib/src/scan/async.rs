@@ -0,0 +1,272 @@
+use super::{cmds, transformers, ScanSpec, UpgradeFn};
+use crate::migration::MigrationProgress;
+use crate::soft_delete::{self, Storage};
+use crate::{Namespace, RedisModel, WithNamespace};
+use futures::stream::{self, BoxStream, StreamExt};
+use redis::{RedisResult, Value};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+struct ScanState<'a, M, C> {
+    conn: &'a mut C,
+    ns: Namespace,
+    spec: ScanSpec<M>,
+    batch_size: usize,
+    cursor: Option<u64>,
+    buffer: VecDeque<RedisResult<M>>,
+}
+
+impl<'a, M: RedisModel, C: WithNamespace + Send> ScanState<'a, M, C> {
+    async fn next_batch(&mut self, cursor: u64) -> RedisResult<()> {
+        let (cursor, keys): (u64, Vec<String>) =
+            cmds::scan::<M>(&self.ns, cursor, self.batch_size, self.spec.key_type)?
+                .query_async(self.conn.connection())
+                .await?;
+
+        self.cursor = (cursor != 0).then_some(cursor);
+
+        if keys.is_empty() {
+            return Ok(());
+        }
+
+        let values: Vec<Value> = cmds::fetch(&self.ns, &self.spec, &keys)?
+            .query_async(self.conn.connection())
+            .await?;
+
+        let models = transformers::batch_to_models(&self.ns, &self.spec, keys, values);
+        self.buffer.extend(models);
+
+        Ok(())
+    }
+}
+
+/// Stream all models stored under `M` prefix, fetching `batch_size` keys per round trip.
+pub fn scan<'a, M, C>(
+    spec: ScanSpec<M>,
+    batch_size: usize,
+    conn: &'a mut C,
+) -> BoxStream<'a, RedisResult<M>>
+where
+    M: RedisModel + Send + 'a,
+    C: WithNamespace + Send,
+{
+    let state = ScanState {
+        ns: conn.namespace().clone(),
+        conn,
+        spec,
+        batch_size,
+        cursor: Some(0),
+        buffer: VecDeque::new(),
+    };
+
+    stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(item) = state.buffer.pop_front() {
+                return Some((item, state));
+            }
+
+            let cursor = state.cursor?;
+
+            if let Err(err) = state.next_batch(cursor).await {
+                state.cursor = None;
+                return Some((Err(err), state));
+            }
+        }
+    })
+    .boxed()
+}
+
+/// Count records stored under `M` prefix, scanning `batch_size` keys per round trip.
+/// With the soft delete `storage` of `M`, records soft deleted in place aren't counted.
+///
+/// The count is approximate: keys added or removed during the scan may or may not be counted,
+/// and keys SCAN returns more than once, e.g. while the keyspace is rehashed, are counted again.
+/// Models aren't tied to a search index redis-om could count with, exact counts from FT.SEARCH
+/// take the index name instead, see `count_indexed` of the models.
+pub async fn count<M, C>(
+    key_type: &str,
+    storage: Option<&Storage>,
+    batch_size: usize,
+    conn: &mut C,
+) -> RedisResult<usize>
+where
+    M: RedisModel,
+    C: WithNamespace + Send,
+{
+    let ns = conn.namespace().clone();
+    let mut cursor = 0;
+    let mut count = 0;
+
+    loop {
+        let (next, keys): (u64, Vec<String>) = cmds::scan::<M>(&ns, cursor, batch_size, key_type)?
+            .query_async(conn.connection())
+            .await?;
+
+        match storage {
+            None => count += keys.len(),
+            Some(storage) => {
+                for keys in transformers::group_by_slot(&ns, keys) {
+                    if !keys.is_empty() {
+                        let live: usize = soft_delete::count_live(storage, &keys)
+                            .query_async(conn.connection())
+                            .await?;
+                        count += live;
+                    }
+                }
+            }
+        }
+
+        if next == 0 {
+            return Ok(count);
+        }
+
+        cursor = next;
+    }
+}
+
+/// Unlink all keys of `M` records in batches, soft deleted ones included, returning the number
+/// of unlinked keys.
+///
+/// With `dry_run` nothing is unlinked and the number of matching keys is returned instead.
+pub async fn unlink_all<M, C>(
+    key_type: &str,
+    batch_size: usize,
+    dry_run: bool,
+    conn: &mut C,
+) -> RedisResult<usize>
+where
+    M: RedisModel,
+    C: WithNamespace + Send,
+{
+    let ns = conn.namespace().clone();
+    let mut unlinked = 0;
+
+    for pattern in transformers::record_patterns::<M>(&ns) {
+        let mut cursor = 0;
+
+        loop {
+            let (next, keys): (u64, Vec<String>) =
+                cmds::scan_pattern(&pattern, cursor, batch_size, key_type)?
+                    .query_async(conn.connection())
+                    .await?;
+
+            if dry_run {
+                unlinked += keys.len();
+            } else {
+                for keys in transformers::group_by_slot(&ns, keys) {
+                    if !keys.is_empty() {
+                        let count: usize = cmds::unlink(&ns, &keys)?
+                            .query_async(conn.connection())
+                            .await?;
+                        unlinked += count;
+                    }
+                }
+            }
+
+            if next == 0 {
+                break;
+            }
+
+            cursor = next;
+        }
+    }
+
+    Ok(unlinked)
+}
+
+/// Unlink all keys stored under `M` prefix soft deleted before `older_than` ago,
+/// returning the number of unlinked keys.
+pub async fn purge_deleted<M, C>(
+    key_type: &str,
+    storage: &Storage,
+    older_than: Duration,
+    batch_size: usize,
+    conn: &mut C,
+) -> RedisResult<usize>
+where
+    M: RedisModel,
+    C: WithNamespace + Send,
+{
+    let ns = conn.namespace().clone();
+    let mut purged = 0;
+
+    for pattern in transformers::record_patterns::<M>(&ns) {
+        let mut cursor = 0;
+
+        loop {
+            let (next, keys): (u64, Vec<String>) =
+                cmds::scan_pattern(&pattern, cursor, batch_size, key_type)?
+                    .query_async(conn.connection())
+                    .await?;
+
+            for keys in transformers::group_by_slot(&ns, keys) {
+                if !keys.is_empty() {
+                    let count: usize = soft_delete::purge(storage, &keys, older_than)?
+                        .query_async(conn.connection())
+                        .await?;
+                    purged += count;
+                }
+            }
+
+            if next == 0 {
+                break;
+            }
+
+            cursor = next;
+        }
+    }
+
+    Ok(purged)
+}
+
+/// Persist upgrades of all records stored under `M` prefix of older schema versions, scanning
+/// from `cursor` and reporting progress after each batch.
+pub async fn migrate_all<M, C>(
+    spec: ScanSpec<M>,
+    upgrade: UpgradeFn,
+    cursor: u64,
+    batch_size: usize,
+    on_progress: &mut (dyn FnMut(&MigrationProgress) + Send),
+    conn: &mut C,
+) -> RedisResult<MigrationProgress>
+where
+    M: RedisModel,
+    C: WithNamespace + Send,
+{
+    let ns = conn.namespace().clone();
+    let mut progress = MigrationProgress {
+        cursor,
+        ..Default::default()
+    };
+
+    loop {
+        let (next, keys): (u64, Vec<String>) =
+            cmds::scan::<M>(&ns, progress.cursor, batch_size, spec.key_type)?
+                .query_async(conn.connection())
+                .await?;
+
+        for keys in transformers::group_by_slot(&ns, keys) {
+            if keys.is_empty() {
+                continue;
+            }
+
+            let values: Vec<Value> = cmds::fetch(&ns, &spec, &keys)?
+                .query_async(conn.connection())
+                .await?;
+
+            if let Some(pipe) =
+                transformers::batch_to_upgrades(upgrade, keys, values, &mut progress)
+            {
+                let upgraded: Vec<bool> = pipe.query_async(conn.connection()).await?;
+                progress.migrated += upgraded.into_iter().filter(|upgraded| *upgraded).count();
+            }
+        }
+
+        progress.cursor = next;
+        on_progress(&progress);
+
+        if next == 0 {
+            return Ok(progress);
+        }
+    }
+}
@@ -0,0 +1,161 @@
+#[cfg(feature = "aio")]
+mod r#async;
+#[cfg(not(feature = "aio"))]
+mod sync;
+
+#[cfg(feature = "aio")]
+pub use r#async::{count, migrate_all, purge_deleted, scan, unlink_all};
+#[cfg(not(feature = "aio"))]
+pub use sync::{count, migrate_all, purge_deleted, scan, unlink_all};
+
+use crate::migration::MigrationProgress;
+use crate::{Namespace, RedisModel};
+use redis::{Cmd, Pipeline, RedisResult, Value};
+
+/// Default number of keys to request per SCAN round trip.
+pub const DEFAULT_BATCH_SIZE: usize = 100;
+
+/// Model specific parts of a batched scan.
+pub struct ScanSpec<M> {
+    /// Redis TYPE of the keys holding models, used to skip unrelated keys.
+    pub key_type: &'static str,
+    /// Command fetching a single model by its redis key.
+    pub fetch: fn(&Namespace, &str) -> RedisResult<Cmd>,
+    /// Parse fetch reply, `None` when the key vanished between SCAN and fetch.
+    pub parse: fn(Value) -> RedisResult<Option<M>>,
+}
+
+/// Command persisting upgrade of fetch reply of record at given key, `None` if up to date.
+pub type UpgradeFn = fn(&str, Value) -> RedisResult<Option<Cmd>>;
+
+impl<M> Clone for ScanSpec<M> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<M> Copy for ScanSpec<M> {}
+
+mod cmds {
+    use super::*;
+
+    pub fn scan<M: RedisModel>(
+        ns: &Namespace,
+        cursor: u64,
+        count: usize,
+        key_type: &str,
+    ) -> RedisResult<Cmd> {
+        scan_pattern(&M::_scan_pattern(ns), cursor, count, key_type)
+    }
+
+    pub fn scan_pattern(
+        pattern: &str,
+        cursor: u64,
+        count: usize,
+        key_type: &str,
+    ) -> RedisResult<Cmd> {
+        let mut cmd = redis::cmd("SCAN");
+
+        cmd.arg(cursor)
+            .arg("MATCH")
+            .arg(pattern)
+            .arg("COUNT")
+            .arg(count)
+            .arg("TYPE")
+            .arg(key_type);
+
+        Ok(cmd)
+    }
+
+    pub fn unlink(ns: &Namespace, keys: &[String]) -> RedisResult<Cmd> {
+        ns.ensure_same_slot(keys)?;
+
+        let mut cmd = redis::cmd("UNLINK");
+        cmd.arg(keys);
+
+        Ok(cmd)
+    }
+
+    pub fn fetch<M>(ns: &Namespace, spec: &ScanSpec<M>, keys: &[String]) -> RedisResult<Pipeline> {
+        let mut pipe = redis::pipe();
+
+        for key in keys {
+            pipe.add_command((spec.fetch)(ns, key)?);
+        }
+
+        Ok(pipe)
+    }
+}
+
+mod transformers {
+    use super::*;
+    use crate::{key_slot, SoftDelete};
+    use std::collections::BTreeMap;
+
+    /// Split `keys` into groups sharing a cluster slot, a single group outside of cluster mode.
+    pub fn group_by_slot(ns: &Namespace, keys: Vec<String>) -> Vec<Vec<String>> {
+        if !ns.is_cluster() {
+            return vec![keys];
+        }
+
+        let mut groups = BTreeMap::<u16, Vec<String>>::new();
+        for key in keys {
+            groups.entry(key_slot(&key)).or_default().push(key);
+        }
+
+        groups.into_values().collect()
+    }
+
+    /// SCAN patterns of keys of records of `M`, including records moved when soft deleted
+    pub fn record_patterns<M: RedisModel>(ns: &Namespace) -> Vec<String> {
+        let mut patterns = vec![M::_scan_pattern(ns)];
+        if M::_soft_delete() == Some(SoftDelete::Move) {
+            patterns.push(M::_deleted_scan_pattern(ns));
+        }
+        patterns
+    }
+
+    /// Pipeline persisting upgrades of fetched `values` of `keys`, recording failures in `progress`.
+    pub fn batch_to_upgrades(
+        upgrade: UpgradeFn,
+        keys: Vec<String>,
+        values: Vec<Value>,
+        progress: &mut MigrationProgress,
+    ) -> Option<Pipeline> {
+        let mut pipe = redis::pipe();
+        let mut pending = false;
+
+        for (key, value) in keys.into_iter().zip(values) {
+            progress.scanned += 1;
+            match upgrade(&key, value) {
+                Ok(Some(cmd)) => {
+                    pipe.add_command(cmd);
+                    pending = true;
+                }
+                Ok(None) => {}
+                Err(err) => progress.failed.push((key, err.to_string())),
+            }
+        }
+
+        pending.then_some(pipe)
+    }
+
+    pub fn batch_to_models<M: RedisModel>(
+        ns: &Namespace,
+        spec: &ScanSpec<M>,
+        keys: Vec<String>,
+        values: Vec<Value>,
+    ) -> Vec<RedisResult<M>> {
+        keys.into_iter()
+            .zip(values)
+            .filter_map(|(key, value)| match (spec.parse)(value) {
+                Ok(Some(mut model)) => {
+                    let pk = model._set_pk(M::_strip_pk(ns, &key));
+                    Some(pk.map(|_| model))
+                }
+                Ok(None) => None,
+                Err(err) => Some(Err(err)),
+            })
+            .collect()
+    }
+}
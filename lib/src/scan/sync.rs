@@ -0,0 +1,264 @@
+use super::{cmds, transformers, ScanSpec, UpgradeFn};
+use crate::migration::MigrationProgress;
+use crate::soft_delete::{self, Storage};
+use crate::{Namespace, RedisModel, WithNamespace};
+use redis::{RedisResult, Value};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+struct ScanIter<'a, M, C> {
+    conn: &'a mut C,
+    ns: Namespace,
+    spec: ScanSpec<M>,
+    batch_size: usize,
+    cursor: Option<u64>,
+    buffer: VecDeque<RedisResult<M>>,
+}
+
+impl<'a, M: RedisModel, C: WithNamespace> ScanIter<'a, M, C> {
+    fn next_batch(&mut self, cursor: u64) -> RedisResult<()> {
+        let (cursor, keys): (u64, Vec<String>) =
+            cmds::scan::<M>(&self.ns, cursor, self.batch_size, self.spec.key_type)?
+                .query(self.conn.connection())?;
+
+        self.cursor = (cursor != 0).then_some(cursor);
+
+        if keys.is_empty() {
+            return Ok(());
+        }
+
+        for keys in transformers::group_by_slot(&self.ns, keys) {
+            let values: Vec<Value> =
+                cmds::fetch(&self.ns, &self.spec, &keys)?.query(self.conn.connection())?;
+
+            let models = transformers::batch_to_models(&self.ns, &self.spec, keys, values);
+            self.buffer.extend(models);
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a, M: RedisModel, C: WithNamespace> Iterator for ScanIter<'a, M, C> {
+    type Item = RedisResult<M>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.buffer.pop_front() {
+                return Some(item);
+            }
+
+            let cursor = self.cursor?;
+
+            if let Err(err) = self.next_batch(cursor) {
+                self.cursor = None;
+                return Some(Err(err));
+            }
+        }
+    }
+}
+
+/// Iterate all models stored under `M` prefix, fetching `batch_size` keys per round trip.
+pub fn scan<'a, M, C>(
+    spec: ScanSpec<M>,
+    batch_size: usize,
+    conn: &'a mut C,
+) -> Box<dyn Iterator<Item = RedisResult<M>> + 'a>
+where
+    M: RedisModel + 'a,
+    C: WithNamespace,
+{
+    Box::new(ScanIter {
+        ns: conn.namespace().clone(),
+        conn,
+        spec,
+        batch_size,
+        cursor: Some(0),
+        buffer: VecDeque::new(),
+    })
+}
+
+/// Count records stored under `M` prefix, scanning `batch_size` keys per round trip.
+/// With the soft delete `storage` of `M`, records soft deleted in place aren't counted.
+///
+/// The count is approximate: keys added or removed during the scan may or may not be counted,
+/// and keys SCAN returns more than once, e.g. while the keyspace is rehashed, are counted again.
+/// Models aren't tied to a search index redis-om could count with, exact counts from FT.SEARCH
+/// take the index name instead, see `count_indexed` of the models.
+pub fn count<M, C>(
+    key_type: &str,
+    storage: Option<&Storage>,
+    batch_size: usize,
+    conn: &mut C,
+) -> RedisResult<usize>
+where
+    M: RedisModel,
+    C: WithNamespace,
+{
+    let ns = conn.namespace().clone();
+    let mut cursor = 0;
+    let mut count = 0;
+
+    loop {
+        let (next, keys): (u64, Vec<String>) =
+            cmds::scan::<M>(&ns, cursor, batch_size, key_type)?.query(conn.connection())?;
+
+        match storage {
+            None => count += keys.len(),
+            Some(storage) => {
+                for keys in transformers::group_by_slot(&ns, keys) {
+                    if !keys.is_empty() {
+                        let live: usize =
+                            soft_delete::count_live(storage, &keys).query(conn.connection())?;
+                        count += live;
+                    }
+                }
+            }
+        }
+
+        if next == 0 {
+            return Ok(count);
+        }
+
+        cursor = next;
+    }
+}
+
+/// Unlink all keys of `M` records in batches, soft deleted ones included, returning the number
+/// of unlinked keys.
+///
+/// With `dry_run` nothing is unlinked and the number of matching keys is returned instead.
+pub fn unlink_all<M, C>(
+    key_type: &str,
+    batch_size: usize,
+    dry_run: bool,
+    conn: &mut C,
+) -> RedisResult<usize>
+where
+    M: RedisModel,
+    C: WithNamespace,
+{
+    let ns = conn.namespace().clone();
+    let mut unlinked = 0;
+
+    for pattern in transformers::record_patterns::<M>(&ns) {
+        let mut cursor = 0;
+
+        loop {
+            let (next, keys): (u64, Vec<String>) =
+                cmds::scan_pattern(&pattern, cursor, batch_size, key_type)?
+                    .query(conn.connection())?;
+
+            if dry_run {
+                unlinked += keys.len();
+            } else {
+                for keys in transformers::group_by_slot(&ns, keys) {
+                    if !keys.is_empty() {
+                        let count: usize = cmds::unlink(&ns, &keys)?.query(conn.connection())?;
+                        unlinked += count;
+                    }
+                }
+            }
+
+            if next == 0 {
+                break;
+            }
+
+            cursor = next;
+        }
+    }
+
+    Ok(unlinked)
+}
+
+/// Unlink all keys stored under `M` prefix soft deleted before `older_than` ago,
+/// returning the number of unlinked keys.
+pub fn purge_deleted<M, C>(
+    key_type: &str,
+    storage: &Storage,
+    older_than: Duration,
+    batch_size: usize,
+    conn: &mut C,
+) -> RedisResult<usize>
+where
+    M: RedisModel,
+    C: WithNamespace,
+{
+    let ns = conn.namespace().clone();
+    let mut purged = 0;
+
+    for pattern in transformers::record_patterns::<M>(&ns) {
+        let mut cursor = 0;
+
+        loop {
+            let (next, keys): (u64, Vec<String>) =
+                cmds::scan_pattern(&pattern, cursor, batch_size, key_type)?
+                    .query(conn.connection())?;
+
+            for keys in transformers::group_by_slot(&ns, keys) {
+                if !keys.is_empty() {
+                    let count: usize =
+                        soft_delete::purge(storage, &keys, older_than)?.query(conn.connection())?;
+                    purged += count;
+                }
+            }
+
+            if next == 0 {
+                break;
+            }
+
+            cursor = next;
+        }
+    }
+
+    Ok(purged)
+}
+
+/// Persist upgrades of all records stored under `M` prefix of older schema versions, scanning
+/// from `cursor` and reporting progress after each batch.
+pub fn migrate_all<M, C>(
+    spec: ScanSpec<M>,
+    upgrade: UpgradeFn,
+    cursor: u64,
+    batch_size: usize,
+    on_progress: &mut dyn FnMut(&MigrationProgress),
+    conn: &mut C,
+) -> RedisResult<MigrationProgress>
+where
+    M: RedisModel,
+    C: WithNamespace,
+{
+    let ns = conn.namespace().clone();
+    let mut progress = MigrationProgress {
+        cursor,
+        ..Default::default()
+    };
+
+    loop {
+        let (next, keys): (u64, Vec<String>) =
+            cmds::scan::<M>(&ns, progress.cursor, batch_size, spec.key_type)?
+                .query(conn.connection())?;
+
+        for keys in transformers::group_by_slot(&ns, keys) {
+            if keys.is_empty() {
+                continue;
+            }
+
+            let values: Vec<Value> = cmds::fetch(&ns, &spec, &keys)?.query(conn.connection())?;
+
+            if let Some(pipe) =
+                transformers::batch_to_upgrades(upgrade, keys, values, &mut progress)
+            {
+                let upgraded: Vec<bool> = pipe.query(conn.connection())?;
+                progress.migrated += upgraded.into_iter().filter(|upgraded| *upgraded).count();
+            }
+        }
+
+        progress.cursor = next;
+        on_progress(&progress);
+
+        if next == 0 {
+            return Ok(progress);
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use crate::{Namespace, RedisModel, ToPk};
+use redis::{Cmd, ErrorKind, RedisError, RedisResult};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Field holding unix time in milliseconds at which a record was soft deleted.
+pub const DELETED_AT: &str = "__deleted_at";
+
+/// How `delete` soft deletes records, set with `#[redis(soft_delete)]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SoftDelete {
+    /// Set [`DELETED_AT`] on the record, keeping its key.
+    InPlace,
+    /// Set [`DELETED_AT`] and move the record to "{prefix}__deleted:{pk}".
+    Move,
+}
+
+/// Lua function `deleted_at(key)` reading, and statements setting/clearing [`DELETED_AT`]
+/// of `key` for a given redis key type.
+pub(crate) struct Storage {
+    pub deleted_at: &'static str,
+    pub set: &'static str,
+    pub clear: &'static str,
+}
+
+pub(crate) const HASH: Storage = Storage {
+    deleted_at: "local function deleted_at(key) return redis.call('HGET', key, ARGV[1]) end",
+    set: "redis.call('HSETNX', key, ARGV[1], ARGV[2])",
+    clear: "redis.call('HDEL', key, ARGV[1])",
+};
+
+#[cfg(feature = "json")]
+pub(crate) const JSON: Storage = Storage {
+    deleted_at: "local function deleted_at(key) \
+        local raw = redis.call('JSON.GET', key, '$.' .. ARGV[1]) \
+        if raw then return cjson.decode(raw)[1] end \
+    end",
+    set: "redis.call('JSON.SET', key, '$.' .. ARGV[1], ARGV[2], 'NX')",
+    clear: "redis.call('JSON.DEL', key, '$.' .. ARGV[1])",
+};
+
+/// Soft delete record of `pk`, replying whether a live record was found.
+pub(crate) fn delete<M: RedisModel>(
+    storage: &Storage,
+    ns: &Namespace,
+    pk: impl ToPk,
+) -> RedisResult<Cmd> {
+    let set = storage.set;
+    let script = format!(
+        "local key = KEYS[1] \
+        if redis.call('EXISTS', key) == 0 then return 0 end \
+        {set} \
+        if KEYS[2] then redis.call('RENAME', key, KEYS[2]) end \
+        return 1"
+    );
+
+    Ok(eval(
+        script,
+        keys::<M>(ns, pk)?,
+        now_millis(Duration::ZERO)?,
+    ))
+}
+
+/// Restore soft deleted record of `pk`, replying whether a soft deleted record was found.
+/// Moved records aren't restored over a live record saved since.
+pub(crate) fn restore<M: RedisModel>(
+    storage: &Storage,
+    ns: &Namespace,
+    pk: impl ToPk,
+) -> RedisResult<Cmd> {
+    let (deleted_at, clear) = (storage.deleted_at, storage.clear);
+    let script = format!(
+        "{deleted_at} \
+        local key = KEYS[1] \
+        if KEYS[2] and redis.call('EXISTS', KEYS[2]) == 1 then \
+            if redis.call('RENAMENX', KEYS[2], key) == 0 then return 0 end \
+        end \
+        if redis.call('EXISTS', key) == 0 or not deleted_at(key) then return 0 end \
+        {clear} \
+        return 1"
+    );
+
+    Ok(eval(script, keys::<M>(ns, pk)?, 0))
+}
+
+/// Unlink `keys` soft deleted before `older_than` ago, replying the number of unlinked keys.
+pub(crate) fn purge(storage: &Storage, keys: &[String], older_than: Duration) -> RedisResult<Cmd> {
+    let deleted_at = storage.deleted_at;
+    let script = format!(
+        "{deleted_at} \
+        local purged = 0 \
+        for _, key in ipairs(KEYS) do \
+            local at = deleted_at(key) \
+            if at and tonumber(at) <= tonumber(ARGV[2]) then \
+                redis.call('UNLINK', key) \
+                purged = purged + 1 \
+            end \
+        end \
+        return purged"
+    );
+
+    Ok(eval(script, keys.to_vec(), now_millis(older_than)?))
+}
+
+/// Count existing `keys` that aren't soft deleted.
+pub(crate) fn count_live(storage: &Storage, keys: &[String]) -> Cmd {
+    let deleted_at = storage.deleted_at;
+    let script = format!(
+        "{deleted_at} \
+        local live = 0 \
+        for _, key in ipairs(KEYS) do \
+            if redis.call('EXISTS', key) == 1 and not deleted_at(key) then \
+                live = live + 1 \
+            end \
+        end \
+        return live"
+    );
+
+    eval(script, keys.to_vec(), 0)
+}
+
+/// Error of `get` for soft deleted records.
+pub(crate) fn deleted_error() -> RedisError {
+    RedisError::from((ErrorKind::ResponseError, "record is soft deleted"))
+}
+
+/// Keys of record `pk`, followed by its soft deleted key when records are moved.
+fn keys<M: RedisModel>(ns: &Namespace, pk: impl ToPk) -> RedisResult<Vec<String>> {
+    let key = M::_fmt_key(ns, &pk.to_pk());
+    let deleted_key = (M::_soft_delete() == Some(SoftDelete::Move))
+        .then(|| M::_fmt_deleted_key(ns, M::_strip_pk(ns, &key)));
+    let keys = std::iter::once(key).chain(deleted_key).collect::<Vec<_>>();
+
+    ns.ensure_same_slot(&keys)?;
+
+    Ok(keys)
+}
+
+fn eval(script: String, keys: Vec<String>, millis: u64) -> Cmd {
+    let mut cmd = redis::cmd("EVAL");
+    cmd.arg(script)
+        .arg(keys.len())
+        .arg(keys)
+        .arg(DELETED_AT)
+        .arg(millis);
+
+    cmd
+}
+
+/// Unix time in milliseconds `ago` before now.
+fn now_millis(ago: Duration) -> RedisResult<u64> {
+    SystemTime::now()
+        .checked_sub(ago)
+        .and_then(|at| at.duration_since(UNIX_EPOCH).ok())
+        .map(|at| at.as_millis() as u64)
+        .ok_or_else(|| RedisError::from((ErrorKind::ClientError, "time is before unix epoch")))
+}
@@ -1,18 +1,30 @@
-use redis::aio::ConnectionLike;
 use redis::streams::StreamMaxlen;
 use redis::{FromRedisValue, RedisResult, ToRedisArgs};
 
 use super::cmds;
-use super::message::Message;
-use super::reply::StreamReadReply;
+use super::dead_letter::DeadLetter;
+use super::entry_id::StreamEntryId;
+use super::group::StartId;
+use super::info::{
+    ClaimOptions, ConsumerInfo, GroupInfo, PendingEntry, PendingQuery, PendingSummary, StreamInfo,
+};
+use super::message::TypedMessage;
+use super::publish::PublishOptions;
+use super::range::StreamRange;
+use super::reply::{StreamAutoclaimReply, StreamReadReply};
 use super::transformers;
+use crate::WithNamespace;
+use futures::stream::{self, BoxStream, StreamExt};
+use std::collections::VecDeque;
+use std::time::Duration;
 
-impl Message {
-    pub async fn ack<Data: StreamModel, C: ConnectionLike + Send>(
+impl<D> TypedMessage<D> {
+    /// Acknowledge message for its group
+    pub async fn ack<S: StreamModel<Data = D>, C: WithNamespace + Send>(
         &self,
         conn: &mut C,
     ) -> RedisResult<()> {
-        Data::ack(&self.group, &[&self.id], conn).await
+        S::ack(&self.group, &[&self.id], conn).await
     }
 }
 
@@ -31,90 +43,342 @@ pub trait StreamModel: Sized + Send {
     /// Consumer Name
     fn consumer_name(&self) -> &str;
 
+    /// Get length streams are trimmed to on publish, set with `#[redis(maxlen = N)]`
+    fn maxlen() -> Option<StreamMaxlen> {
+        None
+    }
+
     /// Publish self to stream, returning event id
-    async fn publish<C: ConnectionLike + Send>(
+    async fn publish<C: WithNamespace + Send>(
         data: &Self::Data,
         conn: &mut C,
     ) -> RedisResult<String> {
-        cmds::publish::<Self, _>(data)?.query_async(conn).await
+        cmds::publish::<Self, _>(conn.namespace(), data, PublishOptions::new())?
+            .query_async(conn.connection())
+            .await
+    }
+
+    /// Publish self to stream with `options`, returning event id, or `None` when the stream
+    /// doesn't exist and [`PublishOptions::nomkstream`] is set
+    async fn publish_with<C: WithNamespace + Send>(
+        data: &Self::Data,
+        options: PublishOptions,
+        conn: &mut C,
+    ) -> RedisResult<Option<String>> {
+        cmds::publish::<Self, _>(conn.namespace(), data, options)?
+            .query_async(conn.connection())
+            .await
     }
 
     /// Ensure group stream exists for [`Self::stream_key`], creates a new if it doesn't exists.
     /// Errors if it fails to ensure stream
-    async fn ensure_group_stream<C: ConnectionLike + Send>(&self, conn: &mut C) -> RedisResult<()> {
-        let res = cmds::ensure_group_stream::<Self>(self)?
-            .query_async(conn)
+    async fn ensure_group_stream<C: WithNamespace + Send>(&self, conn: &mut C) -> RedisResult<()> {
+        self.ensure_group_stream_from(StartId::Latest, None, conn)
+            .await
+    }
+
+    /// Ensure group stream exists like [`Self::ensure_group_stream`], a new group delivering
+    /// messages added after `start`. `entries_read` sets the number of messages the group
+    /// already read, used by redis 7 to compute its lag.
+    async fn ensure_group_stream_from<C: WithNamespace + Send>(
+        &self,
+        start: StartId,
+        entries_read: Option<u64>,
+        conn: &mut C,
+    ) -> RedisResult<()> {
+        let group = self.group_name();
+        let res = cmds::ensure_group_stream::<Self>(conn.namespace(), group, &start, entries_read)
+            .query_async(conn.connection())
             .await;
         transformers::ensure_group_stream_success(res)
     }
 
     /// Read from [`Self::stream_key`] with group name and consumer name.
-    async fn read<C: ConnectionLike + Send>(
+    async fn read<C: WithNamespace + Send>(
         &self,
         read_count: Option<usize>,
         block_interval: Option<usize>,
         conn: &mut C,
-    ) -> RedisResult<Vec<Message>> {
-        cmds::read::<Self>(self, read_count, block_interval)?
-            .query_async::<_, StreamReadReply>(conn)
+    ) -> RedisResult<Vec<TypedMessage<Self::Data>>> {
+        let id = cmds::NEW_MESSAGES;
+        cmds::read::<Self>(conn.namespace(), self, read_count, block_interval, id)?
+            .query_async::<_, StreamReadReply>(conn.connection())
             .await
             .map(|reply| transformers::stream_read_reply_to_messages(self, reply))?
     }
 
+    /// Read messages pending for consumer name, i.e. delivered but not acknowledged, with ids
+    /// greater than `start_id`, e.g. [`StreamEntryId::MIN`] to read them all after a restart.
+    ///
+    /// Messages carry their delivery count and idle time. Messages deleted since their
+    /// delivery fail to decode.
+    async fn read_pending<C: WithNamespace + Send>(
+        &self,
+        start_id: StreamEntryId,
+        count: Option<usize>,
+        conn: &mut C,
+    ) -> RedisResult<Vec<TypedMessage<Self::Data>>>
+    where
+        Self::Data: Send,
+    {
+        let messages = cmds::read::<Self>(conn.namespace(), self, count, None, start_id)?
+            .query_async::<_, StreamReadReply>(conn.connection())
+            .await
+            .map(|reply| transformers::stream_read_reply_to_messages(self, reply))??;
+        with_pending::<Self, C>(self.group_name(), messages, conn).await
+    }
+
     /// Abstraction with default options and without a group.
-    async fn read_no_group<C: ConnectionLike + Send>(
+    async fn read_no_group<C: WithNamespace + Send>(
         id: impl AsRef<str> + Send,
         conn: &mut C,
-    ) -> RedisResult<Vec<Message>> {
-        cmds::read_no_group::<Self>(id)?
-            .query_async::<_, StreamReadReply>(conn)
+    ) -> RedisResult<Vec<TypedMessage<Self::Data>>> {
+        cmds::read_no_group::<Self>(conn.namespace(), id)?
+            .query_async::<_, StreamReadReply>(conn.connection())
             .await
             .map(transformers::stream_read_no_group_reply_to_messages)?
     }
 
     /// Autoclaim an event and return a stream of messages found during the autoclaim.
-    async fn autoclaim<C: ConnectionLike + Send>(
+    ///
+    /// Returns the cursor of the next autoclaim, [`StreamEntryId::MIN`] once the whole
+    /// pending entries list was scanned. Messages carry their delivery count and idle time.
+    async fn autoclaim<C: WithNamespace + Send>(
         group: impl AsRef<str> + Send,
         consumer: impl AsRef<str> + Send,
         min_idle_time: usize,
-        last_autocalim_id: impl AsRef<str> + Send,
+        last_autocalim_id: StreamEntryId,
         read_count: Option<usize>,
         conn: &mut C,
-    ) -> RedisResult<(String, Vec<Message>)> {
+    ) -> RedisResult<(StreamEntryId, Vec<TypedMessage<Self::Data>>)>
+    where
+        Self::Data: Send,
+    {
         let group = group.as_ref();
-        cmds::autoclaim::<Self>(
+        let (next_id, messages) = cmds::autoclaim::<Self>(
+            conn.namespace(),
             group,
             consumer,
             min_idle_time,
             last_autocalim_id,
             read_count,
         )?
-        .query_async(conn)
+        .query_async::<_, StreamAutoclaimReply>(conn.connection())
         .await
-        .map(|(new_id, reply)| {
-            transformers::autoclaim_range_to_id_and_messages(group, new_id, reply)
-        })?
+        .map(|reply| transformers::autoclaim_range_to_id_and_messages(group, reply))??;
+        Ok((
+            next_id,
+            with_pending::<Self, C>(group, messages, conn).await?,
+        ))
     }
 
     /// Acknowledge a given list of ids for group
-    async fn ack<C: ConnectionLike + Send, I: ToRedisArgs + Sync>(
+    async fn ack<C: WithNamespace + Send, I: ToRedisArgs + Sync>(
         group: impl ToRedisArgs + Send,
         ids: &[I],
         conn: &mut C,
     ) -> RedisResult<()> {
-        let cmd = cmds::ack::<Self>(group, ids)?;
+        let cmd = cmds::ack::<Self>(conn.namespace(), group, ids)?;
 
-        cmd.query_async(conn).await
+        cmd.query_async(conn.connection()).await
     }
 
     /// Return the length of the stream
-    async fn len<C: ConnectionLike + Send>(conn: &mut C) -> RedisResult<usize> {
-        cmds::len::<Self>()?.query_async(conn).await
+    async fn len<C: WithNamespace + Send>(conn: &mut C) -> RedisResult<usize> {
+        cmds::len::<Self>(conn.namespace())?
+            .query_async(conn.connection())
+            .await
+    }
+
+    /// Number of times message `id` was delivered to `group`, `None` if it isn't pending
+    async fn delivery_count<C: WithNamespace + Send>(
+        group: impl AsRef<str> + Send,
+        id: StreamEntryId,
+        conn: &mut C,
+    ) -> RedisResult<Option<usize>> {
+        cmds::delivery_count::<Self>(conn.namespace(), group.as_ref(), id)
+            .query_async(conn.connection())
+            .await
+            .map(transformers::delivery_count)
+    }
+
+    /// Move `message` to the dead-letter stream "{stream_key}:dlq" along `error` and the
+    /// number of `deliveries`, acknowledging it in the same transaction.
+    async fn dead_letter<C: WithNamespace + Send>(
+        message: &TypedMessage<Self::Data>,
+        error: impl AsRef<str> + Send,
+        deliveries: usize,
+        conn: &mut C,
+    ) -> RedisResult<()>
+    where
+        Self::Data: Send,
+    {
+        cmds::dead_letter::<Self>(conn.namespace(), message, error.as_ref(), deliveries)?
+            .query_async(conn.connection())
+            .await
+    }
+
+    /// Returns a range of messages of the dead-letter stream, optionally limited to `count`.
+    async fn dead_letters<C: WithNamespace + Send, S: ToRedisArgs + Send, E: ToRedisArgs + Send>(
+        start: S,
+        end: E,
+        count: Option<usize>,
+        conn: &mut C,
+    ) -> RedisResult<Vec<TypedMessage<DeadLetter<Self::Data>>>> {
+        cmds::dead_letters::<Self>(conn.namespace(), start, end, count)
+            .query_async(conn.connection())
+            .await
+            .map(transformers::stream_range_to_messages)?
+    }
+
+    /// Summary of pending messages of `group`
+    async fn pending_summary<C: WithNamespace + Send>(
+        group: impl AsRef<str> + Send,
+        conn: &mut C,
+    ) -> RedisResult<PendingSummary> {
+        cmds::pending_summary::<Self>(conn.namespace(), group.as_ref())
+            .query_async(conn.connection())
+            .await
+    }
+
+    /// Pending messages of `group` matching `query`, with their idle time and delivery count
+    async fn pending<C: WithNamespace + Send>(
+        group: impl AsRef<str> + Send,
+        query: &PendingQuery,
+        conn: &mut C,
+    ) -> RedisResult<Vec<PendingEntry>> {
+        cmds::pending::<Self>(conn.namespace(), group.as_ref(), query)
+            .query_async(conn.connection())
+            .await
+    }
+
+    /// Claim pending messages `ids` of `group` idle for at least `min_idle`, transferring them
+    /// to `consumer`
+    ///
+    /// Claimed messages carry their delivery count and idle time.
+    async fn claim<C: WithNamespace + Send, I: ToRedisArgs + Sync>(
+        group: impl AsRef<str> + Send,
+        consumer: impl AsRef<str> + Send,
+        min_idle: Duration,
+        ids: &[I],
+        options: &ClaimOptions,
+        conn: &mut C,
+    ) -> RedisResult<Vec<TypedMessage<Self::Data>>>
+    where
+        Self::Data: Send,
+    {
+        let group = group.as_ref();
+        let messages = cmds::claim::<Self>(
+            conn.namespace(),
+            group,
+            consumer.as_ref(),
+            min_idle,
+            ids,
+            options,
+            false,
+        )
+        .query_async(conn.connection())
+        .await
+        .map(|reply| transformers::claim_to_messages(group, reply))??;
+        with_pending::<Self, C>(group, messages, conn).await
+    }
+
+    /// Claim like [`Self::claim`] with JUSTID, returning ids of claimed messages without
+    /// incrementing their delivery count
+    async fn claim_ids<C: WithNamespace + Send, I: ToRedisArgs + Sync>(
+        group: impl AsRef<str> + Send,
+        consumer: impl AsRef<str> + Send,
+        min_idle: Duration,
+        ids: &[I],
+        options: &ClaimOptions,
+        conn: &mut C,
+    ) -> RedisResult<Vec<String>> {
+        cmds::claim::<Self>(
+            conn.namespace(),
+            group.as_ref(),
+            consumer.as_ref(),
+            min_idle,
+            ids,
+            options,
+            true,
+        )
+        .query_async(conn.connection())
+        .await
+    }
+
+    /// Details of [`Self::stream_key`]
+    async fn info<C: WithNamespace + Send>(conn: &mut C) -> RedisResult<StreamInfo> {
+        cmds::info_stream::<Self>(conn.namespace())
+            .query_async(conn.connection())
+            .await
+    }
+
+    /// Details of consumer groups of [`Self::stream_key`]
+    async fn groups<C: WithNamespace + Send>(conn: &mut C) -> RedisResult<Vec<GroupInfo>> {
+        cmds::info_groups::<Self>(conn.namespace())
+            .query_async(conn.connection())
+            .await
+    }
+
+    /// Details of consumers of `group`
+    async fn consumers<C: WithNamespace + Send>(
+        group: impl AsRef<str> + Send,
+        conn: &mut C,
+    ) -> RedisResult<Vec<ConsumerInfo>> {
+        cmds::info_consumers::<Self>(conn.namespace(), group.as_ref())
+            .query_async(conn.connection())
+            .await
+    }
+
+    /// Delete `consumer` of `group`, returning the number of its pending messages, which are lost
+    async fn delete_consumer<C: WithNamespace + Send>(
+        group: impl AsRef<str> + Send,
+        consumer: impl AsRef<str> + Send,
+        conn: &mut C,
+    ) -> RedisResult<usize> {
+        cmds::delete_consumer::<Self>(conn.namespace(), group.as_ref(), consumer.as_ref())
+            .query_async(conn.connection())
+            .await
+    }
+
+    /// Delete consumers of `group` with generated names, i.e. of managers created with `new`,
+    /// idle for at least `min_idle` and without pending messages, returning their names
+    async fn cleanup_consumers<C: WithNamespace + Send>(
+        group: impl AsRef<str> + Send,
+        min_idle: Duration,
+        conn: &mut C,
+    ) -> RedisResult<Vec<String>> {
+        cmds::cleanup_consumers::<Self>(conn.namespace(), group.as_ref(), min_idle)
+            .query_async(conn.connection())
+            .await
+    }
+
+    /// Set last delivered id of `group` to `id`, e.g. `$` to skip or `0` to replay all messages
+    async fn set_group_id<C: WithNamespace + Send>(
+        group: impl AsRef<str> + Send,
+        id: impl AsRef<str> + Send,
+        conn: &mut C,
+    ) -> RedisResult<()> {
+        cmds::set_group_id::<Self>(conn.namespace(), group.as_ref(), id.as_ref())
+            .query_async(conn.connection())
+            .await
+    }
+
+    /// Destroy `group` along its consumers and pending messages, returning whether it existed
+    async fn destroy_group<C: WithNamespace + Send>(
+        group: impl AsRef<str> + Send,
+        conn: &mut C,
+    ) -> RedisResult<bool> {
+        cmds::destroy_group::<Self>(conn.namespace(), group.as_ref())
+            .query_async(conn.connection())
+            .await
     }
 
     /// Trim a stream to a MAXLEN count.
-    async fn trim<C: ConnectionLike + Send>(maxlen: StreamMaxlen, conn: &mut C) -> RedisResult<()> {
-        cmds::trim::<Self>(maxlen)?.query_async(conn).await
+    async fn trim<C: WithNamespace + Send>(maxlen: StreamMaxlen, conn: &mut C) -> RedisResult<()> {
+        cmds::trim::<Self>(conn.namespace(), maxlen)?
+            .query_async(conn.connection())
+            .await
     }
 
     /// Returns a range of messages.
@@ -125,7 +389,7 @@ pub trait StreamModel: Sized + Send {
     /// You can pass message `id` to both `start` and `end`.
     ///
     async fn range_count<
-        C: ConnectionLike + Send,
+        C: WithNamespace + Send,
         S: ToRedisArgs + Send,
         E: ToRedisArgs + Send,
         N: ToRedisArgs + Send,
@@ -134,31 +398,112 @@ pub trait StreamModel: Sized + Send {
         end: E,
         count: N,
         conn: &mut C,
-    ) -> RedisResult<Vec<Message>> {
-        cmds::range_count::<Self, _, _, _>(start, end, count)?
-            .query_async(conn)
+    ) -> RedisResult<Vec<TypedMessage<Self::Data>>> {
+        cmds::range_count::<Self, _, _, _>(conn.namespace(), start, end, count)?
+            .query_async(conn.connection())
             .await
             .map(transformers::stream_range_to_messages)?
     }
 
     /// A method for paginating the stream
-    async fn range<C: ConnectionLike + Send, S: ToRedisArgs + Send, E: ToRedisArgs + Send>(
+    async fn range<C: WithNamespace + Send, S: ToRedisArgs + Send, E: ToRedisArgs + Send>(
         start: S,
         end: E,
         conn: &mut C,
-    ) -> RedisResult<Vec<Message>> {
-        cmds::range::<Self, _, _>(start, end)?
-            .query_async(conn)
+    ) -> RedisResult<Vec<TypedMessage<Self::Data>>> {
+        cmds::range::<Self, _, _>(conn.namespace(), start, end)?
+            .query_async(conn.connection())
             .await
             .map(transformers::stream_range_to_messages)?
     }
 
-    /// A helper method for automatically returning all messages in a stream by `key`.
-    /// **Use with caution!**
-    async fn range_all<C: ConnectionLike + Send>(conn: &mut C) -> RedisResult<Vec<Message>> {
-        cmds::range_all::<Self>()?
-            .query_async(conn)
+    /// Returns messages of `range`, in its order
+    async fn range_with<C: WithNamespace + Send>(
+        range: &StreamRange,
+        conn: &mut C,
+    ) -> RedisResult<Vec<TypedMessage<Self::Data>>> {
+        cmds::range_with::<Self>(conn.namespace(), range)
+            .query_async(conn.connection())
             .await
             .map(transformers::stream_range_to_messages)?
     }
+
+    /// Stream messages of `range` in its order, fetching `page_size` messages per round trip.
+    /// The count of `range` is ignored.
+    fn paginate<'a, C>(
+        range: StreamRange,
+        page_size: usize,
+        conn: &'a mut C,
+    ) -> BoxStream<'a, RedisResult<TypedMessage<Self::Data>>>
+    where
+        C: WithNamespace + Send,
+        Self::Data: Send + 'a,
+    {
+        let state = RangePages {
+            conn,
+            range: Some(range),
+            page_size: page_size.max(1),
+            buffer: VecDeque::new(),
+        };
+
+        stream::unfold(state, |mut state| async move {
+            loop {
+                if let Some(message) = state.buffer.pop_front() {
+                    return Some((Ok(message), state));
+                }
+
+                let range = state.range.take()?;
+                let page = range.clone().count(state.page_size);
+                let messages = match Self::range_with(&page, state.conn).await {
+                    Ok(messages) => messages,
+                    Err(err) => return Some((Err(err), state)),
+                };
+
+                // A short page is the last one
+                if messages.len() == state.page_size {
+                    state.range = messages.last().map(|last| range.following(last.id));
+                }
+                state.buffer.extend(messages);
+            }
+        })
+        .boxed()
+    }
+
+    /// A helper method for automatically returning all messages in a stream by `key`, oldest
+    /// first. **Use with caution!**, see [`Self::paginate`] for large streams
+    async fn range_all<C: WithNamespace + Send>(
+        conn: &mut C,
+    ) -> RedisResult<Vec<TypedMessage<Self::Data>>> {
+        cmds::range_all::<Self>(conn.namespace())?
+            .query_async(conn.connection())
+            .await
+            .map(transformers::stream_range_to_messages)?
+    }
+}
+
+/// Set delivery count and idle time of `messages` pending for `group`
+async fn with_pending<S: StreamModel, C: WithNamespace + Send>(
+    group: &str,
+    messages: Vec<TypedMessage<S::Data>>,
+    conn: &mut C,
+) -> RedisResult<Vec<TypedMessage<S::Data>>> {
+    if messages.is_empty() {
+        return Ok(messages);
+    }
+
+    let ids = messages
+        .iter()
+        .map(|message| message.id)
+        .collect::<Vec<_>>();
+    cmds::pending_entries::<S>(conn.namespace(), group, &ids)
+        .query_async(conn.connection())
+        .await
+        .map(|entries| transformers::with_pending(messages, entries))
+}
+
+struct RangePages<'a, D, C> {
+    conn: &'a mut C,
+    range: Option<StreamRange>,
+    page_size: usize,
+    buffer: VecDeque<TypedMessage<D>>,
 }
@@ -0,0 +1,85 @@
+use super::entry_id::StreamEntryId;
+use redis::{ErrorKind, FromRedisValue, RedisError, RedisResult, RedisWrite, ToRedisArgs, Value};
+use std::collections::HashMap;
+
+/// Suffix of dead-letter stream keys, i.e. "{stream}:dlq"
+pub(crate) const DLQ_SUFFIX: &str = "dlq";
+
+const ID: &str = "__dlq_id";
+const GROUP: &str = "__dlq_group";
+const ERROR: &str = "__dlq_error";
+const DELIVERIES: &str = "__dlq_deliveries";
+
+/// Message moved to the dead-letter stream after failing too many deliveries.
+///
+/// Stored as the original fields, followed by "__dlq_id", "__dlq_group", "__dlq_error" and
+/// "__dlq_deliveries" fields.
+#[derive(Debug, Clone)]
+pub struct DeadLetter<D> {
+    /// ID of the original message
+    pub id: StreamEntryId,
+    /// Group failing to handle the message
+    pub group: String,
+    /// Error of the last failed delivery
+    pub error: String,
+    /// Number of deliveries of the message
+    pub deliveries: usize,
+    /// Data of the original message
+    pub data: D,
+}
+
+impl<D: FromRedisValue> FromRedisValue for DeadLetter<D> {
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        let mut fields = HashMap::<String, Value>::from_redis_value(v)?;
+        let mut take = |field: &str| {
+            fields.remove(field).ok_or_else(|| {
+                RedisError::from((
+                    ErrorKind::TypeError,
+                    "invalid dead letter",
+                    format!("missing {field:?}"),
+                ))
+            })
+        };
+
+        Ok(Self {
+            id: StreamEntryId::from_redis_value(&take(ID)?)?,
+            group: String::from_redis_value(&take(GROUP)?)?,
+            error: String::from_redis_value(&take(ERROR)?)?,
+            deliveries: usize::from_redis_value(&take(DELIVERIES)?)?,
+            data: D::from_redis_value(v)?,
+        })
+    }
+}
+
+/// Arguments of XADD of message of `id` with `value` to a dead-letter stream.
+pub(crate) struct DeadLetterArgs<'a> {
+    pub id: StreamEntryId,
+    pub group: &'a str,
+    pub value: &'a Value,
+    pub error: &'a str,
+    pub deliveries: usize,
+}
+
+impl ToRedisArgs for DeadLetterArgs<'_> {
+    fn write_redis_args<W: ?Sized + RedisWrite>(&self, out: &mut W) {
+        if let Value::Bulk(items) = self.value {
+            for item in items {
+                match item {
+                    Value::Data(data) => out.write_arg(data),
+                    Value::Int(int) => out.write_arg_fmt(int),
+                    Value::Status(status) => out.write_arg(status.as_bytes()),
+                    _ => out.write_arg(b""),
+                }
+            }
+        }
+
+        out.write_arg(ID.as_bytes());
+        out.write_arg_fmt(self.id);
+        for (field, value) in [(GROUP, self.group), (ERROR, self.error)] {
+            out.write_arg(field.as_bytes());
+            out.write_arg(value.as_bytes());
+        }
+        out.write_arg(DELIVERIES.as_bytes());
+        out.write_arg_fmt(self.deliveries);
+    }
+}
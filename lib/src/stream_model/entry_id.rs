@@ -0,0 +1,68 @@
+use redis::{ErrorKind, FromRedisValue, RedisError, RedisResult, RedisWrite, ToRedisArgs, Value};
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// ID of a stream message, "{millis}-{seq}": the unix time in milliseconds it was added at,
+/// and its sequence number among messages added the same millisecond.
+///
+/// IDs are ordered like messages of a stream.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StreamEntryId {
+    /// Unix time in milliseconds
+    pub millis: u64,
+    /// Sequence number within `millis`
+    pub seq: u64,
+}
+
+impl StreamEntryId {
+    /// Smallest ID, i.e. "0-0"
+    pub const MIN: Self = Self::new(0, 0);
+    /// Greatest ID
+    pub const MAX: Self = Self::new(u64::MAX, u64::MAX);
+
+    /// Create ID of `seq`th message added at `millis`
+    pub const fn new(millis: u64, seq: u64) -> Self {
+        Self { millis, seq }
+    }
+
+    /// Time message was added at
+    pub fn to_system_time(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(self.millis)
+    }
+}
+
+impl fmt::Display for StreamEntryId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.millis, self.seq)
+    }
+}
+
+/// Parse "{millis}-{seq}", or "{millis}" with sequence number 0
+impl FromStr for StreamEntryId {
+    type Err = RedisError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (millis, seq) = s.split_once('-').unwrap_or((s, "0"));
+        match (millis.parse(), seq.parse()) {
+            (Ok(millis), Ok(seq)) => Ok(Self { millis, seq }),
+            _ => Err(RedisError::from((
+                ErrorKind::TypeError,
+                "invalid stream entry id",
+                s.to_owned(),
+            ))),
+        }
+    }
+}
+
+impl FromRedisValue for StreamEntryId {
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        String::from_redis_value(v)?.parse()
+    }
+}
+
+impl ToRedisArgs for StreamEntryId {
+    fn write_redis_args<W: ?Sized + RedisWrite>(&self, out: &mut W) {
+        out.write_arg_fmt(self);
+    }
+}
@@ -0,0 +1,23 @@
+use redis::{RedisWrite, ToRedisArgs};
+
+/// ID after which a new consumer group starts delivering messages.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum StartId {
+    /// Deliver every message of the stream, i.e. `0`
+    Beginning,
+    /// Deliver messages added after the group is created, i.e. `$`
+    #[default]
+    Latest,
+    /// Deliver messages added after message `id`
+    Id(String),
+}
+
+impl ToRedisArgs for StartId {
+    fn write_redis_args<W: ?Sized + RedisWrite>(&self, out: &mut W) {
+        match self {
+            StartId::Beginning => out.write_arg(b"0"),
+            StartId::Latest => out.write_arg(b"$"),
+            StartId::Id(id) => out.write_arg(id.as_bytes()),
+        }
+    }
+}
@@ -0,0 +1,313 @@
+use redis::{ErrorKind, FromRedisValue, RedisError, RedisResult, RedisWrite, ToRedisArgs, Value};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Summary of the pending entries list of a group, as replied by XPENDING.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PendingSummary {
+    /// Number of pending messages
+    pub count: usize,
+    /// Smallest id of pending messages
+    pub min_id: Option<String>,
+    /// Greatest id of pending messages
+    pub max_id: Option<String>,
+    /// Consumers with pending messages, along their number of pending messages
+    pub consumers: Vec<(String, usize)>,
+}
+
+impl FromRedisValue for PendingSummary {
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        let (count, min_id, max_id, consumers): (usize, _, _, Vec<Value>) =
+            FromRedisValue::from_redis_value(v)?;
+
+        Ok(Self {
+            count,
+            min_id,
+            max_id,
+            // Parsed one by one, as a `Vec` of tuples is parsed from a flat list
+            consumers: consumers
+                .iter()
+                .map(FromRedisValue::from_redis_value)
+                .collect::<RedisResult<_>>()?,
+        })
+    }
+}
+
+/// Pending message of a group, as replied by the extended form of XPENDING.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingEntry {
+    /// ID of the message
+    pub id: String,
+    /// Consumer the message was delivered to
+    pub consumer: String,
+    /// Time since the message was last delivered
+    pub idle: Duration,
+    /// Number of times the message was delivered
+    pub deliveries: usize,
+}
+
+impl FromRedisValue for PendingEntry {
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        let (id, consumer, idle, deliveries): (String, String, u64, usize) =
+            FromRedisValue::from_redis_value(v)?;
+
+        Ok(Self {
+            id,
+            consumer,
+            idle: Duration::from_millis(idle),
+            deliveries,
+        })
+    }
+}
+
+/// Filters of the extended form of XPENDING, listing up to 10 messages of any consumer by default.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingQuery {
+    start: String,
+    end: String,
+    count: usize,
+    min_idle: Option<Duration>,
+    consumer: Option<String>,
+}
+
+impl Default for PendingQuery {
+    fn default() -> Self {
+        Self {
+            start: "-".into(),
+            end: "+".into(),
+            count: 10,
+            min_idle: None,
+            consumer: None,
+        }
+    }
+}
+
+impl PendingQuery {
+    /// Create query of up to 10 pending messages of any consumer
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// List messages with ids between `start` and `end`, defaults to `-` and `+`
+    pub fn range(mut self, start: impl Into<String>, end: impl Into<String>) -> Self {
+        self.start = start.into();
+        self.end = end.into();
+        self
+    }
+
+    /// List up to `count` messages
+    pub fn count(mut self, count: usize) -> Self {
+        self.count = count;
+        self
+    }
+
+    /// List messages idle for at least `min_idle`
+    pub fn min_idle(mut self, min_idle: Duration) -> Self {
+        self.min_idle = Some(min_idle);
+        self
+    }
+
+    /// List messages of `consumer` only
+    pub fn consumer(mut self, consumer: impl Into<String>) -> Self {
+        self.consumer = Some(consumer.into());
+        self
+    }
+}
+
+impl ToRedisArgs for PendingQuery {
+    fn write_redis_args<W: ?Sized + RedisWrite>(&self, out: &mut W) {
+        if let Some(min_idle) = self.min_idle {
+            out.write_arg(b"IDLE");
+            out.write_arg_fmt(min_idle.as_millis());
+        }
+        out.write_arg(self.start.as_bytes());
+        out.write_arg(self.end.as_bytes());
+        out.write_arg_fmt(self.count);
+        if let Some(consumer) = &self.consumer {
+            out.write_arg(consumer.as_bytes());
+        }
+    }
+}
+
+/// Options of XCLAIM.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClaimOptions {
+    idle: Option<Duration>,
+    retry_count: Option<usize>,
+    force: bool,
+}
+
+impl ClaimOptions {
+    /// Create options claiming messages as is
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set idle time of claimed messages, instead of resetting it
+    pub fn idle(mut self, idle: Duration) -> Self {
+        self.idle = Some(idle);
+        self
+    }
+
+    /// Set delivery count of claimed messages, instead of incrementing it
+    pub fn retry_count(mut self, retry_count: usize) -> Self {
+        self.retry_count = Some(retry_count);
+        self
+    }
+
+    /// Claim messages even if they aren't pending, as long as they exist
+    pub fn force(mut self) -> Self {
+        self.force = true;
+        self
+    }
+}
+
+impl ToRedisArgs for ClaimOptions {
+    fn write_redis_args<W: ?Sized + RedisWrite>(&self, out: &mut W) {
+        if let Some(idle) = self.idle {
+            out.write_arg(b"IDLE");
+            out.write_arg_fmt(idle.as_millis());
+        }
+        if let Some(retry_count) = self.retry_count {
+            out.write_arg(b"RETRYCOUNT");
+            out.write_arg_fmt(retry_count);
+        }
+        if self.force {
+            out.write_arg(b"FORCE");
+        }
+    }
+}
+
+/// Stream details, as replied by XINFO STREAM.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamInfo {
+    /// Number of messages
+    pub length: usize,
+    /// Number of consumer groups
+    pub groups: usize,
+    /// ID of the last added message
+    pub last_generated_id: String,
+    /// ID of the first message
+    pub first_entry_id: Option<String>,
+    /// ID of the last message
+    pub last_entry_id: Option<String>,
+    /// Number of keys of the underlying radix tree
+    pub radix_tree_keys: usize,
+    /// Number of nodes of the underlying radix tree
+    pub radix_tree_nodes: usize,
+}
+
+impl FromRedisValue for StreamInfo {
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        let mut fields = Fields::from_redis_value(v)?;
+
+        Ok(Self {
+            length: fields.get("length")?,
+            groups: fields.get("groups")?,
+            last_generated_id: fields.get("last-generated-id")?,
+            first_entry_id: fields.entry_id("first-entry")?,
+            last_entry_id: fields.entry_id("last-entry")?,
+            radix_tree_keys: fields.get("radix-tree-keys")?,
+            radix_tree_nodes: fields.get("radix-tree-nodes")?,
+        })
+    }
+}
+
+/// Consumer group details, as replied by XINFO GROUPS.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupInfo {
+    /// Name of the group
+    pub name: String,
+    /// Number of consumers
+    pub consumers: usize,
+    /// Number of pending messages
+    pub pending: usize,
+    /// ID of the last message delivered to the group
+    pub last_delivered_id: String,
+    /// Number of messages read by the group, since redis 7
+    pub entries_read: Option<u64>,
+    /// Number of messages yet to be delivered to the group, since redis 7
+    pub lag: Option<u64>,
+}
+
+impl FromRedisValue for GroupInfo {
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        let mut fields = Fields::from_redis_value(v)?;
+
+        Ok(Self {
+            name: fields.get("name")?,
+            consumers: fields.get("consumers")?,
+            pending: fields.get("pending")?,
+            last_delivered_id: fields.get("last-delivered-id")?,
+            entries_read: fields.get_opt("entries-read")?,
+            lag: fields.get_opt("lag")?,
+        })
+    }
+}
+
+/// Consumer details, as replied by XINFO CONSUMERS.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsumerInfo {
+    /// Name of the consumer
+    pub name: String,
+    /// Number of pending messages
+    pub pending: usize,
+    /// Time since the consumer last attempted an interaction, e.g. a read
+    pub idle: Duration,
+    /// Time since the consumer last read or claimed messages, since redis 7.2
+    pub inactive: Option<Duration>,
+}
+
+impl FromRedisValue for ConsumerInfo {
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        let mut fields = Fields::from_redis_value(v)?;
+
+        Ok(Self {
+            name: fields.get("name")?,
+            pending: fields.get("pending")?,
+            idle: Duration::from_millis(fields.get("idle")?),
+            inactive: fields
+                .get_opt::<i64>("inactive")?
+                .and_then(|inactive| u64::try_from(inactive).ok())
+                .map(Duration::from_millis),
+        })
+    }
+}
+
+/// Field/value pairs of XINFO replies.
+struct Fields(HashMap<String, Value>);
+
+impl Fields {
+    fn get<T: FromRedisValue>(&mut self, field: &str) -> RedisResult<T> {
+        self.get_opt(field)?.ok_or_else(|| {
+            RedisError::from((
+                ErrorKind::TypeError,
+                "invalid XINFO reply",
+                format!("missing {field:?}"),
+            ))
+        })
+    }
+
+    fn get_opt<T: FromRedisValue>(&mut self, field: &str) -> RedisResult<Option<T>> {
+        match self.0.remove(field) {
+            None | Some(Value::Nil) => Ok(None),
+            Some(value) => T::from_redis_value(&value).map(Some),
+        }
+    }
+
+    /// Get ID of `[id, fields]` entry `field`
+    fn entry_id(&mut self, field: &str) -> RedisResult<Option<String>> {
+        match self.0.remove(field) {
+            Some(Value::Bulk(entry)) if !entry.is_empty() => {
+                String::from_redis_value(&entry[0]).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+impl FromRedisValue for Fields {
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        HashMap::from_redis_value(v).map(Self)
+    }
+}
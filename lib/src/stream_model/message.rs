@@ -1,25 +1,136 @@
+use super::entry_id::StreamEntryId;
+use super::info::PendingEntry;
 use super::reply::StreamId;
-use redis::{FromRedisValue, RedisResult, Value};
+use redis::{ErrorKind, FromRedisValue, RedisError, RedisResult, Value};
+use std::fmt;
+use std::sync::OnceLock;
+use std::time::Duration;
 
-pub struct Message {
+/// Message read from a stream, holding data of type `D`, i.e. `StreamModel::Data`.
+///
+/// Data is decoded on first access, or right away with [`TypedMessage::decode`]. A message
+/// that fails to decode only fails itself, not the batch it was read with.
+pub struct TypedMessage<D> {
     /// The ID of this message (generated by Redis).
-    pub id: String,
+    pub id: StreamEntryId,
     /// The group this message belongs to.
     pub group: String,
+    /// Number of times the message was delivered, for messages read or claimed while pending
+    pub deliveries: Option<usize>,
+    /// Time since the message was last delivered, for messages read or claimed while pending
+    pub idle: Option<Duration>,
     /// The value of message
     value: Value,
+    /// Key of the stream message was read from, when read along other streams
+    stream: Option<String>,
+    /// Decoder of `value`
+    decode: fn(&Value) -> RedisResult<D>,
+    /// Outcome of decoding `value`
+    data: OnceLock<Result<D, (ErrorKind, String)>>,
 }
 
-impl Message {
-    pub fn new(value: StreamId, group: String) -> Self {
+impl<D: FromRedisValue> TypedMessage<D> {
+    pub(crate) fn new(value: StreamId, group: String) -> Self {
+        Self::with_decoder(value, group, None, D::from_redis_value)
+    }
+}
+
+impl<D> TypedMessage<D> {
+    /// Create message of `stream`, decoding its value with `decode`
+    pub(crate) fn with_decoder(
+        value: StreamId,
+        group: String,
+        stream: Option<String>,
+        decode: fn(&Value) -> RedisResult<D>,
+    ) -> Self {
         Self {
             id: value.id,
             group,
+            deliveries: None,
+            idle: None,
             value: value.value,
+            stream,
+            decode,
+            data: OnceLock::new(),
+        }
+    }
+
+    /// Copy of message, decoding its data again on access
+    #[cfg(feature = "aio")]
+    pub(crate) fn duplicate(&self) -> Self {
+        Self {
+            id: self.id,
+            group: self.group.clone(),
+            deliveries: self.deliveries,
+            idle: self.idle,
+            value: self.value.clone(),
+            stream: self.stream.clone(),
+            decode: self.decode,
+            data: OnceLock::new(),
         }
     }
 
-    pub fn data<Data: FromRedisValue>(&self) -> RedisResult<Data> {
-        Data::from_redis_value(&self.value)
+    /// Set delivery count and idle time from pending `entry` of message
+    pub(crate) fn set_pending(&mut self, entry: &PendingEntry) {
+        self.deliveries = Some(entry.deliveries);
+        self.idle = Some(entry.idle);
     }
+
+    /// Raw value of message, e.g. to inspect messages failing to decode
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
+    /// Key of the stream message was read from, set for messages of a `MultiStreamManager`
+    pub fn stream(&self) -> Option<&str> {
+        self.stream.as_deref()
+    }
+
+    /// Data of message, decoded on first access
+    pub fn data(&self) -> RedisResult<&D> {
+        self.decoded()
+            .as_ref()
+            .map_err(|(kind, detail)| decode_error(*kind, detail.clone()))
+    }
+
+    /// Take data of message, decoding it unless already decoded
+    pub fn into_data(mut self) -> RedisResult<D> {
+        match self.data.take() {
+            Some(data) => data.map_err(|(kind, detail)| decode_error(kind, detail)),
+            None => (self.decode)(&self.value),
+        }
+    }
+
+    /// Decode data right away rather than on first access
+    pub fn decode(self) -> Self {
+        self.decoded();
+        self
+    }
+
+    /// Whether data of message decodes
+    pub fn is_valid(&self) -> bool {
+        self.decoded().is_ok()
+    }
+
+    fn decoded(&self) -> &Result<D, (ErrorKind, String)> {
+        self.data
+            .get_or_init(|| (self.decode)(&self.value).map_err(|err| (err.kind(), err.to_string())))
+    }
+}
+
+impl<D> fmt::Debug for TypedMessage<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TypedMessage")
+            .field("id", &self.id)
+            .field("group", &self.group)
+            .field("deliveries", &self.deliveries)
+            .field("idle", &self.idle)
+            .field("stream", &self.stream)
+            .field("value", &self.value)
+            .finish()
+    }
+}
+
+fn decode_error(kind: ErrorKind, detail: String) -> RedisError {
+    RedisError::from((kind, "failed to decode stream message", detail))
 }
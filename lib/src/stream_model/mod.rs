@@ -1,46 +1,105 @@
+mod dead_letter;
+mod entry_id;
+mod group;
+mod info;
 mod message;
-mod reply;
+mod publish;
+mod range;
+pub(crate) mod reply;
 
 #[cfg(feature = "aio")]
 mod r#async;
 #[cfg(not(feature = "aio"))]
 mod sync;
 
+pub use dead_letter::DeadLetter;
+pub use entry_id::StreamEntryId;
+pub use group::StartId;
+pub use info::{
+    ClaimOptions, ConsumerInfo, GroupInfo, PendingEntry, PendingQuery, PendingSummary, StreamInfo,
+};
+pub use message::TypedMessage;
+pub use publish::PublishOptions;
+pub use range::StreamRange;
+
 #[cfg(feature = "aio")]
 pub use r#async::StreamModel;
 #[cfg(not(feature = "aio"))]
 pub use sync::StreamModel;
 
-mod cmds {
+pub(crate) mod cmds {
     use redis::{
         streams::{StreamMaxlen, StreamReadOptions},
-        Cmd, RedisResult, ToRedisArgs,
+        Cmd, Pipeline, RedisResult, ToRedisArgs,
     };
 
-    use super::StreamModel;
+    use super::dead_letter::{DeadLetterArgs, DLQ_SUFFIX};
+    use super::entry_id::StreamEntryId;
+    use super::group::StartId;
+    use super::info::{ClaimOptions, PendingQuery};
+    use super::publish::PublishOptions;
+    use super::range::StreamRange;
+    use super::{StreamModel, TypedMessage};
+    use crate::Namespace;
+    use std::time::Duration;
+
+    /// Get stream key "{namespace}:{S::stream_key}"
+    fn key<S: StreamModel>(ns: &Namespace) -> String {
+        ns.model_prefix(S::stream_key())
+    }
+
+    /// XREADGROUP id of messages never delivered to the group
+    pub const NEW_MESSAGES: &str = ">";
+
+    /// Get dead-letter stream key "{namespace}:{S::stream_key}:dlq"
+    fn dlq_key<S: StreamModel>(ns: &Namespace) -> String {
+        format!("{}{}{DLQ_SUFFIX}", key::<S>(ns), ns.get_separator())
+    }
 
-    pub fn publish<S: StreamModel, D: ToRedisArgs>(data: &D) -> RedisResult<Cmd> {
+    pub fn publish<S: StreamModel, D: ToRedisArgs>(
+        ns: &Namespace,
+        data: &D,
+        options: PublishOptions,
+    ) -> RedisResult<Cmd> {
         let mut cmd = redis::cmd("XADD");
-        cmd.arg(S::stream_key()).arg("*").arg(data);
+        cmd.arg(key::<S>(ns))
+            .arg(options.or_maxlen(S::maxlen()))
+            .arg(data);
 
         Ok(cmd)
     }
 
-    pub fn ensure_group_stream<S: StreamModel>(s: &impl StreamModel) -> RedisResult<Cmd> {
+    pub fn ensure_group_stream<S: StreamModel>(
+        ns: &Namespace,
+        group: &str,
+        start: &StartId,
+        entries_read: Option<u64>,
+    ) -> Cmd {
+        ensure_group(&key::<S>(ns), group, start, entries_read)
+    }
+
+    /// Create `group` of stream at `key`, creating the stream if missing
+    pub fn ensure_group(key: &str, group: &str, start: &StartId, entries_read: Option<u64>) -> Cmd {
         let mut cmd = redis::cmd("XGROUP");
         cmd.arg("CREATE")
-            .arg(S::stream_key())
-            .arg(s.group_name())
-            .arg("$")
+            .arg(key)
+            .arg(group)
+            .arg(start)
             .arg("MKSTREAM");
 
-        Ok(cmd)
+        if let Some(entries_read) = entries_read {
+            cmd.arg("ENTRIESREAD").arg(entries_read);
+        }
+
+        cmd
     }
 
     pub fn read<S: StreamModel>(
+        ns: &Namespace,
         s: &impl StreamModel,
         read_count: Option<usize>,
         block_interval: Option<usize>,
+        id: impl ToRedisArgs,
     ) -> RedisResult<Cmd> {
         let mut cmd = redis::cmd("XREADGROUP");
         let mut opts = StreamReadOptions::default().group(s.group_name(), s.consumer_name());
@@ -51,39 +110,34 @@ mod cmds {
             opts = opts.block(block_interval)
         }
 
-        cmd.arg(opts)
-            .arg("STREAMS")
-            .arg(&[S::stream_key()])
-            .arg(&[">"]);
+        cmd.arg(opts).arg("STREAMS").arg(&[key::<S>(ns)]).arg(id);
 
         Ok(cmd)
     }
 
-    pub fn read_no_group<S: StreamModel>(id: impl AsRef<str>) -> RedisResult<Cmd> {
+    pub fn read_no_group<S: StreamModel>(ns: &Namespace, id: impl AsRef<str>) -> RedisResult<Cmd> {
         let mut cmd = redis::cmd("XREAD");
 
-        cmd.arg("STREAMS")
-            .arg(&[S::stream_key()])
-            .arg(&[id.as_ref()]);
+        cmd.arg("STREAMS").arg(&[key::<S>(ns)]).arg(&[id.as_ref()]);
 
         Ok(cmd)
     }
 
     pub fn autoclaim<S: StreamModel>(
+        ns: &Namespace,
         group: &str,
         consumer: impl AsRef<str>,
         min_idle_time: usize,
-        last_autocalim_id: impl AsRef<str>,
+        last_autocalim_id: StreamEntryId,
         read_count: Option<usize>,
     ) -> RedisResult<Cmd> {
-        let id = last_autocalim_id.as_ref();
         let mut cmd = redis::cmd("XAUTOCLAIM");
 
-        cmd.arg(S::stream_key())
+        cmd.arg(key::<S>(ns))
             .arg(group)
             .arg(consumer.as_ref())
             .arg(min_idle_time)
-            .arg(&id);
+            .arg(last_autocalim_id);
 
         if let Some(read_count) = read_count {
             cmd.arg("COUNT").arg(read_count);
@@ -93,34 +147,206 @@ mod cmds {
     }
 
     pub fn ack<S: StreamModel>(
+        ns: &Namespace,
         group: impl ToRedisArgs,
         ids: &[impl ToRedisArgs],
     ) -> RedisResult<Cmd> {
         let mut cmd = redis::cmd("XACK");
-        cmd.arg(S::stream_key()).arg(group).arg(ids);
+        cmd.arg(key::<S>(ns)).arg(group).arg(ids);
         Ok(cmd)
     }
 
-    pub fn trim<S: StreamModel>(maxlen: StreamMaxlen) -> RedisResult<Cmd> {
+    pub fn delivery_count<S: StreamModel>(ns: &Namespace, group: &str, id: StreamEntryId) -> Cmd {
+        let id = id.to_string();
+        pending::<S>(ns, group, &PendingQuery::new().range(&id, &id).count(1))
+    }
+
+    /// XPENDING entry of each of `ids`, in order, empty for ids that aren't pending
+    pub fn pending_entries<S: StreamModel>(
+        ns: &Namespace,
+        group: &str,
+        ids: &[StreamEntryId],
+    ) -> Pipeline {
+        let mut pipe = redis::pipe();
+        for id in ids {
+            pipe.add_command(delivery_count::<S>(ns, group, *id));
+        }
+        pipe
+    }
+
+    pub fn pending_summary<S: StreamModel>(ns: &Namespace, group: &str) -> Cmd {
+        let mut cmd = redis::cmd("XPENDING");
+        cmd.arg(key::<S>(ns)).arg(group);
+        cmd
+    }
+
+    pub fn pending<S: StreamModel>(ns: &Namespace, group: &str, query: &PendingQuery) -> Cmd {
+        let mut cmd = redis::cmd("XPENDING");
+        cmd.arg(key::<S>(ns)).arg(group).arg(query);
+        cmd
+    }
+
+    pub fn claim<S: StreamModel>(
+        ns: &Namespace,
+        group: &str,
+        consumer: &str,
+        min_idle: Duration,
+        ids: &[impl ToRedisArgs],
+        options: &ClaimOptions,
+        just_id: bool,
+    ) -> Cmd {
+        let mut cmd = redis::cmd("XCLAIM");
+        cmd.arg(key::<S>(ns))
+            .arg(group)
+            .arg(consumer)
+            .arg(min_idle.as_millis() as u64)
+            .arg(ids)
+            .arg(options);
+
+        if just_id {
+            cmd.arg("JUSTID");
+        }
+
+        cmd
+    }
+
+    pub fn info_stream<S: StreamModel>(ns: &Namespace) -> Cmd {
+        let mut cmd = redis::cmd("XINFO");
+        cmd.arg("STREAM").arg(key::<S>(ns));
+        cmd
+    }
+
+    pub fn info_groups<S: StreamModel>(ns: &Namespace) -> Cmd {
+        let mut cmd = redis::cmd("XINFO");
+        cmd.arg("GROUPS").arg(key::<S>(ns));
+        cmd
+    }
+
+    pub fn info_consumers<S: StreamModel>(ns: &Namespace, group: &str) -> Cmd {
+        let mut cmd = redis::cmd("XINFO");
+        cmd.arg("CONSUMERS").arg(key::<S>(ns)).arg(group);
+        cmd
+    }
+
+    pub fn delete_consumer<S: StreamModel>(ns: &Namespace, group: &str, consumer: &str) -> Cmd {
+        let mut cmd = redis::cmd("XGROUP");
+        cmd.arg("DELCONSUMER")
+            .arg(key::<S>(ns))
+            .arg(group)
+            .arg(consumer);
+        cmd
+    }
+
+    /// Delete idle consumers of `group` named with a ULID and without pending messages,
+    /// replying their names
+    pub fn cleanup_consumers<S: StreamModel>(
+        ns: &Namespace,
+        group: &str,
+        min_idle: Duration,
+    ) -> Cmd {
+        let script = "\
+            local deleted = {} \
+            for _, fields in ipairs(redis.call('XINFO', 'CONSUMERS', KEYS[1], ARGV[1])) do \
+                local consumer = {} \
+                for i = 1, #fields, 2 do consumer[fields[i]] = fields[i + 1] end \
+                local name = consumer['name'] \
+                if consumer['pending'] == 0 and consumer['idle'] >= tonumber(ARGV[2]) \
+                    and #name == 26 and not name:find('[^0-9A-HJKMNP-TV-Z]') then \
+                    redis.call('XGROUP', 'DELCONSUMER', KEYS[1], ARGV[1], name) \
+                    table.insert(deleted, name) \
+                end \
+            end \
+            return deleted";
+
+        let mut cmd = redis::cmd("EVAL");
+        cmd.arg(script)
+            .arg(1)
+            .arg(key::<S>(ns))
+            .arg(group)
+            .arg(min_idle.as_millis() as u64);
+        cmd
+    }
+
+    pub fn set_group_id<S: StreamModel>(ns: &Namespace, group: &str, id: &str) -> Cmd {
+        let mut cmd = redis::cmd("XGROUP");
+        cmd.arg("SETID").arg(key::<S>(ns)).arg(group).arg(id);
+        cmd
+    }
+
+    pub fn destroy_group<S: StreamModel>(ns: &Namespace, group: &str) -> Cmd {
+        let mut cmd = redis::cmd("XGROUP");
+        cmd.arg("DESTROY").arg(key::<S>(ns)).arg(group);
+        cmd
+    }
+
+    /// XADD `message` to dead-letter stream and acknowledge it, in the same transaction
+    pub fn dead_letter<S: StreamModel>(
+        ns: &Namespace,
+        message: &TypedMessage<S::Data>,
+        error: &str,
+        deliveries: usize,
+    ) -> RedisResult<Pipeline> {
+        let key = key::<S>(ns);
+        let dlq_key = dlq_key::<S>(ns);
+        ns.ensure_same_slot(&[&key, &dlq_key])?;
+
+        let mut pipe = redis::pipe();
+        pipe.atomic()
+            .cmd("XADD")
+            .arg(dlq_key)
+            .arg("*")
+            .arg(DeadLetterArgs {
+                id: message.id,
+                group: &message.group,
+                value: message.value(),
+                error,
+                deliveries,
+            })
+            .ignore()
+            .cmd("XACK")
+            .arg(key)
+            .arg(&message.group)
+            .arg(message.id)
+            .ignore();
+
+        Ok(pipe)
+    }
+
+    pub fn dead_letters<S: StreamModel>(
+        ns: &Namespace,
+        start: impl ToRedisArgs,
+        end: impl ToRedisArgs,
+        count: Option<usize>,
+    ) -> Cmd {
+        let mut cmd = redis::cmd("XRANGE");
+        cmd.arg(dlq_key::<S>(ns)).arg(start).arg(end);
+        if let Some(count) = count {
+            cmd.arg("COUNT").arg(count);
+        }
+        cmd
+    }
+
+    pub fn trim<S: StreamModel>(ns: &Namespace, maxlen: StreamMaxlen) -> RedisResult<Cmd> {
         let mut cmd = redis::cmd("XTRIM");
-        cmd.arg(S::stream_key()).arg(maxlen);
+        cmd.arg(key::<S>(ns)).arg(maxlen);
         Ok(cmd)
     }
 
-    pub fn len<S: StreamModel>() -> RedisResult<Cmd> {
+    pub fn len<S: StreamModel>(ns: &Namespace) -> RedisResult<Cmd> {
         let mut cmd = redis::cmd("XLEN");
-        cmd.arg(S::stream_key());
+        cmd.arg(key::<S>(ns));
         Ok(cmd)
     }
 
     pub fn range_count<S: StreamModel, B: ToRedisArgs, E: ToRedisArgs, C: ToRedisArgs>(
+        ns: &Namespace,
         start: B,
         end: E,
         count: C,
     ) -> RedisResult<Cmd> {
         let mut cmd = redis::cmd("XRANGE");
 
-        cmd.arg(S::stream_key())
+        cmd.arg(key::<S>(ns))
             .arg(start)
             .arg(end)
             .arg("COUNT")
@@ -130,72 +356,112 @@ mod cmds {
     }
 
     pub fn range<S: StreamModel, B: ToRedisArgs, E: ToRedisArgs>(
+        ns: &Namespace,
         start: B,
         end: E,
     ) -> RedisResult<Cmd> {
         let mut cmd = redis::cmd("XRANGE");
 
-        cmd.arg(S::stream_key()).arg(start).arg(end);
+        cmd.arg(key::<S>(ns)).arg(start).arg(end);
 
         Ok(cmd)
     }
 
-    pub fn range_all<S: StreamModel>() -> RedisResult<Cmd> {
-        let mut cmd = redis::cmd("XREVRANGE");
+    pub fn range_with<S: StreamModel>(ns: &Namespace, range: &StreamRange) -> Cmd {
+        let mut cmd = redis::cmd(range.command());
+        cmd.arg(key::<S>(ns)).arg(range);
+        cmd
+    }
+
+    pub fn range_all<S: StreamModel>(ns: &Namespace) -> RedisResult<Cmd> {
+        let mut cmd = redis::cmd("XRANGE");
 
-        cmd.arg(S::stream_key()).arg("+").arg("-");
+        cmd.arg(key::<S>(ns)).arg("-").arg("+");
 
         Ok(cmd)
     }
 }
 
-mod transformers {
+pub(crate) mod transformers {
     use super::{
-        message::Message,
-        reply::{StreamRangeReply, StreamReadReply},
+        entry_id::StreamEntryId,
+        info::PendingEntry,
+        message::TypedMessage,
+        reply::{StreamAutoclaimReply, StreamRangeReply, StreamReadReply},
         StreamModel,
     };
-    use redis::RedisResult;
+    use redis::{FromRedisValue, RedisResult};
     use tap::Pipe;
 
-    pub fn stream_read_reply_to_messages(
-        s: &impl StreamModel,
+    pub fn stream_read_reply_to_messages<S: StreamModel>(
+        s: &S,
         reply: StreamReadReply,
-    ) -> RedisResult<Vec<Message>> {
+    ) -> RedisResult<Vec<TypedMessage<S::Data>>> {
         reply
             .keys
             .into_iter()
             .flat_map(|stream| {
                 let into_iter = stream.ids.into_iter();
-                into_iter.map(|item| Message::new(item, s.group_name().to_string()))
+                into_iter.map(|item| TypedMessage::new(item, s.group_name().to_string()))
             })
             .collect::<Vec<_>>()
             .pipe(Ok)
     }
 
-    pub fn stream_read_no_group_reply_to_messages(
+    pub fn stream_read_no_group_reply_to_messages<D: FromRedisValue>(
         reply: StreamReadReply,
-    ) -> RedisResult<Vec<Message>> {
+    ) -> RedisResult<Vec<TypedMessage<D>>> {
         reply
             .keys
             .into_iter()
             .flat_map(|stream| {
                 let into_iter = stream.ids.into_iter();
-                into_iter.map(|item| Message::new(item, "none".into()))
+                into_iter.map(|item| TypedMessage::new(item, "none".into()))
             })
             .collect::<Vec<_>>()
             .pipe(Ok)
     }
 
-    pub fn stream_range_to_messages(reply: StreamRangeReply) -> RedisResult<Vec<Message>> {
+    pub fn stream_range_to_messages<D: FromRedisValue>(
+        reply: StreamRangeReply,
+    ) -> RedisResult<Vec<TypedMessage<D>>> {
+        reply
+            .ids
+            .into_iter()
+            .map(move |item| TypedMessage::new(item, "none".into()))
+            .collect::<Vec<_>>()
+            .pipe(Ok)
+    }
+
+    pub fn claim_to_messages<D: FromRedisValue>(
+        group: &str,
+        reply: StreamRangeReply,
+    ) -> RedisResult<Vec<TypedMessage<D>>> {
         reply
             .ids
             .into_iter()
-            .map(move |item| Message::new(item, "none".into()))
+            .map(|item| TypedMessage::new(item, group.to_owned()))
             .collect::<Vec<_>>()
             .pipe(Ok)
     }
 
+    pub fn delivery_count(reply: Vec<PendingEntry>) -> Option<usize> {
+        reply.first().map(|entry| entry.deliveries)
+    }
+
+    /// Set delivery count and idle time of `messages` from their pending `entries`
+    pub fn with_pending<D>(
+        mut messages: Vec<TypedMessage<D>>,
+        entries: Vec<Vec<PendingEntry>>,
+    ) -> Vec<TypedMessage<D>> {
+        for (message, entries) in messages.iter_mut().zip(entries) {
+            if let Some(entry) = entries.first() {
+                message.set_pending(entry);
+            }
+        }
+        messages
+    }
+
     pub fn ensure_group_stream_success(res: RedisResult<String>) -> RedisResult<()> {
         // It is expected behavior that this will fail when already initalized
         // Expected error: `BUSYGROUP: Consumer Group name already exists`
@@ -207,17 +473,17 @@ mod transformers {
         Ok(())
     }
 
-    pub fn autoclaim_range_to_id_and_messages(
+    pub fn autoclaim_range_to_id_and_messages<D: FromRedisValue>(
         group: &str,
-        new_id: String,
-        reply: StreamRangeReply,
-    ) -> RedisResult<(String, Vec<Message>)> {
+        reply: StreamAutoclaimReply,
+    ) -> RedisResult<(StreamEntryId, Vec<TypedMessage<D>>)> {
         let resp = reply
+            .claimed
             .ids
             .into_iter()
-            .map(move |item| Message::new(item, group.to_owned()))
+            .map(move |item| TypedMessage::new(item, group.to_owned()))
             .collect::<Vec<_>>();
 
-        Ok((new_id, resp))
+        Ok((reply.next_id, resp))
     }
 }
@@ -0,0 +1,84 @@
+use redis::{streams::StreamMaxlen, RedisWrite, ToRedisArgs};
+
+/// Options of XADD, adding messages with generated ids to existing or new streams by default.
+///
+/// Streams are trimmed to `StreamModel::maxlen`, set with `#[redis(maxlen = N)]`, unless
+/// trimmed otherwise with [`PublishOptions::maxlen`] or [`PublishOptions::minid`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PublishOptions {
+    trim: Option<Trim>,
+    nomkstream: bool,
+    id: Option<String>,
+}
+
+/// How XADD trims the stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Trim {
+    MaxLen(StreamMaxlen),
+    MinId { id: String, approx: bool },
+}
+
+impl PublishOptions {
+    /// Create options adding messages with generated ids
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trim stream to `maxlen` messages, e.g. `StreamMaxlen::Approx(10_000)`
+    pub fn maxlen(mut self, maxlen: StreamMaxlen) -> Self {
+        self.trim = Some(Trim::MaxLen(maxlen));
+        self
+    }
+
+    /// Trim messages with ids lower than `id`
+    pub fn minid(mut self, id: impl Into<String>) -> Self {
+        let id = id.into();
+        self.trim = Some(Trim::MinId { id, approx: false });
+        self
+    }
+
+    /// Trim messages with ids lower than `id`, as long as whole nodes can be removed
+    pub fn approx_minid(mut self, id: impl Into<String>) -> Self {
+        let id = id.into();
+        self.trim = Some(Trim::MinId { id, approx: true });
+        self
+    }
+
+    /// Don't create the stream if it doesn't exist, publishing nothing
+    pub fn nomkstream(mut self) -> Self {
+        self.nomkstream = true;
+        self
+    }
+
+    /// Add message with explicit `id` rather than a generated one
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Trim to `maxlen` unless trimmed otherwise
+    pub(crate) fn or_maxlen(mut self, maxlen: Option<StreamMaxlen>) -> Self {
+        if self.trim.is_none() {
+            self.trim = maxlen.map(Trim::MaxLen);
+        }
+        self
+    }
+}
+
+impl ToRedisArgs for PublishOptions {
+    fn write_redis_args<W: ?Sized + RedisWrite>(&self, out: &mut W) {
+        if self.nomkstream {
+            out.write_arg(b"NOMKSTREAM");
+        }
+        match &self.trim {
+            Some(Trim::MaxLen(maxlen)) => maxlen.write_redis_args(out),
+            Some(Trim::MinId { id, approx }) => {
+                out.write_arg(b"MINID");
+                out.write_arg(if *approx { b"~" } else { b"=" });
+                out.write_arg(id.as_bytes());
+            }
+            None => {}
+        }
+        out.write_arg(self.id.as_deref().unwrap_or("*").as_bytes());
+    }
+}
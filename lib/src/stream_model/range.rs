@@ -0,0 +1,126 @@
+use super::entry_id::StreamEntryId;
+use redis::{RedisWrite, ToRedisArgs};
+use std::ops::Bound;
+
+/// Range of messages of XRANGE, oldest first, or XREVRANGE, newest first.
+///
+/// Ranges span the whole stream unless bounded by ids, inclusively with `min`/`max`, or
+/// exclusively with `after`/`before`.
+///
+/// # Example
+///
+/// ```ignore
+/// // Up to 10 messages following `last_id`, excluded
+/// let range = StreamRange::range().after(last_id).count(10);
+/// let messages = RoomServiceEventManager::range_with(&range, &mut conn)?;
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamRange {
+    min: Bound<StreamEntryId>,
+    max: Bound<StreamEntryId>,
+    count: Option<usize>,
+    rev: bool,
+}
+
+impl Default for StreamRange {
+    fn default() -> Self {
+        Self::range()
+    }
+}
+
+impl StreamRange {
+    /// Create range of every message, oldest first
+    pub fn range() -> Self {
+        Self {
+            min: Bound::Unbounded,
+            max: Bound::Unbounded,
+            count: None,
+            rev: false,
+        }
+    }
+
+    /// Create range of every message, newest first
+    pub fn rev_range() -> Self {
+        Self {
+            rev: true,
+            ..Self::range()
+        }
+    }
+
+    /// Start range at message `id`, included
+    pub fn min(mut self, id: StreamEntryId) -> Self {
+        self.min = Bound::Included(id);
+        self
+    }
+
+    /// Start range after message `id`, excluded
+    pub fn after(mut self, id: StreamEntryId) -> Self {
+        self.min = Bound::Excluded(id);
+        self
+    }
+
+    /// End range at message `id`, included
+    pub fn max(mut self, id: StreamEntryId) -> Self {
+        self.max = Bound::Included(id);
+        self
+    }
+
+    /// End range before message `id`, excluded
+    pub fn before(mut self, id: StreamEntryId) -> Self {
+        self.max = Bound::Excluded(id);
+        self
+    }
+
+    /// Return at most `count` messages
+    pub fn count(mut self, count: usize) -> Self {
+        self.count = Some(count);
+        self
+    }
+
+    /// Whether messages are returned newest first
+    pub fn is_rev(&self) -> bool {
+        self.rev
+    }
+
+    /// Command reading the range
+    pub(crate) fn command(&self) -> &'static str {
+        if self.rev {
+            "XREVRANGE"
+        } else {
+            "XRANGE"
+        }
+    }
+
+    /// Rest of range following message `id`, in the order messages are returned
+    pub(crate) fn following(mut self, id: StreamEntryId) -> Self {
+        let bound = Bound::Excluded(id);
+        if self.rev {
+            self.max = bound;
+        } else {
+            self.min = bound;
+        }
+        self
+    }
+}
+
+impl ToRedisArgs for StreamRange {
+    fn write_redis_args<W: ?Sized + RedisWrite>(&self, out: &mut W) {
+        let (min, max) = (bound(&self.min, "-"), bound(&self.max, "+"));
+        let (start, end) = if self.rev { (max, min) } else { (min, max) };
+        out.write_arg(start.as_bytes());
+        out.write_arg(end.as_bytes());
+        if let Some(count) = self.count {
+            out.write_arg(b"COUNT");
+            out.write_arg_fmt(count);
+        }
+    }
+}
+
+/// Argument of range `bound`, `unbounded` when unbounded
+fn bound(bound: &Bound<StreamEntryId>, unbounded: &str) -> String {
+    match bound {
+        Bound::Included(id) => id.to_string(),
+        Bound::Excluded(id) => format!("({id}"),
+        Bound::Unbounded => unbounded.to_owned(),
+    }
+}
@@ -1,12 +1,11 @@
-use std::collections::HashMap;
+use super::entry_id::StreamEntryId;
+use redis::{ErrorKind, FromRedisValue, RedisError, RedisResult, Value};
 
-use redis::{FromRedisValue, RedisResult, Value};
-
-/// Represents a stream `id` and its field/values as a `HashMap`
+/// Represents a stream `id` and its field/values
 #[derive(Debug, Clone)]
 pub struct StreamId {
     /// The stream `id` (entry ID) of this particular message.
-    pub id: String,
+    pub id: StreamEntryId,
     /// All fields in this message, associated with their respective values.
     pub value: Value,
 }
@@ -33,34 +32,63 @@ pub struct StreamRangeReply {
 
 impl FromRedisValue for StreamRangeReply {
     fn from_redis_value(v: &Value) -> RedisResult<Self> {
-        let rows: Vec<HashMap<String, Value>> = redis::from_redis_value(v)?;
-        let ids: Vec<StreamId> = rows
-            .into_iter()
-            .flat_map(|row| row.into_iter().map(|(id, value)| StreamId { id, value }))
-            .collect();
-
-        Ok(StreamRangeReply { ids })
+        Ok(StreamRangeReply {
+            ids: stream_ids(v)?,
+        })
     }
 }
 
-type SRRows = Vec<HashMap<String, Vec<HashMap<String, Value>>>>;
 impl FromRedisValue for StreamReadReply {
     fn from_redis_value(v: &Value) -> RedisResult<Self> {
-        let rows: SRRows = redis::from_redis_value(v)?;
+        // Rows of `[key, entries]`, nil when XREAD timed out
+        let rows: Vec<Value> = redis::from_redis_value(v)?;
         let keys = rows
-            .into_iter()
-            .flat_map(|row| {
-                row.into_iter().map(|(key, entry)| {
-                    let ids = entry
-                        .into_iter()
-                        .flat_map(|id_row| {
-                            id_row.into_iter().map(|(id, value)| StreamId { id, value })
-                        })
-                        .collect();
-                    StreamKey { key, ids }
-                })
+            .iter()
+            .map(|row| {
+                let (key, entries): (String, Value) = redis::from_redis_value(row)?;
+                let ids = stream_ids(&entries)?;
+                Ok(StreamKey { key, ids })
             })
-            .collect();
+            .collect::<RedisResult<_>>()?;
+
         Ok(StreamReadReply { keys })
     }
 }
+
+/// Parse `[id, fields]` entries in order. Fields of entries deleted since their delivery
+/// are nil.
+fn stream_ids(v: &Value) -> RedisResult<Vec<StreamId>> {
+    let rows: Vec<Value> = redis::from_redis_value(v)?;
+    rows.iter()
+        .map(|row| {
+            let (id, value) = redis::from_redis_value(row)?;
+            Ok(StreamId { id, value })
+        })
+        .collect()
+}
+
+/// Reply of XAUTOCLAIM: the cursor to continue from, claimed entries and, since
+/// redis 7, ids of claimed entries that no longer exist.
+#[derive(Default, Debug, Clone)]
+pub struct StreamAutoclaimReply {
+    /// Cursor of next XAUTOCLAIM call, "0-0" once the whole pending list was scanned
+    pub next_id: StreamEntryId,
+    /// Claimed entries
+    pub claimed: StreamRangeReply,
+}
+
+impl FromRedisValue for StreamAutoclaimReply {
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        match v {
+            Value::Bulk(items) if matches!(items.len(), 2 | 3) => Ok(StreamAutoclaimReply {
+                next_id: redis::from_redis_value(&items[0])?,
+                claimed: redis::from_redis_value(&items[1])?,
+            }),
+            _ => Err(RedisError::from((
+                ErrorKind::TypeError,
+                "Response was of incompatible type",
+                format!("{v:?} (response was not a XAUTOCLAIM reply)"),
+            ))),
+        }
+    }
+}
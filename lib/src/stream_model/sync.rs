@@ -1,15 +1,26 @@
 use redis::streams::StreamMaxlen;
-use redis::ConnectionLike;
 use redis::{FromRedisValue, RedisResult, ToRedisArgs};
 
 use super::cmds;
-use super::message::Message;
-use super::reply::StreamReadReply;
+use super::dead_letter::DeadLetter;
+use super::entry_id::StreamEntryId;
+use super::group::StartId;
+use super::info::{
+    ClaimOptions, ConsumerInfo, GroupInfo, PendingEntry, PendingQuery, PendingSummary, StreamInfo,
+};
+use super::message::TypedMessage;
+use super::publish::PublishOptions;
+use super::range::StreamRange;
+use super::reply::{StreamAutoclaimReply, StreamReadReply};
 use super::transformers;
+use crate::WithNamespace;
+use std::collections::VecDeque;
+use std::time::Duration;
 
-impl Message {
-    pub fn ack<Data: StreamModel, C: ConnectionLike>(&self, conn: &mut C) -> RedisResult<()> {
-        Data::ack(&self.group, &[&self.id], conn)
+impl<D> TypedMessage<D> {
+    /// Acknowledge message for its group
+    pub fn ack<S: StreamModel<Data = D>, C: WithNamespace>(&self, conn: &mut C) -> RedisResult<()> {
+        S::ack(&self.group, &[&self.id], conn)
     }
 }
 
@@ -27,80 +38,286 @@ pub trait StreamModel: Sized {
     /// Consumer Name
     fn consumer_name(&self) -> &str;
 
+    /// Get length streams are trimmed to on publish, set with `#[redis(maxlen = N)]`
+    fn maxlen() -> Option<StreamMaxlen> {
+        None
+    }
+
     /// Publish self to stream, returning event id
-    fn publish<C: ConnectionLike>(data: &Self::Data, conn: &mut C) -> RedisResult<String> {
-        cmds::publish::<Self, _>(data)?.query(conn)
+    fn publish<C: WithNamespace>(data: &Self::Data, conn: &mut C) -> RedisResult<String> {
+        cmds::publish::<Self, _>(conn.namespace(), data, PublishOptions::new())?
+            .query(conn.connection())
+    }
+
+    /// Publish self to stream with `options`, returning event id, or `None` when the stream
+    /// doesn't exist and [`PublishOptions::nomkstream`] is set
+    fn publish_with<C: WithNamespace>(
+        data: &Self::Data,
+        options: PublishOptions,
+        conn: &mut C,
+    ) -> RedisResult<Option<String>> {
+        cmds::publish::<Self, _>(conn.namespace(), data, options)?.query(conn.connection())
     }
 
     /// Ensure group stream exists for [`Self::stream_key`], creates a new if it doesn't exists.
     /// Errors if it fails to ensure stream
-    fn ensure_group_stream<C: ConnectionLike>(&self, conn: &mut C) -> RedisResult<()> {
-        let res = cmds::ensure_group_stream::<Self>(self)?.query(conn);
+    fn ensure_group_stream<C: WithNamespace>(&self, conn: &mut C) -> RedisResult<()> {
+        self.ensure_group_stream_from(StartId::Latest, None, conn)
+    }
+
+    /// Ensure group stream exists like [`Self::ensure_group_stream`], a new group delivering
+    /// messages added after `start`. `entries_read` sets the number of messages the group
+    /// already read, used by redis 7 to compute its lag.
+    fn ensure_group_stream_from<C: WithNamespace>(
+        &self,
+        start: StartId,
+        entries_read: Option<u64>,
+        conn: &mut C,
+    ) -> RedisResult<()> {
+        let group = self.group_name();
+        let res = cmds::ensure_group_stream::<Self>(conn.namespace(), group, &start, entries_read)
+            .query(conn.connection());
         transformers::ensure_group_stream_success(res)
     }
 
     /// Read from [`Self::stream_key`] with group name and consumer name.
-    fn read<C: ConnectionLike>(
+    fn read<C: WithNamespace>(
         &self,
         read_count: Option<usize>,
         block_interval: Option<usize>,
         conn: &mut C,
-    ) -> RedisResult<Vec<Message>> {
-        cmds::read::<Self>(self, read_count, block_interval)?
-            .query::<StreamReadReply>(conn)
+    ) -> RedisResult<Vec<TypedMessage<Self::Data>>> {
+        let id = cmds::NEW_MESSAGES;
+        cmds::read::<Self>(conn.namespace(), self, read_count, block_interval, id)?
+            .query::<StreamReadReply>(conn.connection())
             .map(|reply| transformers::stream_read_reply_to_messages(self, reply))?
     }
 
+    /// Read messages pending for consumer name, i.e. delivered but not acknowledged, with ids
+    /// greater than `start_id`, e.g. [`StreamEntryId::MIN`] to read them all after a restart.
+    ///
+    /// Messages carry their delivery count and idle time. Messages deleted since their
+    /// delivery fail to decode.
+    fn read_pending<C: WithNamespace>(
+        &self,
+        start_id: StreamEntryId,
+        count: Option<usize>,
+        conn: &mut C,
+    ) -> RedisResult<Vec<TypedMessage<Self::Data>>> {
+        let messages = cmds::read::<Self>(conn.namespace(), self, count, None, start_id)?
+            .query::<StreamReadReply>(conn.connection())
+            .map(|reply| transformers::stream_read_reply_to_messages(self, reply))??;
+        with_pending::<Self, C>(self.group_name(), messages, conn)
+    }
+
     /// Abstraction with default options and without a group.
-    fn read_no_group<C: ConnectionLike>(
+    fn read_no_group<C: WithNamespace>(
         id: impl AsRef<str>,
         conn: &mut C,
-    ) -> RedisResult<Vec<Message>> {
-        cmds::read_no_group::<Self>(id)?
-            .query::<StreamReadReply>(conn)
+    ) -> RedisResult<Vec<TypedMessage<Self::Data>>> {
+        cmds::read_no_group::<Self>(conn.namespace(), id)?
+            .query::<StreamReadReply>(conn.connection())
             .map(transformers::stream_read_no_group_reply_to_messages)?
     }
 
     /// Autoclaim an event and return a stream of messages found during the autoclaim.
-    fn autoclaim<C: ConnectionLike>(
+    ///
+    /// Returns the cursor of the next autoclaim, [`StreamEntryId::MIN`] once the whole
+    /// pending entries list was scanned. Messages carry their delivery count and idle time.
+    fn autoclaim<C: WithNamespace>(
         group: impl AsRef<str>,
         consumer: impl AsRef<str>,
         min_idle_time: usize,
-        last_autocalim_id: impl AsRef<str>,
+        last_autocalim_id: StreamEntryId,
         read_count: Option<usize>,
         conn: &mut C,
-    ) -> RedisResult<(String, Vec<Message>)> {
+    ) -> RedisResult<(StreamEntryId, Vec<TypedMessage<Self::Data>>)> {
         let group = group.as_ref();
-        cmds::autoclaim::<Self>(
+        let (next_id, messages) = cmds::autoclaim::<Self>(
+            conn.namespace(),
             group,
             consumer,
             min_idle_time,
             last_autocalim_id,
             read_count,
         )?
-        .query(conn)
-        .map(|(new_id, reply)| {
-            transformers::autoclaim_range_to_id_and_messages(group, new_id, reply)
-        })?
+        .query::<StreamAutoclaimReply>(conn.connection())
+        .map(|reply| transformers::autoclaim_range_to_id_and_messages(group, reply))??;
+        Ok((next_id, with_pending::<Self, C>(group, messages, conn)?))
     }
 
     /// Acknowledge a given list of ids for group
-    fn ack<'a, C: ConnectionLike>(
+    fn ack<'a, C: WithNamespace>(
         group: impl ToRedisArgs,
         ids: &'a [impl ToRedisArgs],
         conn: &mut C,
     ) -> RedisResult<()> {
-        cmds::ack::<Self>(group, ids)?.query(conn)
+        cmds::ack::<Self>(conn.namespace(), group, ids)?.query(conn.connection())
     }
 
     /// Return the length of the stream
-    fn len<C: ConnectionLike>(conn: &mut C) -> RedisResult<usize> {
-        cmds::len::<Self>()?.query(conn)
+    fn len<C: WithNamespace>(conn: &mut C) -> RedisResult<usize> {
+        cmds::len::<Self>(conn.namespace())?.query(conn.connection())
+    }
+
+    /// Number of times message `id` was delivered to `group`, `None` if it isn't pending
+    fn delivery_count<C: WithNamespace>(
+        group: impl AsRef<str>,
+        id: StreamEntryId,
+        conn: &mut C,
+    ) -> RedisResult<Option<usize>> {
+        cmds::delivery_count::<Self>(conn.namespace(), group.as_ref(), id)
+            .query(conn.connection())
+            .map(transformers::delivery_count)
+    }
+
+    /// Move `message` to the dead-letter stream "{stream_key}:dlq" along `error` and the
+    /// number of `deliveries`, acknowledging it in the same transaction.
+    fn dead_letter<C: WithNamespace>(
+        message: &TypedMessage<Self::Data>,
+        error: impl AsRef<str>,
+        deliveries: usize,
+        conn: &mut C,
+    ) -> RedisResult<()> {
+        cmds::dead_letter::<Self>(conn.namespace(), message, error.as_ref(), deliveries)?
+            .query(conn.connection())
+    }
+
+    /// Returns a range of messages of the dead-letter stream, optionally limited to `count`.
+    fn dead_letters<C: WithNamespace, S: ToRedisArgs, E: ToRedisArgs>(
+        start: S,
+        end: E,
+        count: Option<usize>,
+        conn: &mut C,
+    ) -> RedisResult<Vec<TypedMessage<DeadLetter<Self::Data>>>> {
+        cmds::dead_letters::<Self>(conn.namespace(), start, end, count)
+            .query(conn.connection())
+            .map(transformers::stream_range_to_messages)?
+    }
+
+    /// Summary of pending messages of `group`
+    fn pending_summary<C: WithNamespace>(
+        group: impl AsRef<str>,
+        conn: &mut C,
+    ) -> RedisResult<PendingSummary> {
+        cmds::pending_summary::<Self>(conn.namespace(), group.as_ref()).query(conn.connection())
+    }
+
+    /// Pending messages of `group` matching `query`, with their idle time and delivery count
+    fn pending<C: WithNamespace>(
+        group: impl AsRef<str>,
+        query: &PendingQuery,
+        conn: &mut C,
+    ) -> RedisResult<Vec<PendingEntry>> {
+        cmds::pending::<Self>(conn.namespace(), group.as_ref(), query).query(conn.connection())
+    }
+
+    /// Claim pending messages `ids` of `group` idle for at least `min_idle`, transferring them
+    /// to `consumer`
+    ///
+    /// Claimed messages carry their delivery count and idle time.
+    fn claim<C: WithNamespace, I: ToRedisArgs>(
+        group: impl AsRef<str>,
+        consumer: impl AsRef<str>,
+        min_idle: Duration,
+        ids: &[I],
+        options: &ClaimOptions,
+        conn: &mut C,
+    ) -> RedisResult<Vec<TypedMessage<Self::Data>>> {
+        let group = group.as_ref();
+        let messages = cmds::claim::<Self>(
+            conn.namespace(),
+            group,
+            consumer.as_ref(),
+            min_idle,
+            ids,
+            options,
+            false,
+        )
+        .query(conn.connection())
+        .map(|reply| transformers::claim_to_messages(group, reply))??;
+        with_pending::<Self, C>(group, messages, conn)
+    }
+
+    /// Claim like [`Self::claim`] with JUSTID, returning ids of claimed messages without
+    /// incrementing their delivery count
+    fn claim_ids<C: WithNamespace, I: ToRedisArgs>(
+        group: impl AsRef<str>,
+        consumer: impl AsRef<str>,
+        min_idle: Duration,
+        ids: &[I],
+        options: &ClaimOptions,
+        conn: &mut C,
+    ) -> RedisResult<Vec<String>> {
+        cmds::claim::<Self>(
+            conn.namespace(),
+            group.as_ref(),
+            consumer.as_ref(),
+            min_idle,
+            ids,
+            options,
+            true,
+        )
+        .query(conn.connection())
+    }
+
+    /// Details of [`Self::stream_key`]
+    fn info<C: WithNamespace>(conn: &mut C) -> RedisResult<StreamInfo> {
+        cmds::info_stream::<Self>(conn.namespace()).query(conn.connection())
+    }
+
+    /// Details of consumer groups of [`Self::stream_key`]
+    fn groups<C: WithNamespace>(conn: &mut C) -> RedisResult<Vec<GroupInfo>> {
+        cmds::info_groups::<Self>(conn.namespace()).query(conn.connection())
+    }
+
+    /// Details of consumers of `group`
+    fn consumers<C: WithNamespace>(
+        group: impl AsRef<str>,
+        conn: &mut C,
+    ) -> RedisResult<Vec<ConsumerInfo>> {
+        cmds::info_consumers::<Self>(conn.namespace(), group.as_ref()).query(conn.connection())
+    }
+
+    /// Delete `consumer` of `group`, returning the number of its pending messages, which are lost
+    fn delete_consumer<C: WithNamespace>(
+        group: impl AsRef<str>,
+        consumer: impl AsRef<str>,
+        conn: &mut C,
+    ) -> RedisResult<usize> {
+        cmds::delete_consumer::<Self>(conn.namespace(), group.as_ref(), consumer.as_ref())
+            .query(conn.connection())
+    }
+
+    /// Delete consumers of `group` with generated names, i.e. of managers created with `new`,
+    /// idle for at least `min_idle` and without pending messages, returning their names
+    fn cleanup_consumers<C: WithNamespace>(
+        group: impl AsRef<str>,
+        min_idle: Duration,
+        conn: &mut C,
+    ) -> RedisResult<Vec<String>> {
+        cmds::cleanup_consumers::<Self>(conn.namespace(), group.as_ref(), min_idle)
+            .query(conn.connection())
+    }
+
+    /// Set last delivered id of `group` to `id`, e.g. `$` to skip or `0` to replay all messages
+    fn set_group_id<C: WithNamespace>(
+        group: impl AsRef<str>,
+        id: impl AsRef<str>,
+        conn: &mut C,
+    ) -> RedisResult<()> {
+        cmds::set_group_id::<Self>(conn.namespace(), group.as_ref(), id.as_ref())
+            .query(conn.connection())
+    }
+
+    /// Destroy `group` along its consumers and pending messages, returning whether it existed
+    fn destroy_group<C: WithNamespace>(group: impl AsRef<str>, conn: &mut C) -> RedisResult<bool> {
+        cmds::destroy_group::<Self>(conn.namespace(), group.as_ref()).query(conn.connection())
     }
 
     /// Trim a stream to a MAXLEN count.
-    fn trim<C: ConnectionLike>(maxlen: StreamMaxlen, conn: &mut C) -> RedisResult<()> {
-        cmds::trim::<Self>(maxlen)?.query(conn)
+    fn trim<C: WithNamespace>(maxlen: StreamMaxlen, conn: &mut C) -> RedisResult<()> {
+        cmds::trim::<Self>(conn.namespace(), maxlen)?.query(conn.connection())
     }
 
     /// Returns a range of messages.
@@ -110,33 +327,112 @@ pub trait StreamModel: Sized {
     ///
     /// You can pass message `id` to both `start` and `end`.
     ///
-    fn range_count<C: ConnectionLike, S: ToRedisArgs, E: ToRedisArgs, N: ToRedisArgs>(
+    fn range_count<C: WithNamespace, S: ToRedisArgs, E: ToRedisArgs, N: ToRedisArgs>(
         start: S,
         end: E,
         count: N,
         conn: &mut C,
-    ) -> RedisResult<Vec<Message>> {
-        cmds::range_count::<Self, _, _, _>(start, end, count)?
-            .query(conn)
+    ) -> RedisResult<Vec<TypedMessage<Self::Data>>> {
+        cmds::range_count::<Self, _, _, _>(conn.namespace(), start, end, count)?
+            .query(conn.connection())
             .map(transformers::stream_range_to_messages)?
     }
 
     /// A method for paginating the stream
-    fn range<C: ConnectionLike, S: ToRedisArgs, E: ToRedisArgs>(
+    fn range<C: WithNamespace, S: ToRedisArgs, E: ToRedisArgs>(
         start: S,
         end: E,
         conn: &mut C,
-    ) -> RedisResult<Vec<Message>> {
-        cmds::range::<Self, _, _>(start, end)?
-            .query(conn)
+    ) -> RedisResult<Vec<TypedMessage<Self::Data>>> {
+        cmds::range::<Self, _, _>(conn.namespace(), start, end)?
+            .query(conn.connection())
+            .map(transformers::stream_range_to_messages)?
+    }
+
+    /// Returns messages of `range`, in its order
+    fn range_with<C: WithNamespace>(
+        range: &StreamRange,
+        conn: &mut C,
+    ) -> RedisResult<Vec<TypedMessage<Self::Data>>> {
+        cmds::range_with::<Self>(conn.namespace(), range)
+            .query(conn.connection())
             .map(transformers::stream_range_to_messages)?
     }
 
-    /// A helper method for automatically returning all messages in a stream by `key`.
-    /// **Use with caution!**
-    fn range_all<C: ConnectionLike>(conn: &mut C) -> RedisResult<Vec<Message>> {
-        cmds::range_all::<Self>()?
-            .query(conn)
+    /// Iterate messages of `range` in its order, fetching `page_size` messages per round trip.
+    /// The count of `range` is ignored.
+    fn paginate<'a, C: WithNamespace>(
+        range: StreamRange,
+        page_size: usize,
+        conn: &'a mut C,
+    ) -> Box<dyn Iterator<Item = RedisResult<TypedMessage<Self::Data>>> + 'a>
+    where
+        Self: 'a,
+    {
+        Box::new(RangePages::<Self, C> {
+            conn,
+            range: Some(range),
+            page_size: page_size.max(1),
+            buffer: VecDeque::new(),
+        })
+    }
+
+    /// A helper method for automatically returning all messages in a stream by `key`, oldest
+    /// first. **Use with caution!**, see [`Self::paginate`] for large streams
+    fn range_all<C: WithNamespace>(conn: &mut C) -> RedisResult<Vec<TypedMessage<Self::Data>>> {
+        cmds::range_all::<Self>(conn.namespace())?
+            .query(conn.connection())
             .map(transformers::stream_range_to_messages)?
     }
 }
+
+/// Set delivery count and idle time of `messages` pending for `group`
+fn with_pending<S: StreamModel, C: WithNamespace>(
+    group: &str,
+    messages: Vec<TypedMessage<S::Data>>,
+    conn: &mut C,
+) -> RedisResult<Vec<TypedMessage<S::Data>>> {
+    if messages.is_empty() {
+        return Ok(messages);
+    }
+
+    let ids = messages
+        .iter()
+        .map(|message| message.id)
+        .collect::<Vec<_>>();
+    cmds::pending_entries::<S>(conn.namespace(), group, &ids)
+        .query(conn.connection())
+        .map(|entries| transformers::with_pending(messages, entries))
+}
+
+struct RangePages<'a, S: StreamModel, C> {
+    conn: &'a mut C,
+    range: Option<StreamRange>,
+    page_size: usize,
+    buffer: VecDeque<TypedMessage<S::Data>>,
+}
+
+impl<'a, S: StreamModel, C: WithNamespace> Iterator for RangePages<'a, S, C> {
+    type Item = RedisResult<TypedMessage<S::Data>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(message) = self.buffer.pop_front() {
+                return Some(Ok(message));
+            }
+
+            let range = self.range.take()?;
+            let page = range.clone().count(self.page_size);
+            let messages = match S::range_with(&page, self.conn) {
+                Ok(messages) => messages,
+                Err(err) => return Some(Err(err)),
+            };
+
+            // A short page is the last one
+            if messages.len() == self.page_size {
+                self.range = messages.last().map(|last| range.following(last.id));
+            }
+            self.buffer.extend(messages);
+        }
+    }
+}
@@ -0,0 +1,232 @@
+//! Adapters encoding field types that redis doesn't support natively,
+//! selected with `#[redis(with = "redis_om::with::...")]`.
+//!
+//! Adapters apply to both `T` and `Option<T>` fields.
+//!
+//! # Example
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! #[derive(redis_om::RedisTransportValue)]
+//! struct Session {
+//!     id: String,
+//!     #[redis(with = "redis_om::with::duration_secs")]
+//!     ttl: Duration,
+//!     #[redis(with = "redis_om::with::bool_str")]
+//!     active: Option<bool>,
+//! }
+//! ```
+//!
+//! A custom adapter is any module providing the following functions:
+//!
+//! ```ignore
+//! pub fn to_redis_args(value: &T) -> Vec<Vec<u8>>;
+//! pub fn from_redis_value(value: &redis::Value) -> redis::RedisResult<T>;
+//! ```
+use redis::{ErrorKind, FromRedisValue, RedisError, RedisResult, Value};
+use std::fmt::Display;
+
+/// Field types an adapter of `T` applies to, `T` itself or `Option<T>`.
+pub trait Adapted<T>: Sized {
+    /// Encode field with `encode`, an empty list for `None`.
+    fn encode(&self, encode: impl Fn(&T) -> String) -> Vec<Vec<u8>>;
+
+    /// Decode field with `decode`, `None` for missing values.
+    fn decode(value: &Value, decode: impl Fn(&str) -> RedisResult<T>) -> RedisResult<Self>;
+}
+
+impl<T> Adapted<T> for T {
+    fn encode(&self, encode: impl Fn(&T) -> String) -> Vec<Vec<u8>> {
+        vec![encode(self).into_bytes()]
+    }
+
+    fn decode(value: &Value, decode: impl Fn(&str) -> RedisResult<T>) -> RedisResult<Self> {
+        decode(&String::from_redis_value(value)?)
+    }
+}
+
+impl<T> Adapted<T> for Option<T> {
+    fn encode(&self, encode: impl Fn(&T) -> String) -> Vec<Vec<u8>> {
+        self.iter().map(|v| encode(v).into_bytes()).collect()
+    }
+
+    fn decode(value: &Value, decode: impl Fn(&str) -> RedisResult<T>) -> RedisResult<Self> {
+        match value {
+            Value::Nil => Ok(None),
+            value => T::decode(value, decode).map(Some),
+        }
+    }
+}
+
+/// Map parse error of `value` into [`RedisError`].
+fn parse_error(expected: &'static str, value: &str, err: impl Display) -> RedisError {
+    RedisError::from((ErrorKind::TypeError, expected, format!("{value:?}: {err}")))
+}
+
+/// Define an adapter module from an encode and a decode function.
+macro_rules! adapter {
+    ($(#[$meta:meta])* $name:ident, $ty:ty, $encode:expr, $decode:expr) => {
+        $(#[$meta])*
+        pub mod $name {
+            #[allow(unused_imports)]
+            use super::*;
+
+            /// Encode field into redis args.
+            pub fn to_redis_args<F: Adapted<$ty>>(value: &F) -> Vec<Vec<u8>> {
+                value.encode($encode)
+            }
+
+            /// Decode field from redis value.
+            pub fn from_redis_value<F: Adapted<$ty>>(value: &Value) -> RedisResult<F> {
+                F::decode(value, $decode)
+            }
+        }
+    };
+}
+
+adapter!(
+    /// `bool` as "0"/"1".
+    bool_num,
+    bool,
+    |v| if *v { "1" } else { "0" }.into(),
+    |s| match s {
+        "1" => Ok(true),
+        "0" => Ok(false),
+        s => Err(parse_error("expected \"0\" or \"1\"", s, "invalid bool")),
+    }
+);
+
+adapter!(
+    /// `bool` as "true"/"false".
+    bool_str,
+    bool,
+    |v| v.to_string(),
+    |s| s.parse().map_err(|e| parse_error("expected \"true\" or \"false\"", s, e))
+);
+
+adapter!(
+    /// [`std::time::Duration`] as whole seconds.
+    duration_secs,
+    std::time::Duration,
+    |v| v.as_secs().to_string(),
+    |s| s
+        .parse()
+        .map(std::time::Duration::from_secs)
+        .map_err(|e| parse_error("expected duration in seconds", s, e))
+);
+
+adapter!(
+    /// [`std::time::Duration`] as whole milliseconds.
+    duration_millis,
+    std::time::Duration,
+    |v| v.as_millis().to_string(),
+    |s| s
+        .parse()
+        .map(std::time::Duration::from_millis)
+        .map_err(|e| parse_error("expected duration in milliseconds", s, e))
+);
+
+adapter!(
+    /// [`std::net::IpAddr`] as its textual representation.
+    ip_addr,
+    std::net::IpAddr,
+    |v| v.to_string(),
+    |s| s.parse().map_err(|e| parse_error("expected ip address", s, e))
+);
+
+adapter!(
+    /// [`chrono::DateTime<Utc>`](::chrono::DateTime) as RFC 3339 string.
+    #[cfg(feature = "chrono")]
+    chrono_iso,
+    ::chrono::DateTime<::chrono::Utc>,
+    |v| v.to_rfc3339(),
+    |s| ::chrono::DateTime::parse_from_rfc3339(s)
+        .map(|v| v.with_timezone(&::chrono::Utc))
+        .map_err(|e| parse_error("expected RFC 3339 datetime", s, e))
+);
+
+adapter!(
+    /// [`chrono::DateTime<Utc>`](::chrono::DateTime) as unix timestamp in seconds.
+    #[cfg(feature = "chrono")]
+    chrono_epoch,
+    ::chrono::DateTime<::chrono::Utc>,
+    |v| v.timestamp().to_string(),
+    |s| s
+        .parse()
+        .map_err(|e| parse_error("expected unix timestamp", s, e))
+        .and_then(|secs| {
+            ::chrono::TimeZone::timestamp_opt(&::chrono::Utc, secs, 0)
+                .single()
+                .ok_or_else(|| parse_error("expected unix timestamp", s, "out of range"))
+        })
+);
+
+/// ISO 8601 format of [`time_iso`], with six digit years to represent years before 0
+#[cfg(feature = "time")]
+const TIME_ISO: ::time::format_description::well_known::iso8601::EncodedConfig =
+    ::time::format_description::well_known::iso8601::Config::DEFAULT
+        .set_year_is_six_digits(true)
+        .encode();
+
+adapter!(
+    /// [`time::OffsetDateTime`](::time::OffsetDateTime) as ISO 8601 string, e.g.
+    /// "+002023-02-03T10:00:00.000000000Z".
+    ///
+    /// Offsets with seconds, which ISO 8601 can't represent, are encoded in UTC.
+    #[cfg(feature = "time")]
+    time_iso,
+    ::time::OffsetDateTime,
+    |v| {
+        let v = match v.offset().seconds_past_minute() {
+            0 => *v,
+            _ => v.to_offset(::time::UtcOffset::UTC),
+        };
+        v.format(&::time::format_description::well_known::Iso8601::<TIME_ISO>)
+            .expect("six digit years and whole minute offsets are always formatted")
+    },
+    |s| ::time::OffsetDateTime::parse(s, &::time::format_description::well_known::Iso8601::DEFAULT)
+        .map_err(|e| parse_error("expected ISO 8601 datetime", s, e))
+);
+
+adapter!(
+    /// [`time::OffsetDateTime`](::time::OffsetDateTime) as unix timestamp in seconds.
+    #[cfg(feature = "time")]
+    time_epoch,
+    ::time::OffsetDateTime,
+    |v| v.unix_timestamp().to_string(),
+    |s| s
+        .parse()
+        .map_err(|e| parse_error("expected unix timestamp", s, e))
+        .and_then(|secs| {
+            ::time::OffsetDateTime::from_unix_timestamp(secs)
+                .map_err(|e| parse_error("expected unix timestamp", s, e))
+        })
+);
+
+adapter!(
+    /// [`uuid::Uuid`](::uuid::Uuid) as hyphenated string.
+    #[cfg(feature = "uuid")]
+    uuid,
+    ::uuid::Uuid,
+    |v| v.hyphenated().to_string(),
+    |s| s.parse().map_err(|e| parse_error("expected uuid", s, e))
+);
+
+adapter!(
+    /// [`rust_decimal::Decimal`](::rust_decimal::Decimal) as its textual representation.
+    #[cfg(feature = "rust_decimal")]
+    decimal,
+    ::rust_decimal::Decimal,
+    |v| v.to_string(),
+    |s| s.parse().map_err(|e| parse_error("expected decimal", s, e))
+);
+
+adapter!(
+    /// [`url::Url`](::url::Url) as its textual representation.
+    #[cfg(feature = "url")]
+    url,
+    ::url::Url,
+    |v| v.to_string(),
+    |s| s.parse().map_err(|e| parse_error("expected url", s, e))
+);
@@ -0,0 +1,86 @@
+#![cfg(all(feature = "cluster", not(feature = "aio")))]
+
+use redis::cluster::{ClusterClient, ClusterConnection};
+use redis::ErrorKind;
+use redis_om::{key_slot, HashModel, Namespace, RedisModel, RedisResult, WithNamespace};
+
+type Result<T = (), E = Box<dyn std::error::Error>> = std::result::Result<T, E>;
+
+/// Local cluster, e.g. `docker run -p 7000-7005:7000-7005 grokzen/redis-cluster`
+fn conn() -> RedisResult<ClusterConnection> {
+    ClusterClient::new(vec![
+        "redis://127.0.0.1:7000/",
+        "redis://127.0.0.1:7001/",
+        "redis://127.0.0.1:7002/",
+    ])?
+    .get_connection()
+}
+
+#[test]
+fn static_hash_tag() -> Result {
+    #[derive(HashModel, Debug)]
+    #[redis(prefix_key = "orders", hash_tag = "acme")]
+    struct Order {
+        id: String,
+        total: u32,
+    }
+
+    let mut conn = Namespace::new().cluster(true).wrap(conn()?);
+    let mut keys = vec![];
+
+    for total in [10, 20, 30] {
+        let mut order = Order {
+            id: "".into(),
+            total,
+        };
+        order.save(&mut conn)?;
+        keys.push(order._get_redis_key(conn.namespace()));
+    }
+
+    conn.namespace().ensure_same_slot(&keys)?;
+    assert_eq!(Order::get(&keys[1], &mut conn)?.total, 20);
+
+    // multi-key commands succeed, as all keys hash to the same slot
+    let deleted: usize = redis::cmd("DEL").arg(&keys).query(conn.connection())?;
+    assert_eq!(deleted, 3);
+
+    Ok(())
+}
+
+#[test]
+fn primary_key_hash_tag() -> Result {
+    #[derive(HashModel, Debug)]
+    #[redis(prefix_key = "carts")]
+    struct Cart {
+        #[redis(hash_tag)]
+        id: String,
+        owner: String,
+    }
+
+    let mut conn = Namespace::new().cluster(true).wrap(conn()?);
+    let mut cart = Cart {
+        id: "".into(),
+        owner: "jane".into(),
+    };
+    cart.save(&mut conn)?;
+
+    let key = cart._get_redis_key(conn.namespace());
+    let lines = format!("lines:{{{}}}", cart.id);
+    assert_eq!(key_slot(&key), key_slot(&lines));
+
+    let exists: usize = redis::cmd("EXISTS")
+        .arg(&key)
+        .arg(&lines)
+        .query(conn.connection())?;
+    assert_eq!(exists, 1);
+
+    let err = conn
+        .namespace()
+        .ensure_same_slot(&[key.as_str(), "carts:other"])
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::CrossSlot);
+
+    Cart::delete(&cart.id, &mut conn)?;
+
+    Ok(())
+}
@@ -1,11 +1,11 @@
 #![cfg(all(feature = "tokio-comp", feature = "json"))]
 
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 
 use redis::AsyncCommands;
 use redis_om::redis::Value;
 use redis_om::redis::{FromRedisValue, ToRedisArgs};
-use redis_om::{HashModel, RedisResult};
+use redis_om::{HashModel, Namespace, RedisResult, WithNamespace};
 use tokio::test;
 
 use futures::StreamExt;
@@ -182,3 +182,590 @@ async fn expiring_keys() -> Result {
 
     Ok(())
 }
+
+#[test]
+async fn find_missing_keys() -> Result {
+    #[derive(HashModel, Debug)]
+    #[redis(prefix_key = "visitors")]
+    struct Visitor {
+        id: String,
+        first_name: String,
+        last_name: String,
+    }
+
+    let mut visitor = Visitor {
+        id: "".into(),
+        first_name: "Joe".into(),
+        last_name: "Doe".into(),
+    };
+
+    let mut conn = conn().await?;
+
+    assert!(Visitor::find_by_pk("missing", &mut conn).await?.is_none());
+    assert!(!Visitor::exists("missing", &mut conn).await?);
+
+    visitor.save(&mut conn).await?;
+
+    let db_visitor = Visitor::find_by_pk(&visitor.id, &mut conn).await?;
+
+    assert_eq!(db_visitor.map(|v| v.first_name), Some(visitor.first_name));
+    assert!(Visitor::exists(&visitor.id, &mut conn).await?);
+
+    Visitor::delete(visitor.id, &mut conn).await?;
+
+    Ok(())
+}
+
+#[test]
+async fn all_records() -> Result {
+    #[derive(HashModel, Debug)]
+    #[redis(prefix_key = "guests")]
+    struct Guest {
+        id: String,
+        first_name: String,
+        last_name: String,
+    }
+
+    let mut guests = [["Joe", "Doe"], ["Jane", "Doe"], ["John", "Smith"]]
+        .map(|[first, last]| Guest {
+            id: "".into(),
+            first_name: first.into(),
+            last_name: last.into(),
+        })
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    let mut conn = conn().await?;
+
+    for guest in guests.iter_mut() {
+        guest.save(&mut conn).await?;
+    }
+
+    // Unrelated key sharing the model prefix
+    conn.set::<_, _, ()>("guests:meta", "unrelated").await?;
+
+    let db_guests = Guest::scan(2, &mut conn)
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect::<RedisResult<Vec<_>>>()?;
+
+    let mut pks = db_guests.iter().map(|g| g.id.clone()).collect::<Vec<_>>();
+    let mut expected = guests.iter().map(|g| g.id.clone()).collect::<Vec<_>>();
+    pks.sort();
+    expected.sort();
+
+    assert_eq!(pks, expected);
+
+    conn.del::<_, ()>("guests:meta").await?;
+    for guest in guests.iter() {
+        Guest::delete(&guest.id, &mut conn).await?;
+    }
+
+    Ok(())
+}
+
+#[test]
+async fn count_and_delete_all() -> Result {
+    #[derive(HashModel, Debug)]
+    #[redis(prefix_key = "tenants")]
+    struct Tenant {
+        id: String,
+        name: String,
+    }
+
+    let mut conn = conn().await?;
+    let _: RedisResult<()> = redis::cmd("FT.DROPINDEX")
+        .arg("tenants-idx")
+        .query_async(&mut conn)
+        .await;
+    redis::cmd("FT.CREATE")
+        .arg("tenants-idx")
+        .arg(
+            Tenant::redissearch_schema()
+                .split_whitespace()
+                .collect::<Vec<_>>(),
+        )
+        .query_async::<_, ()>(&mut conn)
+        .await?;
+
+    for name in ["acme", "globex", "initech"] {
+        let mut tenant = Tenant {
+            id: "".into(),
+            name: name.into(),
+        };
+        tenant.save(&mut conn).await?;
+    }
+
+    assert_eq!(Tenant::count(&mut conn).await?, 3);
+    assert_eq!(Tenant::count_indexed("tenants-idx", &mut conn).await?, 3);
+    assert_eq!(Tenant::delete_all_with(2, true, &mut conn).await?, 3);
+    assert_eq!(Tenant::count(&mut conn).await?, 3);
+    assert_eq!(Tenant::delete_all(&mut conn).await?, 3);
+    assert_eq!(Tenant::count(&mut conn).await?, 0);
+    assert_eq!(Tenant::count_indexed("tenants-idx", &mut conn).await?, 0);
+
+    Ok(())
+}
+
+#[test]
+async fn namespaced_keys() -> Result {
+    #[derive(HashModel, Debug)]
+    #[redis(prefix_key = "members")]
+    struct Member {
+        id: String,
+        #[redis(index)]
+        name: String,
+    }
+
+    let mut acme = Namespace::new()
+        .app("app")
+        .tenant("acme")
+        .wrap(conn().await?);
+    let mut globex = Namespace::new()
+        .app("app")
+        .tenant("globex")
+        .wrap(conn().await?);
+    let mut conn = conn().await?;
+
+    let mut member = Member {
+        id: "".into(),
+        name: "Jane".into(),
+    };
+    member.save(&mut acme).await?;
+
+    let key = format!("app:acme:members:{}", member.id);
+    assert!(conn.exists::<_, bool>(&key).await?);
+    assert!(Member::exists(&member.id, &mut acme).await?);
+    assert!(Member::exists(&key, &mut acme).await?);
+    assert!(!Member::exists(&member.id, &mut globex).await?);
+    assert!(!Member::exists(&member.id, &mut conn).await?);
+    assert_eq!(Member::get(&member.id, &mut acme).await?.name, "Jane");
+    assert_eq!(Member::count(&mut globex).await?, 0);
+    assert_eq!(Member::delete_all(&mut acme).await?, 1);
+
+    assert_eq!(
+        Member::redissearch_schema_in(acme.namespace()),
+        "ON HASH PREFIX 1 app:acme:members SCHEMA name TAG SEPARATOR |"
+    );
+
+    Ok(())
+}
+
+#[test]
+async fn save_removes_stale_fields() -> Result {
+    #[derive(HashModel, Debug, PartialEq)]
+    #[redis(prefix_key = "profiles")]
+    struct Profile {
+        id: String,
+        bio: Option<String>,
+        tags: Vec<String>,
+    }
+
+    let mut conn = conn().await?;
+    let mut profile = Profile {
+        id: "".into(),
+        bio: Some("Rust developer".into()),
+        tags: vec!["a".into(), "b".into(), "c".into()],
+    };
+    profile.save(&mut conn).await?;
+
+    profile.bio = None;
+    profile.tags.truncate(1);
+    profile.save(&mut conn).await?;
+
+    let key = format!("profiles:{}", profile.id);
+    let fields: Vec<String> = conn.hkeys(&key).await?;
+    assert!(!fields
+        .iter()
+        .any(|f| f == "bio" || f == "tags.1" || f == "tags.2"));
+    assert_eq!(Profile::get(&profile.id, &mut conn).await?, profile);
+
+    let mut partial = Profile {
+        id: profile.id.clone(),
+        bio: Some("Rust and Redis developer".into()),
+        tags: vec![],
+    };
+    partial.save_merge(&mut conn).await?;
+
+    let merged = Profile::get(&profile.id, &mut conn).await?;
+    assert_eq!(merged.bio, partial.bio);
+    assert_eq!(merged.tags, profile.tags);
+
+    Profile::delete(&profile.id, &mut conn).await?;
+
+    Ok(())
+}
+
+#[test]
+async fn ttl_policies() -> Result {
+    #[derive(HashModel, Debug, PartialEq)]
+    #[redis(prefix_key = "sessions", ttl = "30m")]
+    struct Session {
+        id: String,
+        user: String,
+    }
+
+    let mut conn = conn().await?;
+    let mut session = Session {
+        id: "".into(),
+        user: "jane".into(),
+    };
+    session.save(&mut conn).await?;
+
+    let ttl = session.ttl(&mut conn).await?.expect("save to apply ttl");
+    assert!(ttl > Duration::from_secs(29 * 60) && ttl <= Duration::from_secs(30 * 60));
+
+    assert!(session.persist(&mut conn).await?);
+    assert_eq!(session.ttl(&mut conn).await?, None);
+
+    let at = SystemTime::now() + Duration::from_secs(60);
+    session.expire_at(at, &mut conn).await?;
+    assert!(session.ttl(&mut conn).await? <= Some(Duration::from_secs(60)));
+
+    session.save_merge(&mut conn).await?;
+    assert!(session.ttl(&mut conn).await? > Some(Duration::from_secs(60)));
+
+    Session::delete(&session.id, &mut conn).await?;
+    assert_eq!(session.ttl(&mut conn).await?, None);
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "hash-field-ttl")]
+async fn field_ttl_policies() -> Result {
+    #[derive(HashModel, Debug, PartialEq)]
+    #[redis(prefix_key = "carts")]
+    struct Cart {
+        id: String,
+        owner: String,
+        #[redis(ttl = "10m")]
+        coupon: Option<String>,
+    }
+
+    let mut conn = conn().await?;
+    let mut cart = Cart {
+        id: "".into(),
+        owner: "jane".into(),
+        coupon: Some("WELCOME".into()),
+    };
+    cart.save(&mut conn).await?;
+
+    let ttl = cart
+        .field_ttl("coupon", &mut conn)
+        .await?
+        .expect("save to apply field ttl");
+    assert!(ttl > Duration::from_secs(9 * 60) && ttl <= Duration::from_secs(10 * 60));
+    assert_eq!(cart.field_ttl("owner", &mut conn).await?, None);
+    assert_eq!(cart.ttl(&mut conn).await?, None);
+
+    cart.expire_field("owner", Duration::from_secs(60), &mut conn)
+        .await?;
+    assert!(cart.field_ttl("owner", &mut conn).await? <= Some(Duration::from_secs(60)));
+
+    Cart::delete(&cart.id, &mut conn).await?;
+
+    Ok(())
+}
+
+#[test]
+async fn counter_primary_keys() -> Result {
+    #[derive(HashModel, Debug, PartialEq)]
+    #[redis(prefix_key = "invoices", pk_strategy = "counter")]
+    struct Invoice {
+        id: u64,
+        total: u32,
+    }
+
+    let mut conn = conn().await?;
+    let _: () = conn.del("invoices:__seq").await?;
+
+    let mut first = Invoice { id: 0, total: 10 };
+    let mut second = Invoice { id: 0, total: 20 };
+    first.save(&mut conn).await?;
+    second.save(&mut conn).await?;
+
+    assert_eq!((first.id, second.id), (1, 2));
+    assert_eq!(
+        Invoice::get(second.id.to_string(), &mut conn).await?,
+        second
+    );
+    assert_eq!(Invoice::count(&mut conn).await?, 2);
+
+    Invoice::delete_all(&mut conn).await?;
+    let _: () = conn.del("invoices:__seq").await?;
+
+    Ok(())
+}
+
+#[test]
+async fn composite_primary_keys() -> Result {
+    #[derive(HashModel, Debug, PartialEq)]
+    #[redis(prefix_key = "members")]
+    struct Member {
+        #[redis(primary_key)]
+        tenant_id: String,
+        #[redis(primary_key)]
+        user_id: u64,
+        role: String,
+    }
+
+    assert_eq!(
+        Member::redissearch_schema(),
+        "ON HASH PREFIX 1 members SCHEMA tenant_id TAG SEPARATOR | user_id TAG SEPARATOR |"
+    );
+
+    let mut conn = conn().await?;
+    let mut member = Member {
+        tenant_id: "acme".into(),
+        user_id: 7,
+        role: "admin".into(),
+    };
+    member.save(&mut conn).await?;
+
+    assert!(Member::exists("members:acme:7", &mut conn).await?);
+    assert_eq!(Member::get(("acme", 7), &mut conn).await?, member);
+    assert!(Member::find_by_pk(("acme", 8), &mut conn).await?.is_none());
+
+    let mut unset = Member {
+        tenant_id: "acme".into(),
+        user_id: 0,
+        role: "guest".into(),
+    };
+    assert!(unset.save(&mut conn).await.is_err());
+
+    Member::delete((&member.tenant_id, member.user_id), &mut conn).await?;
+    assert!(!Member::exists(("acme", 7), &mut conn).await?);
+
+    Ok(())
+}
+
+#[test]
+async fn soft_deletes() -> Result {
+    #[derive(HashModel, Debug, PartialEq)]
+    #[redis(prefix_key = "patients", soft_delete)]
+    struct Patient {
+        #[redis(primary_key)]
+        id: String,
+        name: String,
+    }
+
+    #[derive(HashModel, Debug, PartialEq)]
+    #[redis(prefix_key = "archives", soft_delete = "move")]
+    struct Archive {
+        id: String,
+        name: String,
+    }
+
+    assert_eq!(
+        Patient::redissearch_schema(),
+        "ON HASH PREFIX 1 patients FILTER !exists(@__deleted_at) SCHEMA id TAG SEPARATOR |"
+    );
+
+    let mut conn = conn().await?;
+    let mut patient = Patient {
+        id: "".into(),
+        name: "Jane".into(),
+    };
+    patient.save(&mut conn).await?;
+    let live = Patient::count(&mut conn).await?;
+    Patient::delete(&patient.id, &mut conn).await?;
+    assert_eq!(Patient::count(&mut conn).await?, live - 1);
+
+    let key = format!("patients:{}", patient.id);
+    let deleted_at: Option<u64> = conn.hget(&key, "__deleted_at").await?;
+    assert!(deleted_at.is_some());
+    assert!(Patient::get(&patient.id, &mut conn).await.is_err());
+    assert!(Patient::find_by_pk(&patient.id, &mut conn).await?.is_none());
+    assert!(!Patient::exists(&patient.id, &mut conn).await?);
+
+    assert!(Patient::restore(&patient.id, &mut conn).await?);
+    assert!(!Patient::restore(&patient.id, &mut conn).await?);
+    assert_eq!(Patient::get(&patient.id, &mut conn).await?, patient);
+
+    Patient::delete(&patient.id, &mut conn).await?;
+    assert!(Patient::purge_deleted(Duration::from_secs(60), &mut conn).await? == 0);
+    assert!(Patient::purge_deleted(Duration::ZERO, &mut conn).await? >= 1);
+    assert!(!conn.exists::<_, bool>(&key).await?);
+
+    let mut archive = Archive {
+        id: "".into(),
+        name: "2023".into(),
+    };
+    archive.save(&mut conn).await?;
+    let live = Archive::count(&mut conn).await?;
+    Archive::delete(&archive.id, &mut conn).await?;
+    assert_eq!(Archive::count(&mut conn).await?, live - 1);
+
+    let deleted_key = format!("archives__deleted:{}", archive.id);
+    assert!(conn.exists::<_, bool>(&deleted_key).await?);
+    assert!(Archive::find_by_pk(&archive.id, &mut conn).await?.is_none());
+
+    assert!(Archive::restore(&archive.id, &mut conn).await?);
+    assert!(!conn.exists::<_, bool>(&deleted_key).await?);
+    assert_eq!(Archive::get(&archive.id, &mut conn).await?, archive);
+
+    Archive::delete(&archive.id, &mut conn).await?;
+    assert!(Archive::purge_deleted(Duration::ZERO, &mut conn).await? >= 1);
+    assert!(!conn.exists::<_, bool>(&deleted_key).await?);
+
+    archive.save(&mut conn).await?;
+    Archive::delete(&archive.id, &mut conn).await?;
+    assert!(Archive::delete_all(&mut conn).await? >= 1);
+    assert!(!conn.exists::<_, bool>(&deleted_key).await?);
+
+    Ok(())
+}
+
+#[test]
+async fn audit_stream() -> Result {
+    use redis_om::{AuditLog, AuditOp, StreamModel, TypedMessage};
+
+    #[derive(HashModel, Debug, PartialEq)]
+    #[redis(prefix_key = "ledgers", audit_stream = "ledger-audit")]
+    struct Ledger {
+        id: String,
+        owner: String,
+        balance: u32,
+    }
+
+    let mut conn = conn().await?;
+    let auditors = AuditLog::<Ledger>::new("auditors");
+    auditors.ensure_group_stream(&mut conn).await?;
+
+    let mut ledger = Ledger {
+        id: "".into(),
+        owner: "Jane".into(),
+        balance: 10,
+    };
+    ledger.save(&mut conn).await?;
+    ledger.balance = 20;
+    ledger.save_merge(&mut conn).await?;
+    Ledger::delete(&ledger.id, &mut conn).await?;
+
+    let records = auditors
+        .read(Some(10), None, &mut conn)
+        .await?
+        .into_iter()
+        .map(TypedMessage::into_data)
+        .filter(|record| matches!(record, Ok(record) if record.pk == ledger.id))
+        .collect::<RedisResult<Vec<_>>>()?;
+
+    let ops = records.iter().map(|record| record.op).collect::<Vec<_>>();
+    assert_eq!(ops, [AuditOp::Save, AuditOp::Save, AuditOp::Delete]);
+    assert_eq!(records[1].changes.len(), 1);
+    assert_eq!(records[1].changes[0].new.as_deref(), Some("20"));
+
+    Ok(())
+}
+
+#[test]
+async fn cached_model() -> Result {
+    #[derive(HashModel, Debug, PartialEq, Clone)]
+    #[redis(prefix_key = "cached-products")]
+    struct Product {
+        id: String,
+        name: String,
+    }
+
+    let mut conn = conn().await?;
+    let cache = Product::cache()
+        .local(10)
+        .lock_timeout(Duration::from_secs(1));
+    let ttl = Duration::from_secs(60);
+    conn.del::<_, ()>(&["cached-products:stool", "cached-products:bench:__miss"])
+        .await?;
+
+    let loaded = cache
+        .get_or_load(
+            "stool",
+            || async {
+                RedisResult::Ok(Some(Product {
+                    id: "stool".into(),
+                    name: "Stool".into(),
+                }))
+            },
+            ttl,
+            &mut conn,
+        )
+        .await?;
+    assert_eq!(loaded.as_ref().map(|p| p.name.as_str()), Some("Stool"));
+    assert!(Product::exists("stool", &mut conn).await?);
+
+    let load_once = || async { RedisResult::Ok(None) };
+    let miss = cache
+        .get_or_load("bench", load_once, ttl, &mut conn)
+        .await?;
+    assert!(miss.is_none());
+    assert!(
+        conn.exists::<_, bool>("cached-products:bench:__miss")
+            .await?
+    );
+
+    Product::delete("stool", &mut conn).await?;
+
+    Ok(())
+}
+
+#[test]
+async fn schema_migrations() -> Result {
+    use redis_om::{HashRecord, Migrations, SCHEMA_VERSION};
+
+    fn migrations() -> Migrations<HashRecord> {
+        Migrations::new().register((1, |record: &mut HashRecord| {
+            let first = record.remove("first_name").unwrap_or_default();
+            let last = record.remove("last_name").unwrap_or_default();
+            record.insert("full_name".into(), format!("{first} {last}"));
+            Ok(())
+        }))
+    }
+
+    #[derive(HashModel, Debug, PartialEq)]
+    #[redis(prefix_key = "profiles", schema_version = 2, migrations = "migrations")]
+    struct Profile {
+        id: String,
+        full_name: String,
+    }
+
+    let mut conn = conn().await?;
+    Profile::delete_all(&mut conn).await?;
+
+    let key = |id: &str| format!("profiles:{id}");
+    for id in ["1", "2"] {
+        conn.hset_multiple::<_, _, _, ()>(
+            key(id),
+            &[("id", id), ("first_name", "Jane"), ("last_name", "Doe")],
+        )
+        .await?;
+    }
+
+    let profile = Profile::get("1", &mut conn).await?;
+    assert_eq!(profile.full_name, "Jane Doe");
+    assert_eq!(
+        conn.hget::<_, _, String>(key("1"), SCHEMA_VERSION).await?,
+        "2"
+    );
+    assert!(!conn.hexists::<_, _, bool>(key("1"), "first_name").await?);
+
+    let mut batches = 0;
+    let progress = Profile::migrate_all(0, |_| batches += 1, &mut conn).await?;
+    assert_eq!((progress.scanned, progress.migrated), (2, 1));
+    assert!(progress.failed.is_empty());
+    assert_eq!(progress.cursor, 0);
+    assert!(batches > 0);
+    assert_eq!(
+        conn.hget::<_, _, String>(key("2"), "full_name").await?,
+        "Jane Doe"
+    );
+
+    let mut profile = Profile::get("2", &mut conn).await?;
+    profile.full_name = "John Doe".into();
+    profile.save(&mut conn).await?;
+    assert_eq!(
+        conn.hget::<_, _, String>(key("2"), SCHEMA_VERSION).await?,
+        "2"
+    );
+
+    Ok(())
+}
@@ -1,11 +1,11 @@
 #![cfg(all(not(feature = "aio"), feature = "json"))]
 
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 
 use redis::Commands;
 use redis_om::redis::Value;
 use redis_om::redis::{FromRedisValue, ToRedisArgs};
-use redis_om::HashModel;
+use redis_om::{HashModel, Namespace, RedisResult, WithNamespace};
 
 type Result<T = (), E = Box<dyn std::error::Error>> = std::result::Result<T, E>;
 
@@ -188,6 +188,431 @@ fn expiring_keys() -> Result {
     Ok(())
 }
 
+#[test]
+fn find_missing_keys() -> Result {
+    #[derive(HashModel, Debug)]
+    #[redis(prefix_key = "visitors")]
+    struct Visitor {
+        id: String,
+        first_name: String,
+        last_name: String,
+    }
+
+    let mut visitor = Visitor {
+        id: "".into(),
+        first_name: "Joe".into(),
+        last_name: "Doe".into(),
+    };
+
+    let mut conn = client()?.get_connection()?;
+
+    assert!(Visitor::find_by_pk("missing", &mut conn)?.is_none());
+    assert!(!Visitor::exists("missing", &mut conn)?);
+
+    visitor.save(&mut conn)?;
+
+    let db_visitor = Visitor::find_by_pk(&visitor.id, &mut conn)?;
+
+    assert_eq!(db_visitor.map(|v| v.first_name), Some(visitor.first_name));
+    assert!(Visitor::exists(&visitor.id, &mut conn)?);
+
+    Visitor::delete(visitor.id, &mut conn)?;
+
+    Ok(())
+}
+
+#[test]
+fn all_records() -> Result {
+    #[derive(HashModel, Debug)]
+    #[redis(prefix_key = "guests")]
+    struct Guest {
+        id: String,
+        first_name: String,
+        last_name: String,
+    }
+
+    let mut guests = [["Joe", "Doe"], ["Jane", "Doe"], ["John", "Smith"]]
+        .map(|[first, last]| Guest {
+            id: "".into(),
+            first_name: first.into(),
+            last_name: last.into(),
+        })
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    let mut conn = client()?.get_connection()?;
+
+    for guest in guests.iter_mut() {
+        guest.save(&mut conn)?;
+    }
+
+    // Unrelated key sharing the model prefix
+    conn.set::<_, _, ()>("guests:meta", "unrelated")?;
+
+    let db_guests = Guest::scan(2, &mut conn).collect::<RedisResult<Vec<_>>>()?;
+
+    let mut pks = db_guests.iter().map(|g| g.id.clone()).collect::<Vec<_>>();
+    let mut expected = guests.iter().map(|g| g.id.clone()).collect::<Vec<_>>();
+    pks.sort();
+    expected.sort();
+
+    assert_eq!(pks, expected);
+
+    conn.del::<_, ()>("guests:meta")?;
+    for guest in guests.iter() {
+        Guest::delete(&guest.id, &mut conn)?;
+    }
+
+    Ok(())
+}
+
+#[test]
+fn count_and_delete_all() -> Result {
+    #[derive(HashModel, Debug)]
+    #[redis(prefix_key = "tenants")]
+    struct Tenant {
+        id: String,
+        name: String,
+    }
+
+    let mut conn = client()?.get_connection()?;
+    let _: RedisResult<()> = redis::cmd("FT.DROPINDEX")
+        .arg("tenants-idx")
+        .query(&mut conn);
+    redis::cmd("FT.CREATE")
+        .arg("tenants-idx")
+        .arg(
+            Tenant::redissearch_schema()
+                .split_whitespace()
+                .collect::<Vec<_>>(),
+        )
+        .query::<()>(&mut conn)?;
+
+    for name in ["acme", "globex", "initech"] {
+        let mut tenant = Tenant {
+            id: "".into(),
+            name: name.into(),
+        };
+        tenant.save(&mut conn)?;
+    }
+
+    assert_eq!(Tenant::count(&mut conn)?, 3);
+    assert_eq!(Tenant::count_indexed("tenants-idx", &mut conn)?, 3);
+    assert_eq!(Tenant::delete_all_with(2, true, &mut conn)?, 3);
+    assert_eq!(Tenant::count(&mut conn)?, 3);
+    assert_eq!(Tenant::delete_all(&mut conn)?, 3);
+    assert_eq!(Tenant::count(&mut conn)?, 0);
+    assert_eq!(Tenant::count_indexed("tenants-idx", &mut conn)?, 0);
+
+    Ok(())
+}
+
+#[test]
+fn namespaced_keys() -> Result {
+    #[derive(HashModel, Debug)]
+    #[redis(prefix_key = "members")]
+    struct Member {
+        id: String,
+        #[redis(index)]
+        name: String,
+    }
+
+    let client = client()?;
+    let mut conn = client.get_connection()?;
+    let mut acme = Namespace::new()
+        .app("app")
+        .tenant("acme")
+        .wrap(client.get_connection()?);
+    let mut globex = Namespace::new()
+        .app("app")
+        .tenant("globex")
+        .wrap(client.get_connection()?);
+
+    let mut member = Member {
+        id: "".into(),
+        name: "Jane".into(),
+    };
+    member.save(&mut acme)?;
+
+    let key = format!("app:acme:members:{}", member.id);
+    assert!(conn.exists::<_, bool>(&key)?);
+    assert!(Member::exists(&member.id, &mut acme)?);
+    assert!(Member::exists(&key, &mut acme)?);
+    assert!(!Member::exists(&member.id, &mut globex)?);
+    assert!(!Member::exists(&member.id, &mut conn)?);
+    assert_eq!(Member::get(&member.id, &mut acme)?.name, "Jane");
+    assert_eq!(Member::count(&mut globex)?, 0);
+    assert_eq!(Member::delete_all(&mut acme)?, 1);
+
+    assert_eq!(
+        Member::redissearch_schema_in(acme.namespace()),
+        "ON HASH PREFIX 1 app:acme:members SCHEMA name TAG SEPARATOR |"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn save_removes_stale_fields() -> Result {
+    #[derive(HashModel, Debug, PartialEq)]
+    #[redis(prefix_key = "profiles")]
+    struct Profile {
+        id: String,
+        bio: Option<String>,
+        tags: Vec<String>,
+    }
+
+    let mut conn = client()?.get_connection()?;
+    let mut profile = Profile {
+        id: "".into(),
+        bio: Some("Rust developer".into()),
+        tags: vec!["a".into(), "b".into(), "c".into()],
+    };
+    profile.save(&mut conn)?;
+
+    profile.bio = None;
+    profile.tags.truncate(1);
+    profile.save(&mut conn)?;
+
+    let key = format!("profiles:{}", profile.id);
+    let fields: Vec<String> = conn.hkeys(&key)?;
+    assert!(!fields
+        .iter()
+        .any(|f| f == "bio" || f == "tags.1" || f == "tags.2"));
+    assert_eq!(Profile::get(&profile.id, &mut conn)?, profile);
+
+    let mut partial = Profile {
+        id: profile.id.clone(),
+        bio: Some("Rust and Redis developer".into()),
+        tags: vec![],
+    };
+    partial.save_merge(&mut conn)?;
+
+    let merged = Profile::get(&profile.id, &mut conn)?;
+    assert_eq!(merged.bio, partial.bio);
+    assert_eq!(merged.tags, profile.tags);
+
+    Profile::delete(&profile.id, &mut conn)?;
+
+    Ok(())
+}
+
+#[test]
+fn ttl_policies() -> Result {
+    #[derive(HashModel, Debug, PartialEq)]
+    #[redis(prefix_key = "sessions", ttl = "30m")]
+    struct Session {
+        id: String,
+        user: String,
+    }
+
+    let mut conn = client()?.get_connection()?;
+    let mut session = Session {
+        id: "".into(),
+        user: "jane".into(),
+    };
+    session.save(&mut conn)?;
+
+    let ttl = session.ttl(&mut conn)?.expect("save to apply ttl");
+    assert!(ttl > Duration::from_secs(29 * 60) && ttl <= Duration::from_secs(30 * 60));
+
+    assert!(session.persist(&mut conn)?);
+    assert_eq!(session.ttl(&mut conn)?, None);
+
+    let at = SystemTime::now() + Duration::from_secs(60);
+    session.expire_at(at, &mut conn)?;
+    assert!(session.ttl(&mut conn)? <= Some(Duration::from_secs(60)));
+
+    session.save_merge(&mut conn)?;
+    assert!(session.ttl(&mut conn)? > Some(Duration::from_secs(60)));
+
+    Session::delete(&session.id, &mut conn)?;
+    assert_eq!(session.ttl(&mut conn)?, None);
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "hash-field-ttl")]
+fn field_ttl_policies() -> Result {
+    #[derive(HashModel, Debug, PartialEq)]
+    #[redis(prefix_key = "carts")]
+    struct Cart {
+        id: String,
+        owner: String,
+        #[redis(ttl = "10m")]
+        coupon: Option<String>,
+    }
+
+    let mut conn = client()?.get_connection()?;
+    let mut cart = Cart {
+        id: "".into(),
+        owner: "jane".into(),
+        coupon: Some("WELCOME".into()),
+    };
+    cart.save(&mut conn)?;
+
+    let ttl = cart
+        .field_ttl("coupon", &mut conn)?
+        .expect("save to apply field ttl");
+    assert!(ttl > Duration::from_secs(9 * 60) && ttl <= Duration::from_secs(10 * 60));
+    assert_eq!(cart.field_ttl("owner", &mut conn)?, None);
+    assert_eq!(cart.ttl(&mut conn)?, None);
+
+    cart.expire_field("owner", Duration::from_secs(60), &mut conn)?;
+    assert!(cart.field_ttl("owner", &mut conn)? <= Some(Duration::from_secs(60)));
+
+    Cart::delete(&cart.id, &mut conn)?;
+
+    Ok(())
+}
+
+#[test]
+fn counter_primary_keys() -> Result {
+    #[derive(HashModel, Debug, PartialEq)]
+    #[redis(prefix_key = "invoices", pk_strategy = "counter")]
+    struct Invoice {
+        id: u64,
+        total: u32,
+    }
+
+    let mut conn = client()?.get_connection()?;
+    let _: () = conn.del("invoices:__seq")?;
+
+    let mut first = Invoice { id: 0, total: 10 };
+    let mut second = Invoice { id: 0, total: 20 };
+    first.save(&mut conn)?;
+    second.save(&mut conn)?;
+
+    assert_eq!((first.id, second.id), (1, 2));
+    assert_eq!(Invoice::get(second.id.to_string(), &mut conn)?, second);
+    assert_eq!(Invoice::count(&mut conn)?, 2);
+
+    Invoice::delete_all(&mut conn)?;
+    let _: () = conn.del("invoices:__seq")?;
+
+    Ok(())
+}
+
+#[test]
+fn composite_primary_keys() -> Result {
+    #[derive(HashModel, Debug, PartialEq)]
+    #[redis(prefix_key = "members")]
+    struct Member {
+        #[redis(primary_key)]
+        tenant_id: String,
+        #[redis(primary_key)]
+        user_id: u64,
+        role: String,
+    }
+
+    assert_eq!(
+        Member::redissearch_schema(),
+        "ON HASH PREFIX 1 members SCHEMA tenant_id TAG SEPARATOR | user_id TAG SEPARATOR |"
+    );
+
+    let mut conn = client()?.get_connection()?;
+    let mut member = Member {
+        tenant_id: "acme".into(),
+        user_id: 7,
+        role: "admin".into(),
+    };
+    member.save(&mut conn)?;
+
+    assert!(Member::exists("members:acme:7", &mut conn)?);
+    assert_eq!(Member::get(("acme", 7), &mut conn)?, member);
+    assert!(Member::find_by_pk(("acme", 8), &mut conn)?.is_none());
+
+    let mut unset = Member {
+        tenant_id: "acme".into(),
+        user_id: 0,
+        role: "guest".into(),
+    };
+    assert!(unset.save(&mut conn).is_err());
+
+    Member::delete((&member.tenant_id, member.user_id), &mut conn)?;
+    assert!(!Member::exists(("acme", 7), &mut conn)?);
+
+    Ok(())
+}
+
+#[test]
+fn soft_deletes() -> Result {
+    #[derive(HashModel, Debug, PartialEq)]
+    #[redis(prefix_key = "patients", soft_delete)]
+    struct Patient {
+        #[redis(primary_key)]
+        id: String,
+        name: String,
+    }
+
+    #[derive(HashModel, Debug, PartialEq)]
+    #[redis(prefix_key = "archives", soft_delete = "move")]
+    struct Archive {
+        id: String,
+        name: String,
+    }
+
+    assert_eq!(
+        Patient::redissearch_schema(),
+        "ON HASH PREFIX 1 patients FILTER !exists(@__deleted_at) SCHEMA id TAG SEPARATOR |"
+    );
+
+    let mut conn = client()?.get_connection()?;
+    let mut patient = Patient {
+        id: "".into(),
+        name: "Jane".into(),
+    };
+    patient.save(&mut conn)?;
+    let live = Patient::count(&mut conn)?;
+    Patient::delete(&patient.id, &mut conn)?;
+    assert_eq!(Patient::count(&mut conn)?, live - 1);
+
+    let key = format!("patients:{}", patient.id);
+    let deleted_at: Option<u64> = conn.hget(&key, "__deleted_at")?;
+    assert!(deleted_at.is_some());
+    assert!(Patient::get(&patient.id, &mut conn).is_err());
+    assert!(Patient::find_by_pk(&patient.id, &mut conn)?.is_none());
+    assert!(!Patient::exists(&patient.id, &mut conn)?);
+
+    assert!(Patient::restore(&patient.id, &mut conn)?);
+    assert!(!Patient::restore(&patient.id, &mut conn)?);
+    assert_eq!(Patient::get(&patient.id, &mut conn)?, patient);
+
+    Patient::delete(&patient.id, &mut conn)?;
+    assert!(Patient::purge_deleted(Duration::from_secs(60), &mut conn)? == 0);
+    assert!(Patient::purge_deleted(Duration::ZERO, &mut conn)? >= 1);
+    assert!(!conn.exists::<_, bool>(&key)?);
+
+    let mut archive = Archive {
+        id: "".into(),
+        name: "2023".into(),
+    };
+    archive.save(&mut conn)?;
+    let live = Archive::count(&mut conn)?;
+    Archive::delete(&archive.id, &mut conn)?;
+    assert_eq!(Archive::count(&mut conn)?, live - 1);
+
+    let deleted_key = format!("archives__deleted:{}", archive.id);
+    assert!(conn.exists::<_, bool>(&deleted_key)?);
+    assert!(Archive::find_by_pk(&archive.id, &mut conn)?.is_none());
+
+    assert!(Archive::restore(&archive.id, &mut conn)?);
+    assert!(!conn.exists::<_, bool>(&deleted_key)?);
+    assert_eq!(Archive::get(&archive.id, &mut conn)?, archive);
+
+    Archive::delete(&archive.id, &mut conn)?;
+    assert!(Archive::purge_deleted(Duration::ZERO, &mut conn)? >= 1);
+    assert!(!conn.exists::<_, bool>(&deleted_key)?);
+
+    archive.save(&mut conn)?;
+    Archive::delete(&archive.id, &mut conn)?;
+    assert!(Archive::delete_all(&mut conn)? >= 1);
+    assert!(!conn.exists::<_, bool>(&deleted_key)?);
+
+    Ok(())
+}
+
 #[test]
 fn test_redis_search_schema() -> Result {
     #[derive(HashModel, Debug)]
@@ -220,3 +645,163 @@ fn test_redis_search_schema() -> Result {
 
     Ok(())
 }
+
+#[test]
+fn audit_stream() -> Result {
+    use redis_om::{AuditLog, AuditOp, StreamModel, TypedMessage};
+
+    #[derive(HashModel, Debug, PartialEq)]
+    #[redis(prefix_key = "ledgers", audit_stream = "ledger-audit")]
+    struct Ledger {
+        id: String,
+        owner: String,
+        balance: u32,
+    }
+
+    let mut conn = client()?.get_connection()?;
+    let auditors = AuditLog::<Ledger>::new("auditors");
+    auditors.ensure_group_stream(&mut conn)?;
+    assert_eq!(AuditLog::<Ledger>::stream_key(), "ledger-audit");
+
+    let mut ledger = Ledger {
+        id: "".into(),
+        owner: "Jane".into(),
+        balance: 10,
+    };
+    ledger.save(&mut conn)?;
+    ledger.balance = 20;
+    ledger.save(&mut conn)?;
+    Ledger::delete(&ledger.id, &mut conn)?;
+    Ledger::delete(&ledger.id, &mut conn)?;
+
+    let records = auditors
+        .read(Some(10), None, &mut conn)?
+        .into_iter()
+        .map(TypedMessage::into_data)
+        .filter(|record| matches!(record, Ok(record) if record.pk == ledger.id))
+        .collect::<RedisResult<Vec<_>>>()?;
+
+    let ops = records.iter().map(|record| record.op).collect::<Vec<_>>();
+    assert_eq!(ops, [AuditOp::Save, AuditOp::Save, AuditOp::Delete]);
+    assert_eq!(records[0].changes.len(), 3);
+    assert!(records[0].changes.iter().all(|change| change.old.is_none()));
+
+    let change = &records[1].changes[..];
+    assert_eq!(change.len(), 1);
+    assert_eq!(change[0].field, "balance");
+    assert_eq!(change[0].old.as_deref(), Some("10"));
+    assert_eq!(change[0].new.as_deref(), Some("20"));
+
+    assert!(records[2].changes.iter().all(|change| change.new.is_none()));
+
+    Ok(())
+}
+
+#[test]
+fn cached_model() -> Result {
+    #[derive(HashModel, Debug, PartialEq, Clone)]
+    #[redis(prefix_key = "cached-products")]
+    struct Product {
+        id: String,
+        name: String,
+    }
+
+    let mut conn = client()?.get_connection()?;
+    let cache = Product::cache().local(10);
+    let ttl = Duration::from_secs(60);
+    let pk = "chair".to_string();
+    let missing = "table".to_string();
+    conn.del::<_, ()>(&["cached-products:chair", "cached-products:table:__miss"])?;
+
+    let loaded = cache.get_or_load(
+        &pk,
+        || {
+            RedisResult::Ok(Some(Product {
+                id: pk.clone(),
+                name: "Chair".into(),
+            }))
+        },
+        ttl,
+        &mut conn,
+    )?;
+    assert_eq!(loaded.as_ref().map(|p| p.name.as_str()), Some("Chair"));
+    assert!(Product::exists(&pk, &mut conn)?);
+    assert!(conn.pttl::<_, i64>(format!("cached-products:{pk}"))? > 0);
+
+    // Served from cache without calling the loader
+    let cached = cache.get_or_load(&pk, || -> RedisResult<_> { unreachable!() }, ttl, &mut conn)?;
+    assert_eq!(cached, loaded);
+
+    // Misses are cached as well
+    let miss = cache.get_or_load(&missing, || RedisResult::Ok(None), ttl, &mut conn)?;
+    assert!(miss.is_none());
+    assert!(conn.exists::<_, bool>(format!("cached-products:{missing}:__miss"))?);
+
+    let other = Product::cache();
+    let miss = other.get_or_load(
+        &missing,
+        || -> RedisResult<_> { unreachable!() },
+        ttl,
+        &mut conn,
+    )?;
+    assert!(miss.is_none());
+
+    Product::delete(&pk, &mut conn)?;
+
+    Ok(())
+}
+
+#[test]
+fn schema_migrations() -> Result {
+    use redis_om::{HashRecord, Migrations, SCHEMA_VERSION};
+
+    fn migrations() -> Migrations<HashRecord> {
+        Migrations::new().register((1, |record: &mut HashRecord| {
+            let first = record.remove("first_name").unwrap_or_default();
+            let last = record.remove("last_name").unwrap_or_default();
+            record.insert("full_name".into(), format!("{first} {last}"));
+            Ok(())
+        }))
+    }
+
+    #[derive(HashModel, Debug, PartialEq)]
+    #[redis(prefix_key = "profiles", schema_version = 2, migrations = "migrations")]
+    struct Profile {
+        id: String,
+        full_name: String,
+    }
+
+    let mut conn = client()?.get_connection()?;
+    Profile::delete_all(&mut conn)?;
+
+    let key = |id: &str| format!("profiles:{id}");
+    for id in ["1", "2"] {
+        conn.hset_multiple::<_, _, _, ()>(
+            key(id),
+            &[("id", id), ("first_name", "Jane"), ("last_name", "Doe")],
+        )?;
+    }
+
+    let profile = Profile::get("1", &mut conn)?;
+    assert_eq!(profile.full_name, "Jane Doe");
+    assert_eq!(conn.hget::<_, _, String>(key("1"), SCHEMA_VERSION)?, "2");
+    assert!(!conn.hexists::<_, _, bool>(key("1"), "first_name")?);
+
+    let mut batches = 0;
+    let progress = Profile::migrate_all(0, |_| batches += 1, &mut conn)?;
+    assert_eq!((progress.scanned, progress.migrated), (2, 1));
+    assert!(progress.failed.is_empty());
+    assert_eq!(progress.cursor, 0);
+    assert!(batches > 0);
+    assert_eq!(
+        conn.hget::<_, _, String>(key("2"), "full_name")?,
+        "Jane Doe"
+    );
+
+    let mut profile = Profile::get("2", &mut conn)?;
+    profile.full_name = "John Doe".into();
+    profile.save(&mut conn)?;
+    assert_eq!(conn.hget::<_, _, String>(key("2"), SCHEMA_VERSION)?, "2");
+
+    Ok(())
+}
@@ -1,7 +1,8 @@
 #![cfg(all(feature = "tokio-comp", feature = "json"))]
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 use tokio::test;
 
+use futures::StreamExt;
 use redis::AsyncCommands;
 use redis_om::{JsonModel, RedisResult};
 use serde::{Deserialize, Serialize};
@@ -179,3 +180,314 @@ async fn expiring_keys() -> Result {
 
     Ok(())
 }
+
+#[test]
+async fn find_missing_keys() -> Result {
+    #[derive(JsonModel, Serialize, Deserialize, Debug)]
+    #[redis(prefix_key = "visitors")]
+    struct Visitor {
+        id: String,
+        first_name: String,
+        last_name: String,
+    }
+
+    let mut visitor = Visitor {
+        id: "".into(),
+        first_name: "Joe".into(),
+        last_name: "Doe".into(),
+    };
+
+    let mut conn = conn().await?;
+
+    assert!(Visitor::find_by_pk("missing", &mut conn).await?.is_none());
+    assert!(!Visitor::exists("missing", &mut conn).await?);
+
+    visitor.save(&mut conn).await?;
+
+    let db_visitor = Visitor::find_by_pk(&visitor.id, &mut conn).await?;
+
+    assert_eq!(db_visitor.map(|v| v.first_name), Some(visitor.first_name));
+    assert!(Visitor::exists(&visitor.id, &mut conn).await?);
+
+    Visitor::delete(visitor.id, &mut conn).await?;
+
+    Ok(())
+}
+
+#[test]
+async fn all_records() -> Result {
+    #[derive(JsonModel, Serialize, Deserialize, Debug)]
+    #[redis(prefix_key = "guests")]
+    struct Guest {
+        id: String,
+        first_name: String,
+        last_name: String,
+    }
+
+    let mut guests = [["Joe", "Doe"], ["Jane", "Doe"], ["John", "Smith"]]
+        .map(|[first, last]| Guest {
+            id: "".into(),
+            first_name: first.into(),
+            last_name: last.into(),
+        })
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    let mut conn = conn().await?;
+
+    for guest in guests.iter_mut() {
+        guest.save(&mut conn).await?;
+    }
+
+    // Unrelated key sharing the model prefix
+    conn.set::<_, _, ()>("guests:meta", "unrelated").await?;
+
+    let mut db_guests = vec![];
+    let mut stream = Guest::scan(2, &mut conn);
+
+    while let Some(guest) = stream.next().await {
+        db_guests.push(guest?);
+    }
+
+    drop(stream);
+
+    let mut pks = db_guests.iter().map(|g| g.id.clone()).collect::<Vec<_>>();
+    let mut expected = guests.iter().map(|g| g.id.clone()).collect::<Vec<_>>();
+    pks.sort();
+    expected.sort();
+
+    assert_eq!(pks, expected);
+
+    conn.del::<_, ()>("guests:meta").await?;
+    for guest in guests.iter() {
+        Guest::delete(&guest.id, &mut conn).await?;
+    }
+
+    Ok(())
+}
+
+#[test]
+async fn count_and_delete_all() -> Result {
+    #[derive(JsonModel, Serialize, Deserialize, Debug)]
+    #[redis(prefix_key = "tenants")]
+    struct Tenant {
+        id: String,
+        name: String,
+    }
+
+    let mut conn = conn().await?;
+
+    for name in ["acme", "globex", "initech"] {
+        let mut tenant = Tenant {
+            id: "".into(),
+            name: name.into(),
+        };
+        tenant.save(&mut conn).await?;
+    }
+
+    assert_eq!(Tenant::count(&mut conn).await?, 3);
+    assert_eq!(Tenant::delete_all_with(2, true, &mut conn).await?, 3);
+    assert_eq!(Tenant::count(&mut conn).await?, 3);
+    assert_eq!(Tenant::delete_all(&mut conn).await?, 3);
+    assert_eq!(Tenant::count(&mut conn).await?, 0);
+
+    Ok(())
+}
+
+#[test]
+async fn ttl_policies() -> Result {
+    #[derive(JsonModel, Serialize, Deserialize)]
+    #[redis(prefix_key = "carts", ttl = "1h")]
+    struct Cart {
+        id: String,
+        items: Vec<String>,
+    }
+
+    let mut conn = conn().await?;
+    let mut cart = Cart {
+        id: "".into(),
+        items: vec!["book".into()],
+    };
+    cart.save(&mut conn).await?;
+
+    let ttl = cart.ttl(&mut conn).await?.expect("save to apply ttl");
+    assert!(ttl > Duration::from_secs(59 * 60) && ttl <= Duration::from_secs(60 * 60));
+
+    assert!(cart.persist(&mut conn).await?);
+    assert_eq!(cart.ttl(&mut conn).await?, None);
+
+    let at = SystemTime::now() + Duration::from_secs(60);
+    cart.expire_at(at, &mut conn).await?;
+    assert!(cart.ttl(&mut conn).await? <= Some(Duration::from_secs(60)));
+
+    Cart::delete(&cart.id, &mut conn).await?;
+    assert_eq!(cart.ttl(&mut conn).await?, None);
+
+    Ok(())
+}
+
+#[test]
+async fn soft_deletes() -> Result {
+    #[derive(JsonModel, Serialize, Deserialize)]
+    #[redis(prefix_key = "patients", soft_delete)]
+    struct Patient {
+        id: String,
+        name: String,
+    }
+
+    let mut conn = conn().await?;
+    let mut patient = Patient {
+        id: "".into(),
+        name: "Jane".into(),
+    };
+    patient.save(&mut conn).await?;
+    let live = Patient::count(&mut conn).await?;
+    Patient::delete(&patient.id, &mut conn).await?;
+    assert_eq!(Patient::count(&mut conn).await?, live - 1);
+
+    assert!(Patient::get(&patient.id, &mut conn).await.is_err());
+    assert!(Patient::find_by_pk(&patient.id, &mut conn).await?.is_none());
+    assert!(!Patient::exists(&patient.id, &mut conn).await?);
+
+    assert!(Patient::restore(&patient.id, &mut conn).await?);
+    assert_eq!(Patient::get(&patient.id, &mut conn).await?.name, "Jane");
+
+    Patient::delete(&patient.id, &mut conn).await?;
+    assert!(Patient::purge_deleted(Duration::ZERO, &mut conn).await? >= 1);
+    assert!(
+        !conn
+            .exists::<_, bool>(format!("patients:{}", patient.id))
+            .await?
+    );
+
+    patient.save(&mut conn).await?;
+    Patient::delete(&patient.id, &mut conn).await?;
+    assert!(Patient::delete_all(&mut conn).await? >= 1);
+    assert!(Patient::count(&mut conn).await? == 0);
+
+    Ok(())
+}
+
+#[test]
+async fn audit_stream() -> Result {
+    use redis_om::{AuditLog, AuditOp, StreamModel, TypedMessage};
+
+    #[derive(JsonModel, Serialize, Deserialize)]
+    #[redis(prefix_key = "ledgers", audit_stream = "ledger-audit")]
+    struct Ledger {
+        id: String,
+        owner: String,
+        balance: u32,
+    }
+
+    let mut conn = conn().await?;
+    let auditors = AuditLog::<Ledger>::new("auditors");
+    auditors.ensure_group_stream(&mut conn).await?;
+
+    let mut ledger = Ledger {
+        id: "".into(),
+        owner: "Jane".into(),
+        balance: 10,
+    };
+    ledger.save(&mut conn).await?;
+    Ledger::delete(&ledger.id, &mut conn).await?;
+
+    let records = auditors
+        .read(Some(10), None, &mut conn)
+        .await?
+        .into_iter()
+        .map(TypedMessage::into_data)
+        .filter(|record| matches!(record, Ok(record) if record.pk == ledger.id))
+        .collect::<RedisResult<Vec<_>>>()?;
+
+    let ops = records.iter().map(|record| record.op).collect::<Vec<_>>();
+    assert_eq!(ops, [AuditOp::Save, AuditOp::Delete]);
+    assert_eq!(records[1].changes.len(), 3);
+
+    Ok(())
+}
+
+#[test]
+async fn cached_model() -> Result {
+    #[derive(JsonModel, Serialize, Deserialize, Debug, PartialEq, Clone)]
+    #[redis(prefix_key = "cached-documents")]
+    struct Document {
+        id: String,
+        title: String,
+    }
+
+    let mut conn = conn().await?;
+    let cache = Document::cache().local(1);
+    let ttl = Duration::from_secs(60);
+    conn.del::<_, ()>("cached-documents:license").await?;
+
+    let loader = || async {
+        RedisResult::Ok(Some(Document {
+            id: "license".into(),
+            title: "MIT".into(),
+        }))
+    };
+    let loaded = cache.get_or_load("license", loader, ttl, &mut conn).await?;
+    assert_eq!(Document::get("license", &mut conn).await?, loaded.unwrap());
+
+    Document::delete("license", &mut conn).await?;
+
+    Ok(())
+}
+
+#[test]
+async fn schema_migrations() -> Result {
+    use redis_om::{JsonRecord, Migrations};
+
+    fn migrations() -> Migrations<JsonRecord> {
+        Migrations::new().register((1, |document: &mut JsonRecord| {
+            let fields = document.as_object_mut().ok_or("expected an object")?;
+            let first = fields.remove("first_name").unwrap_or_default();
+            let last = fields.remove("last_name").unwrap_or_default();
+            let full_name = format!(
+                "{} {}",
+                first.as_str().unwrap_or_default(),
+                last.as_str().unwrap_or_default()
+            );
+            fields.insert("full_name".into(), full_name.into());
+            Ok(())
+        }))
+    }
+
+    #[derive(JsonModel, Serialize, Deserialize)]
+    #[redis(prefix_key = "profiles", schema_version = 2, migrations = "migrations")]
+    struct Profile {
+        id: String,
+        full_name: String,
+    }
+
+    let mut conn = conn().await?;
+    Profile::delete_all(&mut conn).await?;
+
+    for id in ["1", "2"] {
+        let document = format!(r#"{{"id":"{id}","first_name":"Jane","last_name":"Doe"}}"#);
+        redis::cmd("JSON.SET")
+            .arg(format!("profiles:{id}"))
+            .arg("$")
+            .arg(document)
+            .query_async::<_, ()>(&mut conn)
+            .await?;
+    }
+
+    let profile = Profile::get("1", &mut conn).await?;
+    assert_eq!(profile.full_name, "Jane Doe");
+
+    let progress = Profile::migrate_all(0, |_| {}, &mut conn).await?;
+    assert_eq!((progress.scanned, progress.migrated), (2, 1));
+    assert!(progress.failed.is_empty());
+
+    let version: String = redis::cmd("JSON.GET")
+        .arg("profiles:2")
+        .arg("$.__schema_version")
+        .query_async(&mut conn)
+        .await?;
+    assert_eq!(version, "[2]");
+    assert_eq!(Profile::get("2", &mut conn).await?.full_name, "Jane Doe");
+
+    Ok(())
+}
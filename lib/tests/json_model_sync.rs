@@ -1,9 +1,9 @@
 #![cfg(all(not(feature = "aio"), feature = "json"))]
 
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 
 use redis::Commands;
-use redis_om::JsonModel;
+use redis_om::{JsonModel, RedisResult};
 use serde::{Deserialize, Serialize};
 
 type Result<T = (), E = Box<dyn std::error::Error>> = std::result::Result<T, E>;
@@ -175,6 +175,182 @@ fn expiring_keys() -> Result {
     Ok(())
 }
 
+#[test]
+fn find_missing_keys() -> Result {
+    #[derive(JsonModel, Serialize, Deserialize, Debug)]
+    #[redis(prefix_key = "visitors")]
+    struct Visitor {
+        id: String,
+        first_name: String,
+        last_name: String,
+    }
+
+    let mut visitor = Visitor {
+        id: "".into(),
+        first_name: "Joe".into(),
+        last_name: "Doe".into(),
+    };
+
+    let mut conn = client()?.get_connection()?;
+
+    assert!(Visitor::find_by_pk("missing", &mut conn)?.is_none());
+    assert!(!Visitor::exists("missing", &mut conn)?);
+
+    visitor.save(&mut conn)?;
+
+    let db_visitor = Visitor::find_by_pk(&visitor.id, &mut conn)?;
+
+    assert_eq!(db_visitor.map(|v| v.first_name), Some(visitor.first_name));
+    assert!(Visitor::exists(&visitor.id, &mut conn)?);
+
+    Visitor::delete(visitor.id, &mut conn)?;
+
+    Ok(())
+}
+
+#[test]
+fn all_records() -> Result {
+    #[derive(JsonModel, Serialize, Deserialize, Debug)]
+    #[redis(prefix_key = "guests")]
+    struct Guest {
+        id: String,
+        first_name: String,
+        last_name: String,
+    }
+
+    let mut guests = [["Joe", "Doe"], ["Jane", "Doe"], ["John", "Smith"]]
+        .map(|[first, last]| Guest {
+            id: "".into(),
+            first_name: first.into(),
+            last_name: last.into(),
+        })
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    let mut conn = client()?.get_connection()?;
+
+    for guest in guests.iter_mut() {
+        guest.save(&mut conn)?;
+    }
+
+    // Unrelated key sharing the model prefix
+    conn.set::<_, _, ()>("guests:meta", "unrelated")?;
+
+    let db_guests = Guest::scan(2, &mut conn).collect::<RedisResult<Vec<_>>>()?;
+
+    let mut pks = db_guests.iter().map(|g| g.id.clone()).collect::<Vec<_>>();
+    let mut expected = guests.iter().map(|g| g.id.clone()).collect::<Vec<_>>();
+    pks.sort();
+    expected.sort();
+
+    assert_eq!(pks, expected);
+
+    conn.del::<_, ()>("guests:meta")?;
+    for guest in guests.iter() {
+        Guest::delete(&guest.id, &mut conn)?;
+    }
+
+    Ok(())
+}
+
+#[test]
+fn count_and_delete_all() -> Result {
+    #[derive(JsonModel, Serialize, Deserialize, Debug)]
+    #[redis(prefix_key = "tenants")]
+    struct Tenant {
+        id: String,
+        name: String,
+    }
+
+    let mut conn = client()?.get_connection()?;
+
+    for name in ["acme", "globex", "initech"] {
+        let mut tenant = Tenant {
+            id: "".into(),
+            name: name.into(),
+        };
+        tenant.save(&mut conn)?;
+    }
+
+    assert_eq!(Tenant::count(&mut conn)?, 3);
+    assert_eq!(Tenant::delete_all_with(2, true, &mut conn)?, 3);
+    assert_eq!(Tenant::count(&mut conn)?, 3);
+    assert_eq!(Tenant::delete_all(&mut conn)?, 3);
+    assert_eq!(Tenant::count(&mut conn)?, 0);
+
+    Ok(())
+}
+
+#[test]
+fn ttl_policies() -> Result {
+    #[derive(JsonModel, Serialize, Deserialize)]
+    #[redis(prefix_key = "carts", ttl = "1h")]
+    struct Cart {
+        id: String,
+        items: Vec<String>,
+    }
+
+    let mut conn = client()?.get_connection()?;
+    let mut cart = Cart {
+        id: "".into(),
+        items: vec!["book".into()],
+    };
+    cart.save(&mut conn)?;
+
+    let ttl = cart.ttl(&mut conn)?.expect("save to apply ttl");
+    assert!(ttl > Duration::from_secs(59 * 60) && ttl <= Duration::from_secs(60 * 60));
+
+    assert!(cart.persist(&mut conn)?);
+    assert_eq!(cart.ttl(&mut conn)?, None);
+
+    let at = SystemTime::now() + Duration::from_secs(60);
+    cart.expire_at(at, &mut conn)?;
+    assert!(cart.ttl(&mut conn)? <= Some(Duration::from_secs(60)));
+
+    Cart::delete(&cart.id, &mut conn)?;
+    assert_eq!(cart.ttl(&mut conn)?, None);
+
+    Ok(())
+}
+
+#[test]
+fn soft_deletes() -> Result {
+    #[derive(JsonModel, Serialize, Deserialize)]
+    #[redis(prefix_key = "patients", soft_delete)]
+    struct Patient {
+        id: String,
+        name: String,
+    }
+
+    let mut conn = client()?.get_connection()?;
+    let mut patient = Patient {
+        id: "".into(),
+        name: "Jane".into(),
+    };
+    patient.save(&mut conn)?;
+    let live = Patient::count(&mut conn)?;
+    Patient::delete(&patient.id, &mut conn)?;
+    assert_eq!(Patient::count(&mut conn)?, live - 1);
+
+    assert!(Patient::get(&patient.id, &mut conn).is_err());
+    assert!(Patient::find_by_pk(&patient.id, &mut conn)?.is_none());
+    assert!(!Patient::exists(&patient.id, &mut conn)?);
+
+    assert!(Patient::restore(&patient.id, &mut conn)?);
+    assert_eq!(Patient::get(&patient.id, &mut conn)?.name, "Jane");
+
+    Patient::delete(&patient.id, &mut conn)?;
+    assert!(Patient::purge_deleted(Duration::ZERO, &mut conn)? >= 1);
+    assert!(!conn.exists::<_, bool>(format!("patients:{}", patient.id))?);
+
+    patient.save(&mut conn)?;
+    Patient::delete(&patient.id, &mut conn)?;
+    assert!(Patient::delete_all(&mut conn)? >= 1);
+    assert!(Patient::count(&mut conn)? == 0);
+
+    Ok(())
+}
+
 #[test]
 fn test_redis_schema() -> Result {
     #[derive(JsonModel, Serialize, Deserialize, Debug)]
@@ -211,3 +387,137 @@ fn test_redis_schema() -> Result {
 
     Ok(())
 }
+
+#[test]
+fn audit_stream() -> Result {
+    use redis_om::{AuditLog, AuditOp, StreamModel, TypedMessage};
+
+    #[derive(JsonModel, Serialize, Deserialize)]
+    #[redis(prefix_key = "ledgers", audit_stream = "ledger-audit")]
+    struct Ledger {
+        id: String,
+        owner: String,
+        balance: u32,
+    }
+
+    let mut conn = client()?.get_connection()?;
+    let auditors = AuditLog::<Ledger>::new("auditors");
+    auditors.ensure_group_stream(&mut conn)?;
+
+    let mut ledger = Ledger {
+        id: "".into(),
+        owner: "Jane".into(),
+        balance: 10,
+    };
+    ledger.save(&mut conn)?;
+    ledger.owner = "John".into();
+    ledger.save(&mut conn)?;
+    Ledger::delete(&ledger.id, &mut conn)?;
+
+    let records = auditors
+        .read(Some(10), None, &mut conn)?
+        .into_iter()
+        .map(TypedMessage::into_data)
+        .filter(|record| matches!(record, Ok(record) if record.pk == ledger.id))
+        .collect::<RedisResult<Vec<_>>>()?;
+
+    let ops = records.iter().map(|record| record.op).collect::<Vec<_>>();
+    assert_eq!(ops, [AuditOp::Save, AuditOp::Save, AuditOp::Delete]);
+
+    let change = &records[1].changes[..];
+    assert_eq!(change.len(), 1);
+    assert_eq!(change[0].old.as_deref(), Some("\"Jane\""));
+    assert_eq!(change[0].new.as_deref(), Some("\"John\""));
+
+    Ok(())
+}
+
+#[test]
+fn cached_model() -> Result {
+    #[derive(JsonModel, Serialize, Deserialize, Debug, PartialEq, Clone)]
+    #[redis(prefix_key = "cached-documents")]
+    struct Document {
+        id: String,
+        title: String,
+    }
+
+    let mut conn = client()?.get_connection()?;
+    let cache = Document::cache().negative_ttl(None);
+    let ttl = Duration::from_secs(60);
+    conn.del::<_, ()>(&[
+        "cached-documents:readme",
+        "cached-documents:changelog:__miss",
+    ])?;
+
+    let loader = || {
+        RedisResult::Ok(Some(Document {
+            id: "readme".into(),
+            title: "Readme".into(),
+        }))
+    };
+    let loaded = cache.get_or_load("readme", loader, ttl, &mut conn)?;
+    assert_eq!(Document::get("readme", &mut conn)?, loaded.unwrap());
+
+    let miss = cache.get_or_load("changelog", || RedisResult::Ok(None), ttl, &mut conn)?;
+    assert!(miss.is_none());
+    assert!(!conn.exists::<_, bool>("cached-documents:changelog:__miss")?);
+
+    Document::delete("readme", &mut conn)?;
+
+    Ok(())
+}
+
+#[test]
+fn schema_migrations() -> Result {
+    use redis_om::{JsonRecord, Migrations};
+
+    fn migrations() -> Migrations<JsonRecord> {
+        Migrations::new().register((1, |document: &mut JsonRecord| {
+            let fields = document.as_object_mut().ok_or("expected an object")?;
+            let first = fields.remove("first_name").unwrap_or_default();
+            let last = fields.remove("last_name").unwrap_or_default();
+            let full_name = format!(
+                "{} {}",
+                first.as_str().unwrap_or_default(),
+                last.as_str().unwrap_or_default()
+            );
+            fields.insert("full_name".into(), full_name.into());
+            Ok(())
+        }))
+    }
+
+    #[derive(JsonModel, Serialize, Deserialize)]
+    #[redis(prefix_key = "profiles", schema_version = 2, migrations = "migrations")]
+    struct Profile {
+        id: String,
+        full_name: String,
+    }
+
+    let mut conn = client()?.get_connection()?;
+    Profile::delete_all(&mut conn)?;
+
+    for id in ["1", "2"] {
+        let document = format!(r#"{{"id":"{id}","first_name":"Jane","last_name":"Doe"}}"#);
+        redis::cmd("JSON.SET")
+            .arg(format!("profiles:{id}"))
+            .arg("$")
+            .arg(document)
+            .query::<()>(&mut conn)?;
+    }
+
+    let profile = Profile::get("1", &mut conn)?;
+    assert_eq!(profile.full_name, "Jane Doe");
+
+    let progress = Profile::migrate_all(0, |_| {}, &mut conn)?;
+    assert_eq!((progress.scanned, progress.migrated), (2, 1));
+    assert!(progress.failed.is_empty());
+
+    let version: String = redis::cmd("JSON.GET")
+        .arg("profiles:2")
+        .arg("$.__schema_version")
+        .query(&mut conn)?;
+    assert_eq!(version, "[2]");
+    assert_eq!(Profile::get("2", &mut conn)?.full_name, "Jane Doe");
+
+    Ok(())
+}
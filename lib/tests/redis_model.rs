@@ -1,4 +1,4 @@
-use redis_om::{RedisModel, RedisTransportValue};
+use redis_om::{key_slot, Namespace, PkStrategy, RedisModel, RedisTransportValue, ToPk};
 
 type Result<T = (), E = Box<dyn std::error::Error>> = std::result::Result<T, E>;
 
@@ -17,7 +17,7 @@ fn basic_with_no_options() -> Result {
 
     assert_eq!(Account::_prefix_key(), "Account");
     assert_eq!(account.id, "");
-    account._set_pk("1234".to_string());
+    account._set_pk("1234")?;
     assert_eq!(account.id, "1234");
 
     Ok(())
@@ -40,8 +40,289 @@ fn basic_with_custom_prefix_and_pk() -> Result {
 
     assert_eq!(Details::_prefix_key(), "info_details");
     assert_eq!(details.pk, "");
-    details._set_pk("1234".to_string());
+    details._set_pk("1234")?;
     assert_eq!(details.pk, "1234");
 
     Ok(())
 }
+
+#[test]
+fn soft_deleted_keys() -> Result {
+    #[derive(RedisTransportValue, RedisModel)]
+    #[redis(prefix_key = "archives", soft_delete = "move")]
+    struct Archive {
+        id: String,
+    }
+
+    let ns = Namespace::new().app("app");
+    assert_eq!(Archive::_scan_pattern(&ns), "app:archives:*");
+    // Moved records stay out of `count` and `delete_all` scans
+    assert_eq!(
+        Archive::_fmt_deleted_key(&ns, "7"),
+        "app:archives__deleted:7"
+    );
+    assert_eq!(
+        Archive::_deleted_scan_pattern(&ns),
+        "app:archives__deleted:*"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn hash_tagged_keys() -> Result {
+    #[derive(RedisTransportValue, RedisModel)]
+    #[redis(prefix_key = "orders", hash_tag = "acme")]
+    struct Order {
+        id: String,
+    }
+
+    #[derive(RedisTransportValue, RedisModel)]
+    #[redis(prefix_key = "carts")]
+    struct Cart {
+        #[redis(hash_tag)]
+        id: String,
+    }
+
+    let ns = Namespace::new();
+    let tenant = Namespace::new().tenant("t1");
+
+    assert_eq!(Order::_fmt_pk(&ns, "1"), "orders:{acme}:1");
+    assert_eq!(Order::_fmt_pk(&tenant, "1"), "t1:orders:{acme}:1");
+    assert_eq!(Order::_strip_pk(&ns, "orders:{acme}:1"), "1");
+    assert!(Order::_is_pk_fmt(&ns, "orders:{acme}:1"));
+    assert!(!Order::_is_pk_fmt(&ns, "orders:1"));
+
+    assert_eq!(Cart::_fmt_pk(&ns, "1"), "carts:{1}");
+    assert_eq!(Cart::_strip_pk(&ns, "carts:{1}"), "1");
+    assert_eq!(Cart::_fmt_key(&ns, "carts:{1}"), "carts:{1}");
+
+    let (first, second) = (Order::_fmt_pk(&ns, "1"), Order::_fmt_pk(&ns, "2"));
+    assert_eq!(key_slot(&first), key_slot(&second));
+    assert_eq!(key_slot(Cart::_fmt_pk(&ns, "1")), key_slot("lines:{1}"));
+
+    Ok(())
+}
+
+#[test]
+fn cluster_slots() -> Result {
+    assert_eq!(key_slot("foo"), 12182);
+    assert_eq!(key_slot("123456789"), 12739);
+    assert_ne!(key_slot("{}foo"), key_slot("foo"));
+    assert_eq!(key_slot("foo{bar}{zap}"), key_slot("bar"));
+
+    let cluster = Namespace::new().cluster(true);
+    assert!(cluster.ensure_same_slot(&["{a}:1", "{a}:2"]).is_ok());
+    assert!(cluster.ensure_same_slot(&["a:1", "b:2"]).is_err());
+    assert!(Namespace::new().ensure_same_slot(&["a:1", "b:2"]).is_ok());
+
+    Ok(())
+}
+
+#[test]
+fn pk_strategies() -> Result {
+    #[derive(RedisTransportValue, RedisModel)]
+    #[redis(pk_strategy = "counter")]
+    struct Invoice {
+        id: u64,
+    }
+
+    fn next_ticket() -> TicketId {
+        TicketId(42)
+    }
+
+    #[derive(Default, PartialEq, Debug)]
+    struct TicketId(u32);
+
+    impl std::fmt::Display for TicketId {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "T{}", self.0)
+        }
+    }
+
+    impl std::str::FromStr for TicketId {
+        type Err = std::num::ParseIntError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            s.trim_start_matches('T').parse().map(TicketId)
+        }
+    }
+
+    impl redis_om::ToRedisArgs for TicketId {
+        fn write_redis_args<W: ?Sized + redis_om::redis::RedisWrite>(&self, out: &mut W) {
+            out.write_arg_fmt(self)
+        }
+    }
+
+    impl redis_om::FromRedisValue for TicketId {
+        fn from_redis_value(v: &redis_om::redis::Value) -> redis_om::RedisResult<Self> {
+            redis_om::PrimaryKey::from_pk_str(&String::from_redis_value(v)?)
+        }
+    }
+
+    #[derive(RedisTransportValue, RedisModel)]
+    #[redis(pk_strategy = "next_ticket")]
+    struct Ticket {
+        id: TicketId,
+    }
+
+    #[derive(RedisTransportValue, RedisModel)]
+    struct Receipt {
+        id: u32,
+    }
+
+    let ns = Namespace::new();
+
+    let mut invoice = Invoice { id: 0 };
+    assert!(matches!(Invoice::_pk_strategy(), PkStrategy::Counter));
+    assert_eq!(Invoice::_seq_key(&ns), "Invoice:__seq");
+    assert!(invoice._ensure_pk(&ns)?.is_some());
+    invoice._set_pk("7")?;
+    assert_eq!(invoice.id, 7);
+    assert_eq!(invoice._get_redis_key(&ns), "Invoice:7");
+    assert!(invoice._ensure_pk(&ns)?.is_none());
+    assert!(invoice._set_pk("seven").is_err());
+
+    let mut ticket = Ticket {
+        id: TicketId::default(),
+    };
+    assert!(ticket._ensure_pk(&ns)?.is_none());
+    assert_eq!(ticket.id, TicketId(42));
+    assert_eq!(ticket._get_redis_key(&ns), "Ticket:T42");
+
+    let mut receipt = Receipt { id: 0 };
+    assert!(matches!(Receipt::_pk_strategy(), PkStrategy::Counter));
+    assert!(receipt._ensure_pk(&ns)?.is_some());
+    receipt._set_pk("3")?;
+    assert_eq!(receipt.id, 3);
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "uuid")]
+fn uuid_pk_strategies() -> Result {
+    #[derive(RedisTransportValue, RedisModel)]
+    #[redis(pk_strategy = "uuid_v7")]
+    struct Device {
+        #[redis(with = "redis_om::with::uuid")]
+        id: uuid::Uuid,
+    }
+
+    #[derive(RedisTransportValue, RedisModel)]
+    #[redis(pk_strategy = "uuid_v4")]
+    struct Session {
+        id: String,
+    }
+
+    #[derive(RedisTransportValue, RedisModel)]
+    struct Sensor {
+        #[redis(with = "redis_om::with::uuid")]
+        id: uuid::Uuid,
+    }
+
+    let ns = Namespace::new();
+
+    let mut device = Device {
+        id: uuid::Uuid::nil(),
+    };
+    assert!(device._ensure_pk(&ns)?.is_none());
+    assert_eq!(device.id.get_version_num(), 7);
+
+    let mut session = Session { id: "".into() };
+    assert!(session._ensure_pk(&ns)?.is_none());
+    assert_eq!(uuid::Uuid::parse_str(&session.id)?.get_version_num(), 4);
+
+    let mut sensor = Sensor {
+        id: uuid::Uuid::nil(),
+    };
+    assert!(sensor._ensure_pk(&ns)?.is_none());
+    assert_eq!(sensor.id.get_version_num(), 7);
+
+    Ok(())
+}
+
+#[test]
+fn composite_primary_keys() -> Result {
+    #[derive(RedisTransportValue, RedisModel)]
+    #[redis(prefix_key = "members")]
+    struct Member {
+        #[redis(primary_key)]
+        tenant_id: String,
+        #[redis(primary_key)]
+        user_id: u64,
+        role: String,
+    }
+
+    let ns = Namespace::new();
+    let mut member = Member {
+        tenant_id: "".into(),
+        user_id: 0,
+        role: "admin".into(),
+    };
+
+    assert!(member._ensure_pk(&ns).is_err());
+
+    member._set_pk("acme:7")?;
+    assert_eq!((member.tenant_id.as_str(), member.user_id), ("acme", 7));
+    assert_eq!(member._get_redis_key(&ns), "members:acme:7");
+    assert_eq!(member._get_pk(), ("acme", 7).to_pk());
+    assert!(member._ensure_pk(&ns)?.is_none());
+
+    assert!(member._set_pk("acme").is_err());
+    assert!(member._set_pk("acme:seven").is_err());
+    assert!(member._set_pk("acme:7:7").is_err());
+
+    // Parts containing the separator would read back as other parts
+    member.tenant_id = "acme:eu".into();
+    assert!(member._ensure_pk(&ns).is_err());
+    member.tenant_id = "acme-eu".into();
+    member._ensure_pk(&ns)?;
+    let pk = member._get_pk();
+    member._set_pk(&pk)?;
+    assert_eq!((member.tenant_id.as_str(), member.user_id), ("acme-eu", 7));
+
+    Ok(())
+}
+
+#[test]
+fn schema_version_migrations() -> Result {
+    use redis_om::{HashRecord, Migrations};
+
+    #[derive(RedisTransportValue, RedisModel)]
+    #[redis(schema_version = 3)]
+    struct Profile {
+        id: String,
+        full_name: String,
+    }
+
+    assert_eq!(Profile::_schema_version(), Some(3));
+
+    let migrations = Migrations::<HashRecord>::new()
+        .register((1, |record: &mut HashRecord| {
+            let first = record.remove("first_name").unwrap_or_default();
+            let last = record.remove("last_name").unwrap_or_default();
+            record.insert("full_name".into(), format!("{first} {last}"));
+            Ok(())
+        }))
+        .register((2, |record: &mut HashRecord| {
+            let name = record.get_mut("full_name").ok_or("missing full_name")?;
+            *name = name.trim().to_owned();
+            Ok(())
+        }));
+
+    let mut record = HashRecord::from([
+        ("first_name".to_owned(), "Jane".to_owned()),
+        ("last_name".to_owned(), "".to_owned()),
+    ]);
+    migrations.upgrade(&mut record, 1, 3)?;
+    assert_eq!(
+        record,
+        HashRecord::from([("full_name".into(), "Jane".into())])
+    );
+
+    assert!(migrations.upgrade(&mut record, 3, 4).is_err());
+    assert!(migrations.upgrade(&mut HashRecord::new(), 2, 3).is_err());
+
+    Ok(())
+}
@@ -162,3 +162,422 @@ fn enum_struct_compo() -> Result {
 
     Ok(())
 }
+
+fn roundtrip<T: ToRedisArgs + FromRedisValue>(value: &T) -> Result<(HashMap<String, String>, T)> {
+    let args = value.to_redis_args();
+    let fields = args
+        .chunks(2)
+        .map(|kv| {
+            let key = String::from_utf8(kv[0].clone())?;
+            let value = String::from_utf8(kv[1].clone())?;
+            Ok((key, value))
+        })
+        .collect::<Result<HashMap<_, _>>>()?;
+    let bulk = args.into_iter().map(Value::Data).collect::<Vec<_>>();
+
+    Ok((fields, T::from_redis_value(&Value::Bulk(bulk))?))
+}
+
+#[test]
+fn struct_with_std_adapters() -> Result {
+    use std::net::IpAddr;
+    use std::time::Duration;
+
+    #[derive(RedisTransportValue, Debug, PartialEq)]
+    struct Session {
+        id: String,
+        #[redis(with = "redis_om::with::duration_secs")]
+        ttl: Duration,
+        #[redis(with = "redis_om::with::duration_millis")]
+        latency: Option<Duration>,
+        #[redis(with = "redis_om::with::ip_addr")]
+        ip: IpAddr,
+        #[redis(with = "redis_om::with::bool_num")]
+        active: bool,
+        #[redis(with = "redis_om::with::bool_str")]
+        verified: Option<bool>,
+    }
+
+    let session = Session {
+        id: "1".into(),
+        ttl: Duration::from_secs(60),
+        latency: Some(Duration::from_millis(250)),
+        ip: "127.0.0.1".parse()?,
+        active: true,
+        verified: Some(false),
+    };
+
+    let (fields, deserialized) = roundtrip(&session)?;
+    assert_eq!(fields["ttl"], "60");
+    assert_eq!(fields["latency"], "250");
+    assert_eq!(fields["ip"], "127.0.0.1");
+    assert_eq!(fields["active"], "1");
+    assert_eq!(fields["verified"], "false");
+    assert_eq!(deserialized, session);
+
+    let session = Session {
+        latency: None,
+        verified: None,
+        ..session
+    };
+
+    let (fields, deserialized) = roundtrip(&session)?;
+    assert!(!fields.contains_key("latency"));
+    assert!(!fields.contains_key("verified"));
+    assert_eq!(deserialized, session);
+
+    Ok(())
+}
+
+#[test]
+fn adapters_reject_invalid_values() {
+    #[derive(RedisTransportValue, Debug)]
+    struct Flag {
+        #[redis(with = "redis_om::with::bool_num")]
+        enabled: bool,
+    }
+
+    let bulk = vec![
+        Value::Data(b"enabled".to_vec()),
+        Value::Data(b"yes".to_vec()),
+    ];
+    assert!(Flag::from_redis_value(&Value::Bulk(bulk)).is_err());
+}
+
+#[test]
+#[cfg(all(
+    feature = "chrono",
+    feature = "time",
+    feature = "uuid",
+    feature = "rust_decimal",
+    feature = "url"
+))]
+fn struct_with_feature_adapters() -> Result {
+    #[derive(RedisTransportValue, Debug, PartialEq)]
+    struct Order {
+        #[redis(with = "redis_om::with::uuid")]
+        id: uuid::Uuid,
+        #[redis(with = "redis_om::with::chrono_iso")]
+        created_at: chrono::DateTime<chrono::Utc>,
+        #[redis(with = "redis_om::with::chrono_epoch")]
+        updated_at: Option<chrono::DateTime<chrono::Utc>>,
+        #[redis(with = "redis_om::with::time_iso")]
+        shipped_at: time::OffsetDateTime,
+        #[redis(with = "redis_om::with::time_epoch")]
+        delivered_at: time::OffsetDateTime,
+        #[redis(with = "redis_om::with::decimal")]
+        total: rust_decimal::Decimal,
+        #[redis(with = "redis_om::with::url")]
+        receipt: url::Url,
+    }
+
+    let order = Order {
+        id: "67e55044-10b1-426f-9247-bb680e5fe0c8".parse()?,
+        created_at: "2023-02-01T10:00:00Z".parse()?,
+        updated_at: Some("2023-02-02T10:00:00Z".parse()?),
+        shipped_at: time::OffsetDateTime::from_unix_timestamp(1675418400)?,
+        delivered_at: time::OffsetDateTime::from_unix_timestamp(1675504800)?,
+        total: "19.99".parse()?,
+        receipt: "https://example.com/receipts/1".parse()?,
+    };
+
+    let (fields, deserialized) = roundtrip(&order)?;
+    assert_eq!(fields["id"], "67e55044-10b1-426f-9247-bb680e5fe0c8");
+    assert_eq!(fields["created_at"], "2023-02-01T10:00:00+00:00");
+    assert_eq!(fields["updated_at"], "1675332000");
+    assert_eq!(fields["shipped_at"], "+002023-02-03T10:00:00.000000000Z");
+    assert_eq!(fields["delivered_at"], "1675504800");
+    assert_eq!(fields["total"], "19.99");
+    assert_eq!(fields["receipt"], "https://example.com/receipts/1");
+    assert_eq!(deserialized, order);
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "time")]
+fn time_iso_extended_values() -> Result {
+    use redis_om::with::time_iso;
+
+    let roundtrip = |value: time::OffsetDateTime| -> Result<_> {
+        let args = time_iso::to_redis_args(&value);
+        let encoded = String::from_utf8(args[0].clone())?;
+        let decoded: time::OffsetDateTime =
+            time_iso::from_redis_value(&Value::Data(encoded.clone().into_bytes()))?;
+        Ok((encoded, decoded))
+    };
+
+    let date = time::Date::from_calendar_date(-1, time::Month::January, 1)?;
+    let before_zero = date.midnight().assume_utc();
+    let (encoded, decoded) = roundtrip(before_zero)?;
+    assert_eq!(encoded, "-000001-01-01T00:00:00.000000000Z");
+    assert_eq!(decoded, before_zero);
+
+    let offset = time::UtcOffset::from_hms(1, 2, 3)?;
+    let with_seconds = time::OffsetDateTime::from_unix_timestamp(1675418400)?.to_offset(offset);
+    let (encoded, decoded) = roundtrip(with_seconds)?;
+    assert_eq!(encoded, "+002023-02-03T10:00:00.000000000Z");
+    assert_eq!(decoded, with_seconds);
+
+    Ok(())
+}
+
+#[test]
+fn struct_with_map_fields() -> Result {
+    use std::collections::BTreeMap;
+
+    #[derive(RedisTransportValue, Debug, PartialEq)]
+    struct Settings {
+        id: String,
+        flags: HashMap<String, String>,
+        limits: BTreeMap<String, u32>,
+        groups: HashMap<String, Vec<String>>,
+        empty: BTreeMap<String, u32>,
+    }
+
+    let settings = Settings {
+        id: "1".into(),
+        flags: HashMap::from([
+            ("ui.theme".to_string(), "dark".to_string()),
+            ("path\\sep".to_string(), "/".to_string()),
+        ]),
+        limits: BTreeMap::from([("requests".to_string(), 100), ("users".to_string(), 5)]),
+        groups: HashMap::from([(
+            "admins".to_string(),
+            vec!["jane".to_string(), "joe".to_string()],
+        )]),
+        empty: BTreeMap::new(),
+    };
+
+    let (fields, deserialized) = roundtrip(&settings)?;
+    assert_eq!(fields["flags.ui\\.theme"], "dark");
+    assert_eq!(fields["flags.path\\\\sep"], "/");
+    assert_eq!(fields["limits.requests"], "100");
+    assert_eq!(fields["groups.admins.1"], "joe");
+    assert!(!fields.keys().any(|k| k.starts_with("empty")));
+    assert_eq!(deserialized, settings);
+
+    Ok(())
+}
+
+#[test]
+fn audit_record() -> Result {
+    use redis_om::{AuditChange, AuditOp, AuditRecord};
+
+    let record = AuditRecord {
+        model: "Ledger".into(),
+        pk: "01H".into(),
+        op: AuditOp::Save,
+        changes: vec![
+            AuditChange {
+                field: "balance".into(),
+                old: Some("10".into()),
+                new: Some("20".into()),
+            },
+            AuditChange {
+                field: "owner.name".into(),
+                old: None,
+                new: Some("Jane".into()),
+            },
+        ],
+        timestamp: 1_700_000_000_000,
+    };
+
+    let (fields, deserialized) = roundtrip(&record)?;
+    assert_eq!(fields["op"], "save");
+    assert_eq!(fields["old.balance"], "10");
+    assert_eq!(fields["new.owner.name"], "Jane");
+    assert!(!fields.contains_key("old.owner.name"));
+    assert_eq!(deserialized, record);
+
+    Ok(())
+}
+
+#[test]
+fn stream_introspection_replies() -> Result {
+    use redis_om::{ConsumerInfo, GroupInfo, PendingEntry, PendingSummary, StreamInfo};
+    use std::time::Duration;
+
+    let data = |s: &str| Value::Data(s.as_bytes().to_vec());
+    let bulk = Value::Bulk;
+
+    let summary = PendingSummary::from_redis_value(&bulk(vec![
+        Value::Int(3),
+        data("1-0"),
+        data("3-0"),
+        bulk(vec![
+            bulk(vec![data("alice"), data("2")]),
+            bulk(vec![data("bob"), data("1")]),
+        ]),
+    ]))?;
+    assert_eq!(summary.count, 3);
+    assert_eq!(summary.min_id.as_deref(), Some("1-0"));
+    assert_eq!(summary.consumers, [("alice".into(), 2), ("bob".into(), 1)]);
+
+    let empty = [Value::Int(0), Value::Nil, Value::Nil, Value::Nil];
+    assert_eq!(
+        PendingSummary::from_redis_value(&bulk(empty.to_vec()))?,
+        PendingSummary::default()
+    );
+
+    let entries: Vec<PendingEntry> = from_redis_value(&bulk(vec![bulk(vec![
+        data("1-0"),
+        data("alice"),
+        Value::Int(1500),
+        Value::Int(2),
+    ])]))?;
+    assert_eq!(
+        entries,
+        [PendingEntry {
+            id: "1-0".into(),
+            consumer: "alice".into(),
+            idle: Duration::from_millis(1500),
+            deliveries: 2
+        }]
+    );
+
+    let info = StreamInfo::from_redis_value(&bulk(vec![
+        data("length"),
+        Value::Int(2),
+        data("radix-tree-keys"),
+        Value::Int(1),
+        data("radix-tree-nodes"),
+        Value::Int(2),
+        data("last-generated-id"),
+        data("2-0"),
+        data("groups"),
+        Value::Int(1),
+        data("first-entry"),
+        bulk(vec![data("1-0"), bulk(vec![data("room"), data("3")])]),
+        data("last-entry"),
+        Value::Nil,
+    ]))?;
+    assert_eq!((info.length, info.groups), (2, 1));
+    assert_eq!(info.first_entry_id.as_deref(), Some("1-0"));
+    assert_eq!(info.last_entry_id, None);
+
+    let groups: Vec<GroupInfo> = from_redis_value(&bulk(vec![bulk(vec![
+        data("name"),
+        data("Staff"),
+        data("consumers"),
+        Value::Int(2),
+        data("pending"),
+        Value::Int(1),
+        data("last-delivered-id"),
+        data("2-0"),
+        data("entries-read"),
+        Value::Nil,
+        data("lag"),
+        Value::Int(0),
+    ])]))?;
+    assert_eq!(groups[0].name, "Staff");
+    assert_eq!((groups[0].entries_read, groups[0].lag), (None, Some(0)));
+
+    let consumers: Vec<ConsumerInfo> = from_redis_value(&bulk(vec![bulk(vec![
+        data("name"),
+        data("alice"),
+        data("pending"),
+        Value::Int(1),
+        data("idle"),
+        Value::Int(20),
+        data("inactive"),
+        Value::Int(-1),
+    ])]))?;
+    assert_eq!(consumers[0].idle, Duration::from_millis(20));
+    assert_eq!(consumers[0].inactive, None);
+
+    Ok(())
+}
+
+#[test]
+fn publish_options_args() {
+    use redis_om::redis::streams::StreamMaxlen;
+    use redis_om::PublishOptions;
+
+    let args = |options: PublishOptions| -> Vec<String> {
+        options
+            .to_redis_args()
+            .into_iter()
+            .map(|arg| String::from_utf8(arg).unwrap())
+            .collect()
+    };
+
+    assert_eq!(args(PublishOptions::new()), ["*"]);
+    assert_eq!(
+        args(
+            PublishOptions::new()
+                .maxlen(StreamMaxlen::Approx(10))
+                .id("5-1")
+        ),
+        ["MAXLEN", "~", "10", "5-1"]
+    );
+    assert_eq!(
+        args(
+            PublishOptions::new()
+                .maxlen(StreamMaxlen::Equals(10))
+                .approx_minid("3-0")
+                .nomkstream()
+        ),
+        ["NOMKSTREAM", "MINID", "~", "3-0", "*"]
+    );
+}
+
+#[test]
+fn stream_range_args() {
+    use redis_om::{StreamEntryId, StreamRange};
+
+    let args = |range: StreamRange| -> Vec<String> {
+        range
+            .to_redis_args()
+            .into_iter()
+            .map(|arg| String::from_utf8(arg).unwrap())
+            .collect()
+    };
+
+    let (one, nine) = (StreamEntryId::new(1, 0), StreamEntryId::new(9, 0));
+    assert_eq!(args(StreamRange::range()), ["-", "+"]);
+    assert_eq!(
+        args(StreamRange::range().after(one).max(nine).count(5)),
+        ["(1-0", "9-0", "COUNT", "5"]
+    );
+    assert_eq!(
+        args(StreamRange::rev_range().min(one).before(nine)),
+        ["(9-0", "1-0"]
+    );
+}
+
+#[test]
+fn stream_entry_id() {
+    use redis_om::StreamEntryId;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let id: StreamEntryId = "1526919030474-55".parse().unwrap();
+    assert_eq!(id, StreamEntryId::new(1526919030474, 55));
+    assert_eq!(id.to_string(), "1526919030474-55");
+    assert_eq!(
+        id.to_system_time(),
+        UNIX_EPOCH + Duration::from_millis(1526919030474)
+    );
+    assert_eq!(
+        "5".parse::<StreamEntryId>().unwrap(),
+        StreamEntryId::new(5, 0)
+    );
+    assert!("5-".parse::<StreamEntryId>().is_err());
+    assert!("-".parse::<StreamEntryId>().is_err());
+
+    let mut ids = ["2-0", "1-10", "1-2", "0-0"].map(|id| id.parse().unwrap());
+    ids.sort();
+    assert_eq!(
+        ids.map(|id: StreamEntryId| id.to_string()),
+        ["0-0", "1-2", "1-10", "2-0"]
+    );
+    assert_eq!(StreamEntryId::MIN, StreamEntryId::default());
+    assert!(StreamEntryId::MAX > id);
+
+    let value = Value::Data(b"7-1".to_vec());
+    assert_eq!(
+        StreamEntryId::from_redis_value(&value).unwrap(),
+        StreamEntryId::new(7, 1)
+    );
+    assert_eq!(id.to_redis_args(), [b"1526919030474-55".to_vec()]);
+}
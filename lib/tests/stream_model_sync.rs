@@ -1,10 +1,15 @@
 #![cfg(not(feature = "aio"))]
 
-use redis_om::{RedisTransportValue, StreamModel};
+use redis_om::{RedisResult, RedisTransportValue, StreamModel, TypedMessage};
 use std::error::Error;
+use std::time::Duration;
 
 type Result<T = (), E = Box<dyn Error>> = std::result::Result<T, E>;
 
+fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+}
+
 #[derive(RedisTransportValue)]
 pub enum RoomServiceKind {
     Clean,
@@ -42,9 +47,559 @@ fn example() -> Result {
     RoomServiceEventManager::publish(&event, &mut conn)?;
 
     let read = manager.read(None, None, &mut conn)?;
-    let message = read.first().unwrap().data::<RoomServiceEvent>()?;
+    let message = read.first().unwrap().data()?;
 
     assert_eq!(message.room, event.room);
 
     Ok(())
 }
+
+#[test]
+fn typed_messages() -> Result {
+    #[derive(StreamModel)]
+    #[redis(key = "test-typed-events")]
+    pub struct Delivery {
+        room: usize,
+    }
+
+    let mut conn = client()?.get_connection()?;
+    let manager = DeliveryManager::new("Staff");
+    manager.ensure_group_stream(&mut conn)?;
+
+    DeliveryManager::publish(&Delivery { room: 1 }, &mut conn)?;
+    redis::cmd("XADD")
+        .arg("test-typed-events")
+        .arg("*")
+        .arg("room")
+        .arg("unknown")
+        .query::<String>(&mut conn)?;
+    DeliveryManager::publish(&Delivery { room: 2 }, &mut conn)?;
+
+    let read = manager
+        .read(None, None, &mut conn)?
+        .into_iter()
+        .map(TypedMessage::decode)
+        .collect::<Vec<_>>();
+    assert_eq!(read.len(), 3);
+
+    let valid = read.iter().filter(|message| message.is_valid());
+    let rooms = valid.map(|message| message.data().map(|delivery| delivery.room));
+    assert_eq!(rooms.collect::<RedisResult<Vec<_>>>()?, [1, 2]);
+    assert!(read[1].data().is_err());
+
+    for message in &read {
+        message.ack::<DeliveryManager, _>(&mut conn)?;
+    }
+
+    Ok(())
+}
+
+#[test]
+fn stream_consumer() -> Result {
+    use redis_om::{ConsumerStats, RetryPolicy, StreamConsumer};
+
+    #[derive(StreamModel)]
+    #[redis(key = "test-consumer-events")]
+    pub struct Order {
+        room: usize,
+    }
+
+    let mut conn = client()?.get_connection()?;
+    let consumer = StreamConsumer::new(OrderManager::new("Kitchen"))
+        .block(ms(100))
+        .retry_policy(RetryPolicy::new().max_retries(1).backoff(ms(1), ms(1)))
+        .reclaim(Duration::from_secs(60), Duration::from_secs(60));
+    consumer.manager().ensure_group_stream(&mut conn)?;
+
+    for room in 1..=3 {
+        OrderManager::publish(&Order { room }, &mut conn)?;
+    }
+
+    let token = consumer.token();
+    let mut attempts = Vec::new();
+    let stats = consumer.run(
+        |message| {
+            let room = message.data()?.room;
+            attempts.push(room);
+            if room == 3 {
+                token.cancel();
+            }
+            match room {
+                2 => Err("room unavailable".into()),
+                _ => Ok::<_, Box<dyn Error>>(()),
+            }
+        },
+        &mut conn,
+    )?;
+
+    assert_eq!(attempts, [1, 2, 2, 3]);
+    assert_eq!(
+        stats,
+        ConsumerStats {
+            handled: 2,
+            failed: 1,
+            dead_lettered: 0,
+            claimed: 0,
+            recovered: 0
+        }
+    );
+
+    Ok(())
+}
+
+#[test]
+fn retry_policy_backoff() {
+    use redis_om::RetryPolicy;
+
+    let policy = RetryPolicy::new().backoff(ms(100), ms(500));
+    let delays = (1..=5).map(|retry| policy.delay(retry));
+    assert_eq!(
+        delays.collect::<Vec<_>>(),
+        [ms(100), ms(200), ms(400), ms(500), ms(500)]
+    );
+    assert_eq!(policy.delay(usize::MAX), ms(500));
+}
+
+#[test]
+fn dead_letter_stream() -> Result {
+    use redis_om::{RetryPolicy, StreamConsumer};
+
+    #[derive(StreamModel)]
+    #[redis(key = "test-dlq-events")]
+    pub struct Payment {
+        amount: usize,
+    }
+
+    let mut conn = client()?.get_connection()?;
+    let consumer = StreamConsumer::new(PaymentManager::new("Billing"))
+        .block(ms(100))
+        .retry_policy(
+            RetryPolicy::new()
+                .max_retries(1)
+                .backoff(ms(1), ms(1))
+                .dead_letter_after(1),
+        );
+    consumer.manager().ensure_group_stream(&mut conn)?;
+
+    let id = PaymentManager::publish(&Payment { amount: 42 }, &mut conn)?;
+
+    let token = consumer.token();
+    let stats = consumer.run(
+        |_| {
+            token.cancel();
+            Err("card declined")
+        },
+        &mut conn,
+    )?;
+    assert_eq!(stats.dead_lettered, 1);
+    assert_eq!(
+        PaymentManager::delivery_count("Billing", id.parse()?, &mut conn)?,
+        None
+    );
+
+    let letters = PaymentManager::dead_letters("-", "+", None, &mut conn)?;
+    let letter = letters
+        .into_iter()
+        .map(TypedMessage::into_data)
+        .collect::<RedisResult<Vec<_>>>()?
+        .into_iter()
+        .find(|letter| letter.id.to_string() == id)
+        .ok_or("missing dead letter")?;
+
+    assert_eq!(
+        (letter.group.as_str(), letter.error.as_str()),
+        ("Billing", "card declined")
+    );
+    assert_eq!(letter.deliveries, 1);
+    assert_eq!(letter.data.amount, 42);
+
+    Ok(())
+}
+
+#[test]
+fn pending_claim_and_info() -> Result {
+    use redis_om::{ClaimOptions, PendingQuery};
+
+    #[derive(StreamModel)]
+    #[redis(key = "test-introspection-events")]
+    pub struct Shipment {
+        parcel: usize,
+    }
+
+    let mut conn = client()?.get_connection()?;
+    let alice = ShipmentManager::new_with_consumer_name("Shipping", "alice");
+    ShipmentManager::destroy_group("Shipping", &mut conn)?;
+    alice.ensure_group_stream(&mut conn)?;
+
+    let id = ShipmentManager::publish(&Shipment { parcel: 7 }, &mut conn)?;
+    assert_eq!(alice.read(None, None, &mut conn)?.len(), 1);
+
+    let summary = ShipmentManager::pending_summary("Shipping", &mut conn)?;
+    assert_eq!(summary.count, 1);
+    assert_eq!(summary.consumers, [("alice".to_owned(), 1)]);
+
+    let query = PendingQuery::new().consumer("alice");
+    let pending = ShipmentManager::pending("Shipping", &query, &mut conn)?;
+    assert_eq!(
+        (pending[0].id.as_str(), pending[0].deliveries),
+        (id.as_str(), 1)
+    );
+
+    let options = ClaimOptions::new().retry_count(5);
+    let claimed = ShipmentManager::claim("Shipping", "bob", ms(0), &[&id], &options, &mut conn)?;
+    assert_eq!(claimed[0].data()?.parcel, 7);
+    assert_eq!(claimed[0].id.to_string(), id);
+    assert_eq!(claimed[0].deliveries, Some(5));
+    assert!(claimed[0].idle.is_some());
+    assert_eq!(
+        ShipmentManager::delivery_count("Shipping", id.parse()?, &mut conn)?,
+        Some(5)
+    );
+
+    let ids = ShipmentManager::claim_ids(
+        "Shipping",
+        "alice",
+        ms(0),
+        &[&id],
+        &ClaimOptions::new(),
+        &mut conn,
+    )?;
+    assert_eq!(ids, [id.as_str()]);
+
+    let info = ShipmentManager::info(&mut conn)?;
+    assert_eq!(info.last_entry_id, Some(id.clone()));
+    let groups = ShipmentManager::groups(&mut conn)?;
+    assert!(groups
+        .iter()
+        .any(|group| group.name == "Shipping" && group.pending == 1));
+    let consumers = ShipmentManager::consumers("Shipping", &mut conn)?;
+    assert_eq!(consumers.len(), 2);
+
+    assert_eq!(
+        ShipmentManager::delete_consumer("Shipping", "alice", &mut conn)?,
+        1
+    );
+    ShipmentManager::set_group_id("Shipping", "0", &mut conn)?;
+    assert_eq!(alice.read(None, None, &mut conn)?.len(), info.length);
+    assert!(ShipmentManager::destroy_group("Shipping", &mut conn)?);
+
+    Ok(())
+}
+
+#[test]
+fn multi_stream_manager() -> Result {
+    use redis_om::MultiStreamModel;
+
+    #[derive(StreamModel)]
+    #[redis(key = "test-multi-orders")]
+    pub struct Order {
+        item: String,
+    }
+
+    #[derive(StreamModel)]
+    #[redis(key = "test-multi-payments")]
+    pub struct Payment {
+        amount: usize,
+    }
+
+    #[derive(MultiStreamModel)]
+    pub enum Checkout {
+        Order(Order),
+        #[redis(manager = "PaymentManager")]
+        Payment(Payment),
+    }
+
+    let mut conn = client()?.get_connection()?;
+    let manager = CheckoutManager::new("Billing");
+    manager.ensure_group_streams(&mut conn)?;
+
+    OrderManager::publish(
+        &Order {
+            item: "towel".into(),
+        },
+        &mut conn,
+    )?;
+    PaymentManager::publish(&Payment { amount: 12 }, &mut conn)?;
+
+    let read = manager.read(None, None, &mut conn)?;
+    assert_eq!(read.len(), 2);
+
+    for message in &read {
+        match message.data()? {
+            Checkout::Order(order) => {
+                assert_eq!(order.item, "towel");
+                assert_eq!(message.stream(), Some("test-multi-orders"));
+            }
+            Checkout::Payment(payment) => {
+                assert_eq!(payment.amount, 12);
+                assert_eq!(message.stream(), Some("test-multi-payments"));
+            }
+        }
+        manager.ack(message, &mut conn)?;
+    }
+
+    assert_eq!(
+        OrderManager::pending_summary("Billing", &mut conn)?.count,
+        0
+    );
+    assert_eq!(
+        PaymentManager::pending_summary("Billing", &mut conn)?.count,
+        0
+    );
+
+    Ok(())
+}
+
+#[test]
+fn multi_stream_manager_from() -> Result {
+    use redis_om::{MultiStreamModel, StartId};
+
+    #[derive(StreamModel)]
+    #[redis(key = "test-multi-replay-orders")]
+    pub struct Order {
+        item: String,
+    }
+
+    #[derive(StreamModel)]
+    #[redis(key = "test-multi-replay-payments")]
+    pub struct Payment {
+        amount: usize,
+    }
+
+    #[derive(MultiStreamModel)]
+    pub enum Checkout {
+        Order(Order),
+        Payment(Payment),
+    }
+
+    let mut conn = client()?.get_connection()?;
+    redis::cmd("DEL")
+        .arg("test-multi-replay-orders")
+        .arg("test-multi-replay-payments")
+        .query::<()>(&mut conn)?;
+    OrderManager::publish(
+        &Order {
+            item: "towel".into(),
+        },
+        &mut conn,
+    )?;
+    PaymentManager::publish(&Payment { amount: 12 }, &mut conn)?;
+
+    let live = CheckoutManager::new("Live");
+    live.ensure_group_streams(&mut conn)?;
+    assert!(live.read(None, None, &mut conn)?.is_empty());
+
+    let replay = CheckoutManager::new("Replay");
+    replay.ensure_group_streams_from(StartId::Beginning, Some(0), &mut conn)?;
+    let read = replay.read(None, None, &mut conn)?;
+    assert_eq!(read.len(), 2);
+
+    for message in &read {
+        match message.data()? {
+            Checkout::Order(order) => assert_eq!(order.item, "towel"),
+            Checkout::Payment(payment) => assert_eq!(payment.amount, 12),
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn publish_with_options() -> Result {
+    use redis_om::{redis::streams::StreamMaxlen, PublishOptions};
+
+    #[derive(StreamModel)]
+    #[redis(key = "test-capped-events", maxlen = 2)]
+    pub struct Reading {
+        value: usize,
+    }
+
+    let mut conn = client()?.get_connection()?;
+    redis::cmd("DEL")
+        .arg("test-capped-events")
+        .query::<()>(&mut conn)?;
+
+    let options = PublishOptions::new().nomkstream();
+    assert_eq!(
+        ReadingManager::publish_with(&Reading { value: 0 }, options, &mut conn)?,
+        None
+    );
+
+    let options = PublishOptions::new().id("1-1");
+    let id = ReadingManager::publish_with(&Reading { value: 1 }, options, &mut conn)?;
+    assert_eq!(id.as_deref(), Some("1-1"));
+
+    for value in 2..=4 {
+        let options = PublishOptions::new().maxlen(StreamMaxlen::Equals(2));
+        ReadingManager::publish_with(&Reading { value }, options, &mut conn)?;
+    }
+    assert_eq!(ReadingManager::len(&mut conn)?, 2);
+
+    Ok(())
+}
+
+#[test]
+fn group_start_and_consumer_cleanup() -> Result {
+    use redis_om::StartId;
+
+    #[derive(StreamModel)]
+    #[redis(key = "test-replay-events")]
+    pub struct Checkin {
+        room: usize,
+    }
+
+    let mut conn = client()?.get_connection()?;
+    redis::cmd("DEL")
+        .arg("test-replay-events")
+        .query::<()>(&mut conn)?;
+    let first = CheckinManager::publish(&Checkin { room: 1 }, &mut conn)?;
+    CheckinManager::publish(&Checkin { room: 2 }, &mut conn)?;
+
+    let live = CheckinManager::new("Live");
+    live.ensure_group_stream_from(StartId::Latest, None, &mut conn)?;
+    assert!(live.read(None, None, &mut conn)?.is_empty());
+
+    let replay = CheckinManager::new("Replay");
+    replay.ensure_group_stream_from(StartId::Beginning, Some(0), &mut conn)?;
+    let read = replay.read(None, None, &mut conn)?;
+    assert_eq!(read.len(), 2);
+
+    let resume = CheckinManager::new_with_consumer_name("Resume", "alice");
+    resume.ensure_group_stream_from(StartId::Id(first), None, &mut conn)?;
+    let read = resume.read(None, None, &mut conn)?;
+    assert_eq!(read[0].data()?.room, 2);
+
+    for message in &read {
+        message.ack::<CheckinManager, _>(&mut conn)?;
+    }
+    assert!(CheckinManager::cleanup_consumers("Resume", ms(0), &mut conn)?.is_empty());
+    assert_eq!(
+        CheckinManager::cleanup_consumers("Replay", ms(0), &mut conn)?,
+        Vec::<String>::new()
+    );
+    assert_eq!(
+        CheckinManager::cleanup_consumers("Live", ms(0), &mut conn)?,
+        [live.consumer_name()]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn recover_pending_messages() -> Result {
+    use redis_om::{ConsumerStats, StreamConsumer, StreamEntryId};
+
+    #[derive(StreamModel)]
+    #[redis(key = "test-recovery-events")]
+    pub struct Ticket {
+        table: usize,
+    }
+
+    let mut conn = client()?.get_connection()?;
+    redis::cmd("DEL")
+        .arg("test-recovery-events")
+        .query::<()>(&mut conn)?;
+    let manager = TicketManager::new_with_consumer_name("Cooks", "cook-1");
+    manager.ensure_group_stream(&mut conn)?;
+
+    let first = TicketManager::publish(&Ticket { table: 1 }, &mut conn)?;
+    TicketManager::publish(&Ticket { table: 2 }, &mut conn)?;
+    // Delivered but never acknowledged, as if the consumer crashed
+    assert_eq!(manager.read(None, None, &mut conn)?.len(), 2);
+    assert!(manager.read(None, None, &mut conn)?.is_empty());
+
+    assert_eq!(
+        manager
+            .read_pending(StreamEntryId::MIN, None, &mut conn)?
+            .len(),
+        2
+    );
+    let after_first = manager.read_pending(first.parse()?, Some(10), &mut conn)?;
+    assert_eq!(after_first[0].data()?.table, 2);
+    // Delivered by read and both read_pending
+    assert_eq!(after_first[0].deliveries, Some(3));
+
+    let consumer = StreamConsumer::new(manager)
+        .block(ms(100))
+        .recover_pending();
+    let token = consumer.token();
+    let mut tables = Vec::new();
+    let stats = consumer.run(
+        |message| {
+            tables.push(message.data()?.table);
+            if tables.len() == 2 {
+                token.cancel();
+            }
+            Ok::<_, Box<dyn Error>>(())
+        },
+        &mut conn,
+    )?;
+
+    assert_eq!(tables, [1, 2]);
+    assert_eq!(
+        stats,
+        ConsumerStats {
+            handled: 2,
+            recovered: 2,
+            ..ConsumerStats::default()
+        }
+    );
+    assert_eq!(TicketManager::pending_summary("Cooks", &mut conn)?.count, 0);
+
+    Ok(())
+}
+
+#[test]
+fn ranges_and_pagination() -> Result {
+    use redis_om::{StreamEntryId, StreamRange};
+
+    #[derive(StreamModel)]
+    #[redis(key = "test-range-events")]
+    pub struct Visit {
+        floor: usize,
+    }
+
+    let mut conn = client()?.get_connection()?;
+    redis::cmd("DEL")
+        .arg("test-range-events")
+        .query::<()>(&mut conn)?;
+    let mut ids = Vec::new();
+    for floor in 1..=5 {
+        let id = VisitManager::publish(&Visit { floor }, &mut conn)?;
+        ids.push(id.parse::<StreamEntryId>()?);
+    }
+
+    let floors = |messages: Vec<TypedMessage<Visit>>| -> RedisResult<Vec<usize>> {
+        messages
+            .into_iter()
+            .map(|message| message.into_data().map(|visit| visit.floor))
+            .collect()
+    };
+
+    assert_eq!(
+        floors(VisitManager::range_all(&mut conn)?)?,
+        [1, 2, 3, 4, 5]
+    );
+
+    let range = StreamRange::range().after(ids[0]).before(ids[4]).count(2);
+    assert_eq!(
+        floors(VisitManager::range_with(&range, &mut conn)?)?,
+        [2, 3]
+    );
+
+    let range = StreamRange::rev_range().max(ids[3]).count(2);
+    assert_eq!(
+        floors(VisitManager::range_with(&range, &mut conn)?)?,
+        [4, 3]
+    );
+
+    let pages = VisitManager::paginate(StreamRange::range(), 2, &mut conn)
+        .collect::<RedisResult<Vec<_>>>()?;
+    assert_eq!(floors(pages)?, [1, 2, 3, 4, 5]);
+
+    let pages = VisitManager::paginate(StreamRange::rev_range().after(ids[1]), 2, &mut conn)
+        .collect::<RedisResult<Vec<_>>>()?;
+    assert_eq!(floors(pages)?, [5, 4, 3]);
+
+    Ok(())
+}
@@ -1,8 +1,12 @@
 mod attr;
 mod default;
 mod name;
+mod pk_strategy;
 mod rename;
+mod soft_delete;
 mod style;
 mod symbol;
 
-pub use {attr::*, default::*, name::*, rename::*, style::*, symbol::*};
+pub use {
+    attr::*, default::*, name::*, pk_strategy::*, rename::*, soft_delete::*, style::*, symbol::*,
+};
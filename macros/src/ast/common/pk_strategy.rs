@@ -0,0 +1,74 @@
+use crate::{
+    ast::{symbols::PK_STRATEGY, Ctx, Field},
+    ext::{LitExt, TypeExt},
+};
+use proc_macro2::TokenStream;
+use quote::quote;
+
+/// Represents how primary keys are generated when unset.
+pub enum PkStrategy {
+    /// Generate a ULID string (default).
+    Ulid,
+    /// Generate a random UUID.
+    UuidV4,
+    /// Generate a time ordered UUID.
+    UuidV7,
+    /// INCR a `{prefix}:__seq` key.
+    Counter,
+    /// Call given function returning the primary key.
+    Path(syn::ExprPath),
+}
+
+impl PkStrategy {
+    pub fn from_lit(ctx: &Ctx, lit: &syn::Lit) -> Result<Self, ()> {
+        let value = lit.to_lit_str(ctx, PK_STRATEGY)?.value();
+        Ok(match value.as_str() {
+            "uuid_v4" | "uuid_v7" if cfg!(not(feature = "uuid")) => {
+                let msg = format!("pk_strategy {value} requires the `uuid` feature of redis-om");
+                ctx.error_spanned_by(lit, msg);
+                return Err(());
+            }
+            "ulid" => Self::Ulid,
+            "uuid_v4" => Self::UuidV4,
+            "uuid_v7" => Self::UuidV7,
+            "counter" => Self::Counter,
+            _ => Self::Path(lit.to_expr_path(ctx, PK_STRATEGY)?),
+        })
+    }
+
+    /// Get default strategy of primary key `field` without `pk_strategy`, `None` for ULID
+    /// strings. Errors for types no default strategy generates.
+    pub fn from_pk_field(ctx: &Ctx, field: &Field) -> Result<Option<Self>, ()> {
+        let ty = field.ty;
+        if ty.is_ident("String") {
+            return Ok(None);
+        }
+        if ty.is_numeric_type() {
+            return Ok(Some(Self::Counter));
+        }
+        if ty.is_ident("Uuid") && cfg!(feature = "uuid") {
+            return Ok(Some(Self::UuidV7));
+        }
+
+        let msg = if ty.is_ident("Uuid") {
+            "Uuid primary keys require the `uuid` feature of redis-om, or a pk_strategy"
+        } else {
+            "primary keys other than String, integer or Uuid require a pk_strategy"
+        };
+        ctx.error_spanned_by(field.original, msg);
+        Err(())
+    }
+
+    /// Get `redis_om::PkStrategy` expression.
+    pub fn to_tokens(&self) -> TokenStream {
+        match self {
+            Self::Ulid => quote!(::redis_om::PkStrategy::Ulid),
+            Self::UuidV4 => quote!(::redis_om::PkStrategy::UuidV4),
+            Self::UuidV7 => quote!(::redis_om::PkStrategy::UuidV7),
+            Self::Counter => quote!(::redis_om::PkStrategy::Counter),
+            Self::Path(path) => quote! {
+                ::redis_om::PkStrategy::Custom(|| ::redis_om::PrimaryKey::to_pk_string(&#path()))
+            },
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use crate::{
+    ast::{symbols::SOFT_DELETE, Ctx},
+    ext::LitExt,
+};
+use proc_macro2::TokenStream;
+use quote::quote;
+
+/// Represents how records are soft deleted.
+pub enum SoftDelete {
+    /// Set `__deleted_at` keeping the key, from `#[redis(soft_delete)]`.
+    InPlace,
+    /// Set `__deleted_at` and move the key, from `#[redis(soft_delete = "move")]`.
+    Move,
+}
+
+impl SoftDelete {
+    pub fn from_lit(ctx: &Ctx, lit: &syn::Lit) -> Result<Self, ()> {
+        let s = lit.to_lit_str(ctx, SOFT_DELETE)?;
+        match s.value().as_str() {
+            "in_place" => Ok(Self::InPlace),
+            "move" => Ok(Self::Move),
+            _ => {
+                ctx.error_spanned_by(s, "expected soft_delete to be \"in_place\" or \"move\"");
+                Err(())
+            }
+        }
+    }
+
+    /// Get `redis_om::SoftDelete` expression.
+    pub fn to_tokens(&self) -> TokenStream {
+        match self {
+            Self::InPlace => quote!(::redis_om::SoftDelete::InPlace),
+            Self::Move => quote!(::redis_om::SoftDelete::Move),
+        }
+    }
+}
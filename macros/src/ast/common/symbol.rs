@@ -12,6 +12,15 @@ pub mod symbols {
     pub const DEFUALT: Symbol = Symbol("default");
     pub const PRIMARY_KEY: Symbol = Symbol("primary_key");
     pub const PREFIX_KEY: Symbol = Symbol("prefix_key");
+    pub const HASH_TAG: Symbol = Symbol("hash_tag");
+    pub const TTL: Symbol = Symbol("ttl");
+    pub const PK_STRATEGY: Symbol = Symbol("pk_strategy");
+    pub const SOFT_DELETE: Symbol = Symbol("soft_delete");
+    pub const AUDIT_STREAM: Symbol = Symbol("audit_stream");
+    pub const SCHEMA_VERSION: Symbol = Symbol("schema_version");
+    pub const MIGRATIONS: Symbol = Symbol("migrations");
+    pub const MANAGER: Symbol = Symbol("manager");
+    pub const MAXLEN: Symbol = Symbol("maxlen");
     pub const KEY: Symbol = Symbol("key");
     pub const MODEL_TYPE: Symbol = Symbol("model_type");
     pub const INDEX: Symbol = Symbol("index");
@@ -21,6 +30,7 @@ pub mod symbols {
     pub const RENAME_ALL: Symbol = Symbol("rename_all");
     pub const ALIAS: Symbol = Symbol("alias");
     pub const FLATTEN: Symbol = Symbol("flatten");
+    pub const WITH: Symbol = Symbol("with");
     pub const SKIP: Symbol = Symbol("skip_serializing");
     pub const SKIP_SERIALIZING: Symbol = Symbol("skip_serializing");
     pub const SKIP_DESERIALIZING: Symbol = Symbol("skip_deserializing");
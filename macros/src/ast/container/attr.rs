@@ -14,6 +14,22 @@ pub struct ContainerAttr {
     pub prefix_key: String,
     /// Which key is considered primary key
     pub primary_key: String,
+    /// Static redis cluster hash tag wrapped in `{...}` within keys
+    pub hash_tag: Option<String>,
+    /// Time to live in milliseconds applied on every save
+    pub ttl: Option<u64>,
+    /// How primary keys are generated when unset
+    pub pk_strategy: Option<PkStrategy>,
+    /// How records are soft deleted, hard deleted when unset
+    pub soft_delete: Option<SoftDelete>,
+    /// Stream key audited changes are added to
+    pub audit_stream: Option<String>,
+    /// Schema version records are saved with
+    pub schema_version: Option<u32>,
+    /// Function returning migrations of records of older schema versions
+    pub migrations: Option<syn::ExprPath>,
+    /// Approximate length streams are trimmed to on publish
+    pub maxlen: Option<usize>,
     /// Default alternative for missing fields
     pub default: Default,
     /// Rename all rules
@@ -29,6 +45,14 @@ impl ContainerAttr {
         let mut default: Attr<Default> = Attr::new(ctx, DEFUALT);
         let mut primary_key: Attr<String> = Attr::new(ctx, PRIMARY_KEY);
         let mut prefix_key: Attr<String> = Attr::new(ctx, PREFIX_KEY);
+        let mut hash_tag: Attr<String> = Attr::new(ctx, HASH_TAG);
+        let mut ttl: Attr<u64> = Attr::new(ctx, TTL);
+        let mut pk_strategy: Attr<PkStrategy> = Attr::new(ctx, PK_STRATEGY);
+        let mut soft_delete: Attr<SoftDelete> = Attr::new(ctx, SOFT_DELETE);
+        let mut audit_stream: Attr<String> = Attr::new(ctx, AUDIT_STREAM);
+        let mut schema_version: Attr<u32> = Attr::new(ctx, SCHEMA_VERSION);
+        let mut migrations: Attr<syn::ExprPath> = Attr::new(ctx, MIGRATIONS);
+        let mut maxlen: Attr<usize> = Attr::new(ctx, MAXLEN);
         let mut model_type: Attr<Derive> = Attr::new(ctx, MODEL_TYPE);
         let mut rename_all_ser_rule = Attr::new(ctx, RENAME_ALL);
         let mut rename_all_de_rule = Attr::new(ctx, RENAME_ALL);
@@ -105,6 +129,74 @@ impl ContainerAttr {
                     prefix_key.set_opt(&nv.path, nv.lit.to_string());
                 }
 
+                // Parse `#[redis(hash_tag = "...")]`
+                Meta(NameValue(nv)) if hash_tag.eq(&nv.path) => {
+                    if let Ok(s) = nv.lit.to_lit_str(ctx, HASH_TAG) {
+                        let tag = s.value();
+                        if tag.is_empty() || tag.contains(['{', '}']) {
+                            let msg = "hash_tag must be non empty and not contain `{` or `}`";
+                            ctx.error_spanned_by(s, msg);
+                        } else {
+                            hash_tag.set(&nv.path, tag);
+                        }
+                    }
+                }
+
+                // Parse `#[redis(ttl = "...")]`
+                Meta(NameValue(nv)) if ttl.eq(&nv.path) => {
+                    if let Ok(s) = nv.lit.to_lit_str(ctx, TTL) {
+                        match crate::util::duration::parse_millis(&s.value()) {
+                            Ok(millis) => ttl.set(&nv.path, millis),
+                            Err(err) => ctx.error_spanned_by(s, err),
+                        }
+                    }
+                }
+
+                // Parse `#[redis(pk_strategy = "...")]`
+                Meta(NameValue(nv)) if pk_strategy.eq(&nv.path) => {
+                    pk_strategy.set_opt(&nv.path, PkStrategy::from_lit(ctx, &nv.lit).ok());
+                }
+
+                // Parse `#[redis(soft_delete)]`
+                Meta(Path(word)) if soft_delete.eq(word) => soft_delete.set(word, SoftDelete::InPlace),
+
+                // Parse `#[redis(soft_delete = "...")]`
+                Meta(NameValue(nv)) if soft_delete.eq(&nv.path) => {
+                    soft_delete.set_opt(&nv.path, SoftDelete::from_lit(ctx, &nv.lit).ok());
+                }
+
+                // Parse `#[redis(audit_stream = "...")]`
+                Meta(NameValue(nv)) if audit_stream.eq(&nv.path) => {
+                    if let Ok(s) = nv.lit.to_lit_str(ctx, AUDIT_STREAM) {
+                        if s.value().is_empty() {
+                            ctx.error_spanned_by(s, "audit_stream must be non empty");
+                        } else {
+                            audit_stream.set(&nv.path, s.value());
+                        }
+                    }
+                }
+
+                // Parse `#[redis(schema_version = N)]`
+                Meta(NameValue(nv)) if schema_version.eq(&nv.path) => match &nv.lit {
+                    syn::Lit::Int(int) if matches!(int.base10_parse::<u32>(), Ok(v) if v > 0) => {
+                        schema_version.set_opt(&nv.path, int.base10_parse().ok());
+                    }
+                    lit => ctx.error_spanned_by(lit, "expected schema_version to be a positive integer"),
+                },
+
+                // Parse `#[redis(migrations = "...")]`
+                Meta(NameValue(nv)) if migrations.eq(&nv.path) => {
+                    migrations.set_opt(&nv.path, nv.lit.to_expr_path(ctx, MIGRATIONS).ok());
+                }
+
+                // Parse `#[redis(maxlen = N)]`
+                Meta(NameValue(nv)) if maxlen.eq(&nv.path) => match &nv.lit {
+                    syn::Lit::Int(int) if int.base10_parse::<usize>().is_ok() => {
+                        maxlen.set_opt(&nv.path, int.base10_parse().ok());
+                    }
+                    lit => ctx.error_spanned_by(lit, "expected maxlen to be an integer"),
+                },
+
                 // Parse `#[redis(stream_key = "...")]`
                 Meta(NameValue(nv)) if &nv.path == KEY => {
                     prefix_key.set_opt(&nv.path, nv.lit.to_string());
@@ -174,6 +266,14 @@ impl ContainerAttr {
         Self {
             primary_key: primary_key.get().unwrap_or_else(|| "id".into()),
             prefix_key: prefix_key.get().unwrap_or_else(|| input.ident.to_string()),
+            hash_tag: hash_tag.get(),
+            ttl: ttl.get(),
+            pk_strategy: pk_strategy.get(),
+            soft_delete: soft_delete.get(),
+            audit_stream: audit_stream.get(),
+            schema_version: schema_version.get(),
+            migrations: migrations.get(),
+            maxlen: maxlen.get(),
             default: default.get().unwrap_or(Default::None),
             has_flatten: false,
             model_type: model_type.get().unwrap_or(Derive::HashModel),
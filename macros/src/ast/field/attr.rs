@@ -15,6 +15,8 @@ pub struct FieldAttr {
     pub default: Default,
     /// Whether the key should be considered a primary key
     pub primary_key: bool,
+    /// Whether the primary key should be wrapped in `{...}` as redis cluster hash tag
+    pub hash_tag: bool,
     /// Whether the key should be indexed
     pub index: bool,
     /// Whether the key should be sortable
@@ -23,6 +25,10 @@ pub struct FieldAttr {
     pub fts: bool,
     pub skip_deserializing: bool,
     pub skip_serializing: bool,
+    /// Module providing `to_redis_args` and `from_redis_value` for the field
+    pub with: Option<syn::ExprPath>,
+    /// Time to live in milliseconds of the hash field, applied on every save
+    pub ttl: Option<u64>,
     flatten: bool,
 }
 
@@ -45,10 +51,13 @@ impl FieldAttr {
         let mut de_name = Attr::new(ctx, RENAME);
         let mut de_aliases = VecAttr::new(ctx, RENAME);
         let mut flatten = BoolAttr::new(ctx, FLATTEN);
+        let mut with = Attr::new(ctx, WITH);
+        let mut ttl = Attr::new(ctx, TTL);
         let mut skip_deserializing = BoolAttr::new(ctx, SKIP_DESERIALIZING);
         let mut skip_serializing = BoolAttr::new(ctx, SKIP_SERIALIZING);
 
         let mut primary_key: BoolAttr = BoolAttr::new(ctx, PRIMARY_KEY);
+        let mut hash_tag: BoolAttr = BoolAttr::new(ctx, HASH_TAG);
         let mut index: BoolAttr = BoolAttr::new(ctx, INDEX);
         let mut sortable: BoolAttr = BoolAttr::new(ctx, SORTABLE);
         let mut fts: BoolAttr = BoolAttr::new(ctx, FULL_TEXT_SEARCH);
@@ -119,6 +128,13 @@ impl FieldAttr {
                     ctx.error_spanned_by(&nv.path, msg);
                 }
 
+                // Parse `#[redis(hash_tag)]`
+                Meta(Path(key)) if hash_tag.eq(&key) => hash_tag.set_true(key),
+                Meta(NameValue(nv)) if hash_tag.eq(&nv.path) => {
+                    let msg = "unexpected value for hash_tag, use #[redis(hash_tag)] on primary key or #[redis(hash_tag = \"...\")] on struct";
+                    ctx.error_spanned_by(&nv.path, msg);
+                }
+
                 // Parse `#[redis(index)]`
                 Meta(Path(key)) if index.eq(&key) => index.set_true(key),
                 Meta(NameValue(nv)) if index.eq(&nv.path) => {
@@ -141,6 +157,23 @@ impl FieldAttr {
                     ctx.error_spanned_by(&nv.path, msg);
                 }
 
+                // Parse `#[redis(with = "...")]`
+                Meta(NameValue(m)) if m.path == WITH => {
+                    if let Ok(path) = m.lit.to_expr_path(ctx, WITH) {
+                        with.set(&m.path, path);
+                    }
+                }
+
+                // Parse `#[redis(ttl = "...")]`
+                Meta(NameValue(m)) if m.path == TTL => {
+                    if let Ok(s) = m.lit.to_lit_str(ctx, TTL) {
+                        match crate::util::duration::parse_millis(&s.value()) {
+                            Ok(millis) => ttl.set(&m.path, millis),
+                            Err(err) => ctx.error_spanned_by(s, err),
+                        }
+                    }
+                }
+
                 // Parse `#[redis(flatten)]`
                 Meta(Path(word)) if word == FLATTEN => {
                     flatten.set_true(word);
@@ -167,10 +200,13 @@ impl FieldAttr {
             name: Name::from_attrs(ident, ser_name, de_name, Some(de_aliases)),
             default: default.get().unwrap_or(Default::None),
             primary_key: primary_key.get(),
+            hash_tag: hash_tag.get(),
             index: index.get(),
             sortable: sortable.get(),
             fts: fts.get(),
             flatten: flatten.get(),
+            with: with.get(),
+            ttl: ttl.get(),
             skip_serializing: skip_serializing.get(),
             skip_deserializing: skip_deserializing.get(),
         }
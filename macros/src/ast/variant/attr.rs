@@ -14,6 +14,7 @@ pub struct VariantAttr {
     pub skip_deserializing: bool,
     pub skip_serializing: bool,
     pub rename_all_rules: RenameAllRules,
+    pub manager: Option<syn::Path>,
 }
 
 impl VariantAttr {
@@ -27,6 +28,7 @@ impl VariantAttr {
         let mut skip_serializing = BoolAttr::new(ctx, SKIP_SERIALIZING);
         let mut rename_all_ser_rule = Attr::new(ctx, RENAME_ALL);
         let mut rename_all_de_rule = Attr::new(ctx, RENAME_ALL);
+        let mut manager: Attr<syn::Path> = Attr::new(ctx, MANAGER);
 
         variant
             .attrs
@@ -87,6 +89,11 @@ impl VariantAttr {
                     }
                 }
 
+                // Parse `#[redis(manager = "RoomServiceEventManager")]`
+                Meta(NameValue(m)) if m.path == MANAGER => {
+                    manager.set_opt(&m.path, m.lit.to_type(ctx, MANAGER).ok());
+                }
+
                 // Parse `#[redis(skip)]`
                 Meta(Path(word)) if word == SKIP => {
                     skip_serializing.set_true(word);
@@ -164,6 +171,7 @@ impl VariantAttr {
                 serialize: rename_all_ser_rule.get().unwrap_or(RenameRule::None),
                 deserialize: rename_all_de_rule.get().unwrap_or(RenameRule::None),
             },
+            manager: manager.get(),
         }
     }
 
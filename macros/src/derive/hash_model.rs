@@ -22,14 +22,56 @@ pub fn derive(ctx: &Ctx, cont: &Container) -> Result<TokenStream, ()> {
     crate::redis_model::derive(ctx, cont)?.to_tokens(&mut stream);
     redis_schema::derive(ctx, cont)?.to_tokens(&mut stream);
 
+    let field_ttls = field_ttls::derive(ctx, cont)?;
+    let migrations = super::migrations(ctx, cont, quote!(::redis_om::HashRecord))?;
+
     Ok(quote! {
         #stream
         #(#attributes)*
-        impl ::redis_om::HashModel for #type_name { }
+        impl ::redis_om::HashModel for #type_name {
+            #field_ttls
+            #migrations
+        }
 
     })
 }
 
+mod field_ttls {
+    use super::*;
+
+    /// Per-field time to live, requires redis 7.4 HPEXPIRE.
+    pub fn derive(ctx: &Ctx, cont: &Container) -> Result<TokenStream, ()> {
+        let Data::Struct(_, fields) = &cont.data else {
+            return Ok(quote!());
+        };
+
+        let (names, millis): (Vec<_>, Vec<_>) = fields
+            .iter()
+            .filter_map(|f| Some((f, f.attrs.ttl?)))
+            .map(|(f, millis)| (f.attrs.name.serialize_name(), millis))
+            .unzip();
+
+        if names.is_empty() {
+            return Ok(quote!());
+        }
+
+        if cfg!(not(feature = "hash-field-ttl")) {
+            let msg = "field #[redis(ttl = \"...\")] requires `hash-field-ttl` feature";
+            ctx.error_spanned_by(cont.original, msg);
+            return Err(());
+        }
+
+        Ok(quote! {
+            fn _field_ttls() -> &'static [(&'static str, ::std::time::Duration)] {
+                const FIELD_TTLS: &[(&str, ::std::time::Duration)] = &[
+                    #((#names, ::std::time::Duration::from_millis(#millis))),*
+                ];
+                FIELD_TTLS
+            }
+        })
+    }
+}
+
 mod redis_schema {
     use super::*;
 
@@ -49,18 +91,29 @@ mod redis_schema {
             return Err(());
         };
 
-        let redis_search_schema = format!(
-            "ON HASH PREFIX 1 {prefix_key} SCHEMA {}",
+        // Soft deleted records aren't indexed
+        let filter = match cont.attrs.soft_delete {
+            Some(_) => "FILTER !exists(@__deleted_at) ",
+            None => "",
+        };
+
+        let schema = format!(
+            "{filter}SCHEMA {}",
             fields
                 .iter()
                 .map(schema_for_field)
                 .collect::<Vec<_>>()
                 .join(" ")
         );
+        let redis_search_schema = format!("ON HASH PREFIX 1 {prefix_key} {schema}");
 
         Ok(quote! {
             impl ::redis_om::RedisSearchModel for #type_name {
                 const _REDIS_SEARCH_SCHEMA: &'static str = #redis_search_schema;
+
+                fn _redis_search_schema(prefix: &str) -> String {
+                    format!("ON HASH PREFIX 1 {} {}", prefix, #schema)
+                }
             }
         })
     }
@@ -9,17 +9,28 @@ use super::Derive;
 
 pub fn derive(ctx: &Ctx, cont: &Container) -> Result<TokenStream, ()> {
     let type_name = cont.ident;
+    if let Data::Struct(_, fields) = &cont.data {
+        if let Some(field) = fields.iter().find(|f| f.attrs.ttl.is_some()) {
+            let msg = "field #[redis(ttl = \"...\")] is only supported by HashModel";
+            ctx.error_spanned_by(field.original, msg);
+            return Err(());
+        }
+    }
+
     let mut stream = TokenStream::new();
     crate::redis_model::derive(ctx, cont)?.to_tokens(&mut stream);
     redis_schema::derive(ctx, cont)?.to_tokens(&mut stream);
     let mut attributes = Vec::<syn::Attribute>::new();
     #[cfg(feature = "aio")]
     attributes.push(syn::Attribute::from_token_stream(quote!(#[::redis_om::async_trait])).unwrap());
+    let migrations = super::migrations(ctx, cont, quote!(::redis_om::JsonRecord))?;
 
     Ok(quote! {
         #stream
         #(#attributes)*
-        impl ::redis_om::JsonModel for #type_name { }
+        impl ::redis_om::JsonModel for #type_name {
+            #migrations
+        }
     })
 }
 
@@ -42,8 +53,14 @@ mod redis_schema {
             return Err(());
         };
 
-        let redis_search_schema = format!(
-            "ON JSON PREFIX 1 {prefix_key} SCHEMA {}",
+        // Soft deleted records aren't indexed
+        let filter = match cont.attrs.soft_delete {
+            Some(_) => "FILTER !exists(@__deleted_at) ",
+            None => "",
+        };
+
+        let schema = format!(
+            "{filter}SCHEMA {}",
             fields
                 .iter()
                 .map(schema_for_field)
@@ -51,10 +68,15 @@ mod redis_schema {
                 .join(" ")
                 .trim()
         );
+        let redis_search_schema = format!("ON JSON PREFIX 1 {prefix_key} {schema}");
 
         Ok(quote! {
             impl ::redis_om::RedisSearchModel for #type_name {
                 const _REDIS_SEARCH_SCHEMA: &'static str = #redis_search_schema;
+
+                fn _redis_search_schema(prefix: &str) -> String {
+                    format!("ON JSON PREFIX 1 {} {}", prefix, #schema)
+                }
             }
         })
     }
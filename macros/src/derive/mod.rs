@@ -6,6 +6,7 @@ pub mod get_set;
 pub mod hash_model;
 #[cfg(feature = "json")]
 pub mod json_model;
+pub mod multi_stream_model;
 pub mod redis_model;
 pub mod stream_model;
 pub mod value;
@@ -29,3 +30,25 @@ impl Derive {
         Ok(res)
     }
 }
+
+/// Generate `migrations()` of model trait from `#[redis(migrations = "...")]`, migrating `record` type.
+pub fn migrations(
+    ctx: &Ctx,
+    cont: &crate::ast::Container,
+    record: proc_macro2::TokenStream,
+) -> Result<proc_macro2::TokenStream, ()> {
+    let Some(path) = cont.attrs.migrations.as_ref() else {
+        return Ok(Default::default());
+    };
+
+    if cont.attrs.schema_version.is_none() {
+        ctx.error_spanned_by(path, "migrations requires #[redis(schema_version = N)]");
+        return Err(());
+    }
+
+    Ok(quote::quote! {
+        fn migrations() -> ::redis_om::Migrations<#record> {
+            #path()
+        }
+    })
+}
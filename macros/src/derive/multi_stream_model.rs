@@ -0,0 +1,83 @@
+use crate::ast::{Container, Ctx, Data, Style, Variant};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Path, Type};
+
+pub fn derive(ctx: &Ctx, cont: &Container) -> Result<TokenStream, ()> {
+    let Data::Enum(variants) = &cont.data else {
+        ctx.error_spanned_by(
+            cont.original,
+            "MultiStreamModel is only supported for enums",
+        );
+        return Err(());
+    };
+
+    let type_name = cont.ident;
+    let vis = &cont.original.vis;
+    let manager_type = format_ident!("{}Manager", type_name);
+
+    let (idents, managers): (Vec<_>, Vec<_>) = variants
+        .iter()
+        .map(|v| Ok((&v.ident, variant_manager(ctx, v)?)))
+        .collect::<Result<Vec<_>, ()>>()?
+        .into_iter()
+        .unzip();
+    let indices = 0..idents.len();
+
+    Ok(quote! {
+        impl ::redis_om::MultiStreamModel for #type_name {
+            fn stream_keys() -> Vec<&'static str> {
+                vec![#(<#managers as ::redis_om::StreamModel>::stream_key()),*]
+            }
+
+            fn decoder(
+                index: usize,
+            ) -> Option<fn(&::redis_om::redis::Value) -> ::redis_om::RedisResult<Self>> {
+                let decoder: fn(&::redis_om::redis::Value) -> ::redis_om::RedisResult<Self> =
+                    match index {
+                        #(#indices => |value: &::redis_om::redis::Value| {
+                            <<#managers as ::redis_om::StreamModel>::Data as ::redis_om::FromRedisValue>::from_redis_value(value)
+                                .map(Self::#idents)
+                        },)*
+                        _ => return None,
+                    };
+                Some(decoder)
+            }
+        }
+
+        #[doc = "Manager reading every stream of the enum in a single XREADGROUP"]
+        #vis type #manager_type = ::redis_om::MultiStreamManager<#type_name>;
+    })
+}
+
+/// Get stream model of variant, set with `#[redis(manager = "...")]` or defaulting to
+/// `{Data}Manager` generated by `#[derive(StreamModel)]`.
+fn variant_manager(ctx: &Ctx, variant: &Variant) -> Result<Path, ()> {
+    if !matches!(variant.style, Style::Newtype) {
+        let msg = "MultiStreamModel variants must hold the data of a stream, e.g. `Event(Event)`";
+        ctx.error_spanned_by(variant.inner, msg);
+        return Err(());
+    }
+
+    if let Some(manager) = &variant.attrs.manager {
+        return Ok(manager.clone());
+    }
+
+    let msg = "cannot infer stream model, set it with #[redis(manager = \"...\")]";
+    match variant.fields[0].ty {
+        Type::Path(ty) if ty.qself.is_none() => {
+            let mut path = ty.path.clone();
+            let Some(last) = path.segments.last_mut() else {
+                ctx.error_spanned_by(ty, msg);
+                return Err(());
+            };
+            last.ident = format_ident!("{}Manager", last.ident);
+            last.arguments = syn::PathArguments::None;
+            Ok(path)
+        }
+        ty => {
+            ctx.error_spanned_by(ty, msg);
+            Err(())
+        }
+    }
+}
@@ -1,6 +1,6 @@
-use crate::ast::{Container, Ctx, Field, Style};
+use crate::ast::{Container, Ctx, Field, PkStrategy, Style};
 use proc_macro2::{Ident, TokenStream};
-use quote::quote;
+use quote::{format_ident, quote};
 use syn::AttrStyle;
 
 pub(super) fn derive(
@@ -15,30 +15,129 @@ pub(super) fn derive(
     // TODO: Find away to ignore types already implements default trait.
     match style {
         Style::Struct => {
-            let pk_field = fields.iter().find(|f| f.attrs.primary_key);
-            let pk_ident = pk_field
-                .map(|v| v.ident.unwrap().to_owned())
-                .unwrap_or_else(|| Ident::new("id", cont.ident.span()));
+            let mut pk_idents = fields
+                .iter()
+                .filter(|f| f.attrs.primary_key)
+                .map(|f| f.ident.unwrap().to_owned())
+                .collect::<Vec<_>>();
+            if pk_idents.is_empty() {
+                pk_idents.push(Ident::new("id", cont.ident.span()));
+            }
 
-            if fields.iter().find(|f| f.ident == Some(&pk_ident)).is_none() {
+            let is_pk = |field: &Field| pk_idents.iter().any(|pk| field.ident == Some(pk));
+
+            if pk_idents
+                .iter()
+                .any(|pk| fields.iter().all(|f| f.ident != Some(pk)))
+            {
                 let msg = format!("A primary field doesn't exists, either add `id` field or annotate a field `primary_key`");
                 ctx.error_spanned_by(cont.original, msg);
                 return Err(());
             };
 
+            if pk_idents.len() > 1 && cont.attrs.pk_strategy.is_some() {
+                let msg = "pk_strategy can't be used with composite primary keys";
+                ctx.error_spanned_by(cont.original, msg);
+                return Err(());
+            }
+
+            let tagged_fields = fields.iter().filter(|f| f.attrs.hash_tag);
+            for field in tagged_fields.clone() {
+                if !is_pk(field) {
+                    let msg = "#[redis(hash_tag)] can only be used on the primary key field";
+                    ctx.error_spanned_by(field.original, msg);
+                    return Err(());
+                }
+            }
+
+            let hash_tag = match (&cont.attrs.hash_tag, tagged_fields.count() > 0) {
+                (Some(_), true) => {
+                    let msg = "hash_tag can't be set on both struct and primary key field";
+                    ctx.error_spanned_by(cont.original, msg);
+                    return Err(());
+                }
+                (Some(tag), false) => quote!(::redis_om::HashTag::Static(#tag)),
+                (None, true) => quote!(::redis_om::HashTag::PrimaryKey),
+                (None, false) => quote!(::redis_om::HashTag::None),
+            };
+
+            let primary_key = match pk_idents.as_slice() {
+                [pk_ident] => single_primary_key(pk_ident),
+                pk_idents => composite_primary_key(pk_idents),
+            };
+
+            let ttl = cont.attrs.ttl.map(|millis| {
+                quote! {
+                    fn _ttl() -> Option<::std::time::Duration> {
+                        Some(::std::time::Duration::from_millis(#millis))
+                    }
+                }
+            });
+
+            let pk_field = fields.iter().find(|f| is_pk(f));
+            let default_pk_strategy = match (&cont.attrs.pk_strategy, pk_field) {
+                (None, Some(field)) if pk_idents.len() == 1 => {
+                    PkStrategy::from_pk_field(ctx, field)?
+                }
+                _ => None,
+            };
+
+            let pk_strategy = cont
+                .attrs
+                .pk_strategy
+                .as_ref()
+                .or(default_pk_strategy.as_ref())
+                .map(|strategy| {
+                    let strategy = strategy.to_tokens();
+                    quote! {
+                        fn _pk_strategy() -> ::redis_om::PkStrategy {
+                            #strategy
+                        }
+                    }
+                });
+
+            let soft_delete = cont.attrs.soft_delete.as_ref().map(|soft_delete| {
+                let soft_delete = soft_delete.to_tokens();
+                quote! {
+                    fn _soft_delete() -> Option<::redis_om::SoftDelete> {
+                        Some(#soft_delete)
+                    }
+                }
+            });
+
+            let audit_stream = cont.attrs.audit_stream.as_ref().map(|stream| {
+                quote! {
+                    fn _audit_stream() -> Option<&'static str> {
+                        Some(#stream)
+                    }
+                }
+            });
+
+            let schema_version = cont.attrs.schema_version.map(|version| {
+                quote! {
+                    fn _schema_version() -> Option<u32> {
+                        Some(#version)
+                    }
+                }
+            });
+
             Ok(quote! {
                 impl ::redis_om::RedisModel for #type_name {
+                    #ttl
+                    #pk_strategy
+                    #soft_delete
+                    #audit_stream
+                    #schema_version
+
                     fn _prefix_key() -> &'static str {
                         #prefix_key
                     }
 
-                    fn _get_pk(&self) -> &str {
-                        &self.#pk_ident
+                    fn _hash_tag() -> ::redis_om::HashTag {
+                        #hash_tag
                     }
 
-                    fn _set_pk(&mut self, pk: String) {
-                        self.#pk_ident = pk;
-                    }
+                    #primary_key
                 }
             })
         }
@@ -49,3 +148,59 @@ pub(super) fn derive(
         }
     }
 }
+
+fn single_primary_key(pk_ident: &Ident) -> TokenStream {
+    quote! {
+        fn _get_pk(&self) -> String {
+            ::redis_om::PrimaryKey::to_pk_string(&self.#pk_ident)
+        }
+
+        fn _has_pk(&self) -> bool {
+            !::redis_om::PrimaryKey::is_unset(&self.#pk_ident)
+        }
+
+        fn _set_pk(&mut self, pk: &str) -> ::redis_om::RedisResult<()> {
+            self.#pk_ident = ::redis_om::PrimaryKey::from_pk_str(pk)?;
+            Ok(())
+        }
+    }
+}
+
+/// Primary key of format "{part}:{part}" from multiple `#[redis(primary_key)]` fields.
+fn composite_primary_key(pk_idents: &[Ident]) -> TokenStream {
+    let count = pk_idents.len();
+    let parts = (0..count)
+        .map(|idx| format_ident!("__part{idx}"))
+        .collect::<Vec<_>>();
+
+    quote! {
+        fn _get_pk(&self) -> String {
+            ::redis_om::join_pk_parts(&[#(::redis_om::PrimaryKey::to_pk_string(&self.#pk_idents)),*])
+        }
+
+        fn _has_pk(&self) -> bool {
+            #(!::redis_om::PrimaryKey::is_unset(&self.#pk_idents))&&*
+        }
+
+        fn _set_pk(&mut self, pk: &str) -> ::redis_om::RedisResult<()> {
+            let [#(#parts),*] = ::redis_om::split_pk_parts::<#count>(pk)?;
+            #(self.#pk_idents = ::redis_om::PrimaryKey::from_pk_str(#parts)?;)*
+            Ok(())
+        }
+
+        fn _ensure_pk(
+            &mut self,
+            _: &::redis_om::Namespace,
+        ) -> ::redis_om::RedisResult<Option<::redis_om::redis::Cmd>> {
+            if self._has_pk() {
+                ::redis_om::ensure_pk_parts(&[#(::redis_om::PrimaryKey::to_pk_string(&self.#pk_idents)),*])?;
+                return Ok(None);
+            }
+
+            Err(::redis_om::RedisError::from((
+                ::redis_om::redis::ErrorKind::ClientError,
+                "all parts of composite primary key must be set",
+            )))
+        }
+    }
+}
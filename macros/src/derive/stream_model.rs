@@ -13,6 +13,13 @@ pub fn derive(ctx: &Ctx, cont: &Container) -> Result<TokenStream, ()> {
 
     let mut stream = TokenStream::new();
     let consumer_type = format_ident!("{}Manager", type_name);
+    let maxlen = cont.attrs.maxlen.map(|maxlen| {
+        quote! {
+            fn maxlen() -> Option<::redis_om::redis::streams::StreamMaxlen> {
+                Some(::redis_om::redis::streams::StreamMaxlen::Approx(#maxlen))
+            }
+        }
+    });
 
     crate::value::derive(ctx, cont)?.to_tokens(&mut stream);
 
@@ -54,6 +61,8 @@ pub fn derive(ctx: &Ctx, cont: &Container) -> Result<TokenStream, ()> {
             fn stream_key() -> &'static str {
                 #prefix_key
             }
+
+            #maxlen
         }
 
     })
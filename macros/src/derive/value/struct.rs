@@ -1,4 +1,5 @@
 use crate::ast::{Container, Ctx, Field, FieldAttr, Style};
+use crate::ext::TypeExt;
 use crate::util::{parse, string};
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
@@ -34,9 +35,16 @@ fn derive_to_redis_args(
             .map(|f| {
                 let ident = f.ident.unwrap();
                 let key = f.attrs.name.serialize_name();
+                let to_redis_args = match &f.attrs.with {
+                    Some(with) => quote!(#with::to_redis_args(&self.#ident)),
+                    None if f.ty.is_map_collection() => {
+                        return quote!(::redis_om::map_field::write_map(#key, &self.#ident, out););
+                    }
+                    None => quote!(ToRedisArgs::to_redis_args(&self.#ident)),
+                };
 
                 quote! {
-                    match ToRedisArgs::to_redis_args(&self.#ident) {
+                    match #to_redis_args {
                         redis_args if redis_args.len() == 1 => {
                             out.write_arg_fmt(#key);
                             out.write_arg(&redis_args[0]);
@@ -89,11 +97,29 @@ fn derive_from_redis(
                     let keys_ident = format_ident!("{}_POSSIBLE_KEYS", ident_str.to_uppercase());
                     let possible_keys = f.attrs.name.deserialize_aliases();
                     let possible_keys_len = possible_keys.len();
+                    let from_redis_value = match &f.attrs.with {
+                        Some(with) => quote!(#with::from_redis_value),
+                        None => quote!(from_redis_value),
+                    };
+
+                    if f.attrs.with.is_none() && f.ty.is_map_collection() {
+                        let def = quote! {
+                            const #keys_ident: [&str; #possible_keys_len] = [#(#possible_keys),*];
+                            let #ident = from_redis_value(
+                                &#keys_ident
+                                    .into_iter()
+                                    .find_map(|v| nested.remove(v))
+                                    .map(::redis_om::map_field::entries_to_value)
+                                    .unwrap_or_else(|| Value::Bulk(vec![])),
+                            )?;
+                        };
+                        return (ident, def);
+                    }
 
                     // TODO: Support default in deserialization
                     let def = quote! {
                         const #keys_ident: [&str; #possible_keys_len] = [#(#possible_keys),*];
-                        let #ident = from_redis_value(
+                        let #ident = #from_redis_value(
                           #keys_ident
                             .into_iter()
                             .find(|v| fm.contains_key(*v))
@@ -105,6 +131,20 @@ fn derive_from_redis(
                 })
                 .unzip();
 
+            // Map fields keep "{field}.{key}" suffixes, collected separately from list fields
+            let has_maps = fields
+                .iter()
+                .filter(|f| !f.attrs.skip_deserializing)
+                .any(|f| f.attrs.with.is_none() && f.ty.is_map_collection());
+            let (nested_decl, nested_insert) = if has_maps {
+                (
+                    quote!(let mut nested = std::collections::HashMap::<String, Vec<(String, Value)>>::new();),
+                    quote!(nested.entry(key.to_owned()).or_default().push((idx.to_owned(), value.clone()));),
+                )
+            } else {
+                (quote!(), quote!())
+            };
+
             Ok(quote! {
                 impl ::redis_om::redis::FromRedisValue for #ident {
                     fn from_redis_value(v: &::redis_om::redis::Value) -> ::redis_om::redis::RedisResult<Self> {
@@ -113,6 +153,7 @@ fn derive_from_redis(
                         let Value::Bulk(bulk) = v else { return Err(#err); };
                         if bulk.len() % 2 != 0 { return Err(#err); };
                         let mut fm = std::collections::HashMap::new();
+                        #nested_decl
 
                         for chunks in bulk.chunks(2) {
                             let key: String = from_redis_value(&chunks[0])?;
@@ -121,6 +162,7 @@ fn derive_from_redis(
                                 fm.insert(key, value);
                                 continue;
                             };
+                            #nested_insert
                             let Some(Value::Bulk(vec)) = fm.get_mut(key) else {
                                 fm.insert(key.into(), Value::Bulk(vec![value]));
                                 continue;
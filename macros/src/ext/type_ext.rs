@@ -21,6 +21,8 @@ pub trait TypeExt {
 
     fn is_list_collection(&self) -> bool;
 
+    fn is_map_collection(&self) -> bool;
+
     fn is_numeric_type(&self) -> bool;
 
     fn get_inner_type(&self) -> Option<&Type>;
@@ -161,6 +163,19 @@ impl TypeExt for Type {
         false
     }
 
+    fn is_map_collection(&self) -> bool {
+        let Some(path) = self.path() else { return false };
+        let Some(last) = path.segments.last() else { return false; };
+
+        if last.ident == "HashMap" || last.ident == "BTreeMap" {
+            if let PathArguments::AngleBracketed(bracketed) = &last.arguments {
+                return bracketed.args.len() >= 2;
+            }
+        }
+
+        false
+    }
+
     fn is_numeric_type(&self) -> bool {
         let Some(path) = self.path() else {return false;};
         let Some(ident) = path.segments.last().map(|v| v.ident.to_string()) else { return false; };
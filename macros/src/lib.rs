@@ -50,6 +50,15 @@ pub fn stream_model(attr: TokenStream) -> TokenStream {
     }
 }
 
+#[proc_macro_derive(MultiStreamModel, attributes(redis))]
+pub fn multi_stream_model(attr: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(attr as DeriveInput);
+    match into_container(&input) {
+        Ok((ctx, cont)) => into_stream(multi_stream_model::derive(&ctx, &cont), ctx),
+        Err(value) => value,
+    }
+}
+
 #[proc_macro_derive(RedisTransportValue, attributes(redis))]
 pub fn redis_transport_value(attr: TokenStream) -> TokenStream {
     let input = parse_macro_input!(attr as DeriveInput);
@@ -0,0 +1,59 @@
+/// Parse durations like "500ms", "30m" or "1h30m" into milliseconds, rejecting zero durations.
+pub fn parse_millis(input: &str) -> Result<u64, String> {
+    let err =
+        || format!("invalid duration `{input}`, expected e.g. \"500ms\", \"30m\" or \"1h30m\"");
+    let mut rest = input.trim();
+    let mut total: u64 = 0;
+
+    if rest.is_empty() {
+        return Err(err());
+    }
+
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).ok_or_else(err)?;
+        let value: u64 = rest[..digits].parse().map_err(|_| err())?;
+        rest = &rest[digits..];
+
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let factor = match &rest[..unit_len] {
+            "ms" => 1,
+            "s" => 1_000,
+            "m" => 60_000,
+            "h" => 3_600_000,
+            "d" => 86_400_000,
+            _ => return Err(err()),
+        };
+        rest = &rest[unit_len..];
+
+        total = value
+            .checked_mul(factor)
+            .and_then(|v| total.checked_add(v))
+            .ok_or_else(err)?;
+    }
+
+    if total == 0 {
+        return Err(format!(
+            "invalid duration `{input}`, expected more than 0ms"
+        ));
+    }
+
+    Ok(total)
+}
+
+#[test]
+fn test_parse_millis() {
+    assert_eq!(parse_millis("500ms"), Ok(500));
+    assert_eq!(parse_millis("30s"), Ok(30_000));
+    assert_eq!(parse_millis("30m"), Ok(1_800_000));
+    assert_eq!(parse_millis("1h30m"), Ok(5_400_000));
+    assert_eq!(parse_millis("2d"), Ok(172_800_000));
+    assert!(parse_millis("").is_err());
+    assert!(parse_millis("30").is_err());
+    assert!(parse_millis("m").is_err());
+    assert!(parse_millis("5y").is_err());
+    assert!(parse_millis("0s").is_err());
+    assert!(parse_millis("0ms").is_err());
+    assert!(parse_millis("0h0m").is_err());
+}
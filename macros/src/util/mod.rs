@@ -1,3 +1,4 @@
+pub mod duration;
 pub mod parse;
 pub mod string;
 